@@ -1,9 +1,19 @@
-use crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+pub mod event_source;
+pub mod keybindings;
+pub mod mouse;
+pub mod replay;
+
+use crossterm::event::{Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+use event_source::EventSource;
 use std::io::Result;
 
-pub fn read_key() -> Result<()> {
-    event::read()?;
-    Ok(())
+pub fn read_key(events: &mut dyn EventSource) -> Result<Event> {
+    events.read()
+}
+
+// The window changed size, the screen only needs to be drawn again.
+pub fn is_resize(event: &Event) -> bool {
+    matches!(event, Event::Resize(..))
 }
 
 pub fn read_confirmation(key: &Event) -> bool {
@@ -23,94 +33,6 @@ pub fn read_confirmation(key: &Event) -> bool {
     )
 }
 
-pub fn should_move_up(key: &Event) -> bool {
-    matches!(
-        key,
-        Event::Key(KeyEvent {
-            code: KeyCode::Char('k'),
-            modifiers: KeyModifiers::NONE,
-            kind: KeyEventKind::Press,
-            ..
-        }) | Event::Key(KeyEvent {
-            code: KeyCode::Char('w'),
-            modifiers: KeyModifiers::NONE,
-            kind: KeyEventKind::Press,
-            ..
-        }) | Event::Key(KeyEvent {
-            code: KeyCode::Up,
-            modifiers: KeyModifiers::NONE,
-            kind: KeyEventKind::Press,
-            ..
-        })
-    )
-}
-
-pub fn should_move_down(key: &Event) -> bool {
-    matches!(
-        key,
-        Event::Key(KeyEvent {
-            code: KeyCode::Char('j'),
-            modifiers: KeyModifiers::NONE,
-            kind: KeyEventKind::Press,
-            ..
-        }) | Event::Key(KeyEvent {
-            code: KeyCode::Char('s'),
-            modifiers: KeyModifiers::NONE,
-            kind: KeyEventKind::Press,
-            ..
-        }) | Event::Key(KeyEvent {
-            code: KeyCode::Down,
-            modifiers: KeyModifiers::NONE,
-            kind: KeyEventKind::Press,
-            ..
-        })
-    )
-}
-
-pub fn should_move_right(key: &Event) -> bool {
-    matches!(
-        key,
-        Event::Key(KeyEvent {
-            code: KeyCode::Char('l'),
-            modifiers: KeyModifiers::NONE,
-            kind: KeyEventKind::Press,
-            ..
-        }) | Event::Key(KeyEvent {
-            code: KeyCode::Char('d'),
-            modifiers: KeyModifiers::NONE,
-            kind: KeyEventKind::Press,
-            ..
-        }) | Event::Key(KeyEvent {
-            code: KeyCode::Right,
-            modifiers: KeyModifiers::NONE,
-            kind: KeyEventKind::Press,
-            ..
-        })
-    )
-}
-
-pub fn should_move_left(key: &Event) -> bool {
-    matches!(
-        key,
-        Event::Key(KeyEvent {
-            code: KeyCode::Char('h'),
-            modifiers: KeyModifiers::NONE,
-            kind: KeyEventKind::Press,
-            ..
-        }) | Event::Key(KeyEvent {
-            code: KeyCode::Char('a'),
-            modifiers: KeyModifiers::NONE,
-            kind: KeyEventKind::Press,
-            ..
-        }) | Event::Key(KeyEvent {
-            code: KeyCode::Left,
-            modifiers: KeyModifiers::NONE,
-            kind: KeyEventKind::Press,
-            ..
-        })
-    )
-}
-
 pub fn should_force_quit(key: &Event) -> bool {
     matches!(
         key,
@@ -122,85 +44,3 @@ pub fn should_force_quit(key: &Event) -> bool {
         })
     )
 }
-
-pub fn should_quit(key: &Event) -> bool {
-    matches!(
-        key,
-        Event::Key(KeyEvent {
-            code: KeyCode::Esc,
-            modifiers: KeyModifiers::NONE,
-            kind: KeyEventKind::Press,
-            ..
-        }) | Event::Key(KeyEvent {
-            code: KeyCode::Char('q'),
-            modifiers: KeyModifiers::NONE,
-            kind: KeyEventKind::Press,
-            ..
-        })
-    )
-}
-
-pub fn should_play(key: &Event) -> bool {
-    matches!(
-        key,
-        Event::Key(KeyEvent {
-            code: KeyCode::Enter,
-            modifiers: KeyModifiers::NONE,
-            kind: KeyEventKind::Press,
-            ..
-        }) | Event::Key(KeyEvent {
-            code: KeyCode::Char('p'),
-            modifiers: KeyModifiers::NONE,
-            kind: KeyEventKind::Press,
-            ..
-        })
-    )
-}
-
-pub fn should_help(key: &Event) -> bool {
-    matches!(
-        key,
-        Event::Key(KeyEvent {
-            code: KeyCode::Char('?'),
-            modifiers: KeyModifiers::NONE,
-            kind: KeyEventKind::Press,
-            ..
-        })
-    )
-}
-
-pub fn should_increase_fps(key: &Event) -> bool {
-    matches!(
-        key,
-        Event::Key(KeyEvent {
-            code: KeyCode::Char('F'),
-            kind: KeyEventKind::Press,
-            modifiers: KeyModifiers::SHIFT,
-            ..
-        })
-    )
-}
-
-pub fn should_decrease_fps(key: &Event) -> bool {
-    matches!(
-        key,
-        Event::Key(KeyEvent {
-            code: KeyCode::Char('f'),
-            modifiers: KeyModifiers::NONE,
-            kind: KeyEventKind::Press,
-            ..
-        })
-    )
-}
-
-pub fn should_pause(key: &Event) -> bool {
-    matches!(
-        key,
-        Event::Key(KeyEvent {
-            code: KeyCode::Char('p'),
-            modifiers: KeyModifiers::NONE,
-            kind: KeyEventKind::Press,
-            ..
-        })
-    )
-}
@@ -0,0 +1,319 @@
+use super::replay::RECORDING_HEADER;
+use crossterm::event::{
+    self, Event, KeyCode, KeyEvent, KeyModifiers, MouseButton, MouseEvent, MouseEventKind,
+};
+use std::{
+    collections::VecDeque,
+    fs,
+    io::{Error, ErrorKind, Result},
+    path::Path,
+    sync::mpsc::{self, Receiver, RecvTimeoutError},
+    thread,
+    time::Duration,
+};
+
+pub trait EventSource {
+    // Blocks until an event is available.
+    fn read(&mut self) -> Result<Event>;
+    // Returns whether an event is available, waiting at most `timeout` for one.
+    fn poll(&mut self, timeout: Duration) -> Result<bool>;
+    // Size of the terminal the events were recorded on. Replays check whether a game fits with
+    // it instead of the real one, so they take the same path through the game the recording did.
+    fn screen_size(&self) -> Option<(u16, u16)> {
+        None
+    }
+}
+
+pub struct TerminalEventSource;
+
+impl EventSource for TerminalEventSource {
+    fn read(&mut self) -> Result<Event> {
+        loop {
+            let event = event::read()?;
+            if !is_ignored(&event) {
+                return Ok(event);
+            }
+        }
+    }
+
+    fn poll(&mut self, timeout: Duration) -> Result<bool> {
+        event::poll(timeout)
+    }
+}
+
+// Reads the terminal on a thread of its own and hands the events over a channel, so the game
+// loop only ever waits on the channel. The thread starts on the first read or poll, and only one
+// source should read the terminal at a time.
+#[derive(Default)]
+pub struct ThreadedEventSource {
+    receiver: Option<Receiver<Result<Event>>>,
+    // Event taken from the channel by `poll`, returned by the next `read`.
+    pending: Option<Event>,
+}
+
+impl ThreadedEventSource {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn receiver(&mut self) -> &Receiver<Result<Event>> {
+        self.receiver.get_or_insert_with(|| {
+            let (sender, receiver) = mpsc::channel();
+            thread::spawn(move || loop {
+                let event = event::read();
+                if event.as_ref().is_ok_and(is_ignored) {
+                    continue;
+                }
+                let failed = event.is_err();
+                // Stops once the source is dropped or the terminal can't be read anymore.
+                if sender.send(event).is_err() || failed {
+                    break;
+                }
+            });
+            receiver
+        })
+    }
+
+    fn disconnected() -> Error {
+        Error::new(ErrorKind::BrokenPipe, "the input thread stopped")
+    }
+}
+
+impl EventSource for ThreadedEventSource {
+    fn read(&mut self) -> Result<Event> {
+        match self.pending.take() {
+            Some(event) => Ok(event),
+            None => self.receiver().recv().map_err(|_| Self::disconnected())?,
+        }
+    }
+
+    fn poll(&mut self, timeout: Duration) -> Result<bool> {
+        if self.pending.is_some() {
+            return Ok(true);
+        }
+        match self.receiver().recv_timeout(timeout) {
+            Ok(event) => {
+                self.pending = Some(event?);
+                Ok(true)
+            }
+            Err(RecvTimeoutError::Timeout) => Ok(false),
+            Err(RecvTimeoutError::Disconnected) => Err(Self::disconnected()),
+        }
+    }
+}
+
+// Feeds a fixed sequence of events, so games can be driven without a terminal. An idle entry
+// (`None`) makes one `poll` report that nothing happened, letting timed games advance a frame.
+// Once the queue runs out a CTRL-c is produced, so every game ends cleanly.
+pub struct ScriptedEventSource {
+    events: VecDeque<Option<Event>>,
+}
+
+impl ScriptedEventSource {
+    pub fn new(events: impl IntoIterator<Item = Option<Event>>) -> Self {
+        Self {
+            events: events.into_iter().collect(),
+        }
+    }
+
+    // Reads an events file: one event per line, `idle` for an empty frame and `#` for comments.
+    pub fn from_file(path: &Path) -> Result<Self> {
+        let text = fs::read_to_string(path)?;
+        if text.lines().next().map(str::trim) == Some(RECORDING_HEADER) {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                "it is a recording, play it with --replay instead",
+            ));
+        }
+        let mut events = Vec::new();
+        for (number, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            if line == "idle" {
+                events.push(None);
+                continue;
+            }
+            match parse_event(line) {
+                Some(event) => events.push(Some(event)),
+                None => {
+                    return Err(Error::new(
+                        ErrorKind::InvalidData,
+                        format!("invalid event \"{line}\" on line {}", number + 1),
+                    ))
+                }
+            }
+        }
+        Ok(Self::new(events))
+    }
+
+    fn force_quit() -> Event {
+        Event::Key(KeyEvent::new(KeyCode::Char('c'), KeyModifiers::CONTROL))
+    }
+}
+
+impl EventSource for ScriptedEventSource {
+    fn read(&mut self) -> Result<Event> {
+        while let Some(event) = self.events.pop_front() {
+            if let Some(event) = event {
+                return Ok(event);
+            }
+        }
+        Ok(Self::force_quit())
+    }
+
+    fn poll(&mut self, _timeout: Duration) -> Result<bool> {
+        match self.events.front() {
+            Some(None) => {
+                self.events.pop_front();
+                Ok(false)
+            }
+            Some(Some(_)) | None => Ok(true),
+        }
+    }
+}
+
+// Only presses of the mouse buttons are used. Moving it, dragging, releasing and scrolling would
+// otherwise wake the games up for nothing, and count as a key on the screens that wait for any.
+fn is_ignored(event: &Event) -> bool {
+    matches!(
+        event,
+        Event::Mouse(MouseEvent { kind, .. }) if !matches!(kind, MouseEventKind::Down(_))
+    )
+}
+
+// Parses events written as `a`, `A`, `enter`, `esc`, `space`, `up`, `ctrl+c`, `resize 80 24`,
+// `click left 10 4`, ...
+pub fn parse_event(text: &str) -> Option<Event> {
+    if let Some(size) = text.strip_prefix("resize ") {
+        let (columns, rows) = size.split_once(' ')?;
+        return Some(Event::Resize(columns.parse().ok()?, rows.parse().ok()?));
+    }
+    if let Some(click) = text.strip_prefix("click ") {
+        let mut parts = click.split(' ');
+        let button = match parts.next()? {
+            "left" => MouseButton::Left,
+            "right" => MouseButton::Right,
+            "middle" => MouseButton::Middle,
+            _ => return None,
+        };
+        let column = parts.next()?.parse().ok()?;
+        let row = parts.next()?.parse().ok()?;
+        return Some(Event::Mouse(MouseEvent {
+            kind: MouseEventKind::Down(button),
+            column,
+            row,
+            modifiers: KeyModifiers::NONE,
+        }));
+    }
+    match text {
+        "focus_gained" => return Some(Event::FocusGained),
+        "focus_lost" => return Some(Event::FocusLost),
+        _ => (),
+    }
+    let mut modifiers = KeyModifiers::NONE;
+    let mut key = text;
+    while let Some((modifier, rest)) = key.split_once('+').filter(|(_, rest)| !rest.is_empty()) {
+        modifiers |= match modifier {
+            "ctrl" => KeyModifiers::CONTROL,
+            "alt" => KeyModifiers::ALT,
+            "shift" => KeyModifiers::SHIFT,
+            _ => return None,
+        };
+        key = rest;
+    }
+    let code = match key {
+        "enter" => KeyCode::Enter,
+        "esc" => KeyCode::Esc,
+        "space" => KeyCode::Char(' '),
+        "backspace" => KeyCode::Backspace,
+        "tab" => KeyCode::Tab,
+        "up" => KeyCode::Up,
+        "down" => KeyCode::Down,
+        "left" => KeyCode::Left,
+        "right" => KeyCode::Right,
+        "home" => KeyCode::Home,
+        "end" => KeyCode::End,
+        "pageup" => KeyCode::PageUp,
+        "pagedown" => KeyCode::PageDown,
+        "delete" => KeyCode::Delete,
+        "backtab" => KeyCode::BackTab,
+        "null" => KeyCode::Null,
+        _ => {
+            let mut characters = key.chars();
+            match (characters.next(), characters.next()) {
+                (Some(character), None) => {
+                    if character.is_uppercase() {
+                        modifiers |= KeyModifiers::SHIFT;
+                    }
+                    KeyCode::Char(character)
+                }
+                _ => return None,
+            }
+        }
+    };
+    Some(Event::Key(KeyEvent::new(code, modifiers)))
+}
+
+// Writes an event in the syntax `parse_event` reads. Keys without a name are written as `null`,
+// which still counts as a key press when replayed.
+pub fn format_event(event: &Event) -> String {
+    let key = match event {
+        Event::Key(key) => key,
+        Event::Resize(columns, rows) => return format!("resize {columns} {rows}"),
+        Event::FocusGained => return String::from("focus_gained"),
+        Event::FocusLost => return String::from("focus_lost"),
+        Event::Mouse(MouseEvent {
+            kind: MouseEventKind::Down(button),
+            column,
+            row,
+            ..
+        }) => {
+            let button = match button {
+                MouseButton::Left => "left",
+                MouseButton::Right => "right",
+                MouseButton::Middle => "middle",
+            };
+            return format!("click {button} {column} {row}");
+        }
+        _ => return String::from("null"),
+    };
+    let mut modifiers = key.modifiers;
+    let code = match key.code {
+        KeyCode::Enter => String::from("enter"),
+        KeyCode::Esc => String::from("esc"),
+        KeyCode::Char(' ') => String::from("space"),
+        KeyCode::Backspace => String::from("backspace"),
+        KeyCode::Tab => String::from("tab"),
+        KeyCode::Up => String::from("up"),
+        KeyCode::Down => String::from("down"),
+        KeyCode::Left => String::from("left"),
+        KeyCode::Right => String::from("right"),
+        KeyCode::Home => String::from("home"),
+        KeyCode::End => String::from("end"),
+        KeyCode::PageUp => String::from("pageup"),
+        KeyCode::PageDown => String::from("pagedown"),
+        KeyCode::Delete => String::from("delete"),
+        KeyCode::BackTab => String::from("backtab"),
+        KeyCode::Char(character) => {
+            // Upper case letters already imply SHIFT.
+            if character.is_uppercase() {
+                modifiers.remove(KeyModifiers::SHIFT);
+            }
+            character.to_string()
+        }
+        _ => return String::from("null"),
+    };
+    let mut text = String::new();
+    for (modifier, name) in [
+        (KeyModifiers::CONTROL, "ctrl+"),
+        (KeyModifiers::ALT, "alt+"),
+        (KeyModifiers::SHIFT, "shift+"),
+    ] {
+        if modifiers.contains(modifier) {
+            text += name;
+        }
+    }
+    text + &code
+}
@@ -0,0 +1,393 @@
+use super::event_source::parse_event;
+use crate::tgames::{glyphs::Glyphs, Games};
+use crossterm::event::{Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::BTreeMap,
+    fs,
+    io::{Error, ErrorKind, Result},
+    path::Path,
+};
+use strum::IntoEnumIterator;
+use strum_macros::{Display, EnumIter};
+
+const KEYBINDINGS_FILE_NAME: &str = "keybindings.toml";
+// Section of the config applied to every game and to the main menu.
+const DEFAULT_SECTION: &str = "default";
+
+#[derive(
+    Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize, Display, EnumIter,
+)]
+#[serde(rename_all = "snake_case")]
+#[strum(serialize_all = "snake_case")]
+pub enum Action {
+    MoveUp,
+    MoveDown,
+    MoveLeft,
+    MoveRight,
+    Play,
+    Continue,
+    Help,
+    Leaderboard,
+    IncreaseFps,
+    DecreaseFps,
+    Pause,
+    Quit,
+    Rotate,
+    SoftDrop,
+    HardDrop,
+    Jump,
+    Reveal,
+    Mark,
+    Chord,
+    Hint,
+    Export,
+    PlayEasy,
+    PlayMedium,
+    PlayHard,
+    PlayCustom,
+    ChangeTheme,
+    ToggleNoGuess,
+}
+
+impl Action {
+    fn default_keys(&self) -> &'static [&'static str] {
+        match self {
+            Action::MoveUp | Action::Rotate => &["w", "k", "up"],
+            Action::MoveDown | Action::SoftDrop => &["s", "j", "down"],
+            Action::MoveLeft => &["a", "h", "left"],
+            Action::MoveRight => &["d", "l", "right"],
+            Action::Play => &["enter", "p"],
+            Action::Continue => &["c"],
+            Action::Help => &["?"],
+            Action::Leaderboard => &["l"],
+            Action::IncreaseFps => &["F"],
+            Action::DecreaseFps => &["f"],
+            Action::Pause => &["p"],
+            Action::Quit => &["esc", "q"],
+            Action::HardDrop => &["space"],
+            Action::Jump => &["w", "k", "up", "space"],
+            Action::Reveal => &["enter", "r"],
+            Action::Mark => &["!", "m"],
+            Action::Chord => &["c"],
+            Action::Hint => &["?"],
+            Action::Export => &["e"],
+            Action::PlayEasy => &["e"],
+            Action::PlayMedium => &["m"],
+            Action::PlayHard => &["h"],
+            Action::PlayCustom => &["x"],
+            Action::ChangeTheme => &["t"],
+            Action::ToggleNoGuess => &["g"],
+        }
+    }
+}
+
+// Actions read at the same time, with the description shown in the help screens. Two actions of
+// the same list can't share a key.
+pub type ActionGuide = &'static [(Action, &'static str)];
+
+#[derive(Clone, PartialEq, Eq)]
+struct KeyBinding {
+    code: KeyCode,
+    modifiers: KeyModifiers,
+}
+
+impl KeyBinding {
+    fn parse(text: &str) -> Option<Self> {
+        match parse_event(text)? {
+            Event::Key(KeyEvent {
+                code, modifiers, ..
+            }) => Some(Self::new(code, modifiers)),
+            _ => None,
+        }
+    }
+
+    // SHIFT is already part of the character, terminals don't agree on reporting it.
+    fn new(code: KeyCode, mut modifiers: KeyModifiers) -> Self {
+        if let KeyCode::Char(_) = code {
+            modifiers.remove(KeyModifiers::SHIFT);
+        }
+        Self { code, modifiers }
+    }
+
+    fn matches(&self, event: &Event) -> bool {
+        match event {
+            Event::Key(KeyEvent {
+                code,
+                modifiers,
+                kind: KeyEventKind::Press,
+                ..
+            }) => *self == Self::new(*code, *modifiers),
+            _ => false,
+        }
+    }
+
+    fn label(&self, glyphs: &Glyphs) -> String {
+        let key = match self.code {
+            KeyCode::Up => String::from(glyphs.arrows[0]),
+            KeyCode::Down => String::from(glyphs.arrows[1]),
+            KeyCode::Left => String::from(glyphs.arrows[2]),
+            KeyCode::Right => String::from(glyphs.arrows[3]),
+            KeyCode::Char(' ') => String::from("SPACE"),
+            KeyCode::Char(character) => character.to_string(),
+            code => code.to_string().to_uppercase(),
+        };
+        let mut label = String::new();
+        for (modifier, name) in [
+            (KeyModifiers::CONTROL, "CTRL-"),
+            (KeyModifiers::ALT, "ALT-"),
+            (KeyModifiers::SHIFT, "SHIFT-"),
+        ] {
+            if self.modifiers.contains(modifier) {
+                label += name;
+            }
+        }
+        label + &key
+    }
+}
+
+// The keys bound to each action, for one game.
+#[derive(Clone)]
+pub struct Keymap {
+    bindings: BTreeMap<Action, Vec<KeyBinding>>,
+}
+
+impl Default for Keymap {
+    fn default() -> Self {
+        Keybindings::default().keymap(DEFAULT_SECTION)
+    }
+}
+
+impl Keymap {
+    pub fn matches(&self, action: Action, event: &Event) -> bool {
+        self.bindings
+            .get(&action)
+            .is_some_and(|keys| keys.iter().any(|key| key.matches(event)))
+    }
+
+    // Press of the first key bound to the action.
+    pub fn key_event(&self, action: Action) -> Option<Event> {
+        let key = self.bindings.get(&action)?.first()?;
+        Some(Event::Key(KeyEvent::new(key.code, key.modifiers)))
+    }
+
+    // Something like `w or k or ESC` for the keys of the action.
+    pub fn keys_label(&self, action: Action, glyphs: &Glyphs) -> String {
+        self.bindings
+            .get(&action)
+            .map(|keys| {
+                keys.iter()
+                    .map(|key| key.label(glyphs))
+                    .collect::<Vec<_>>()
+                    .join(" or ")
+            })
+            .unwrap_or_default()
+    }
+
+    // Help text with one line per action, keys on the left and the description on the right.
+    pub fn guide(&self, actions: ActionGuide, glyphs: &Glyphs) -> String {
+        let labels: Vec<String> = actions
+            .iter()
+            .map(|(action, _)| self.keys_label(*action, glyphs))
+            .collect();
+        let width = labels
+            .iter()
+            .map(|label| label.chars().count())
+            .max()
+            .unwrap_or(0);
+        let mut guide = String::new();
+        for (label, (_, description)) in labels.iter().zip(actions.iter()) {
+            guide += &format!("{label:<width$} - {description}\n");
+        }
+        guide
+    }
+
+    // Describes every key bound to more than one action of the list, or reserved for CTRL-c.
+    pub fn conflicts(&self, actions: ActionGuide) -> Vec<String> {
+        let force_quit = KeyBinding::new(KeyCode::Char('c'), KeyModifiers::CONTROL);
+        let mut conflicts = Vec::new();
+        for (index, (action, _)) in actions.iter().enumerate() {
+            for key in self.bindings.get(action).into_iter().flatten() {
+                if *key == force_quit {
+                    conflicts.push(format!(
+                        "{} is reserved to end the execution, it can't be bound to {action}",
+                        key.label(&Glyphs::default())
+                    ));
+                }
+                for (other, _) in &actions[index + 1..] {
+                    if self
+                        .bindings
+                        .get(other)
+                        .is_some_and(|keys| keys.contains(key))
+                    {
+                        conflicts.push(format!(
+                            "{} is bound to both {action} and {other}",
+                            key.label(&Glyphs::default())
+                        ));
+                    }
+                }
+            }
+        }
+        conflicts
+    }
+}
+
+// The whole keybindings config: a `[default]` section plus one section per game overriding it,
+// each one mapping action names to a list of keys, like `move_up = ["w", "k", "up"]`.
+#[derive(Clone, Default)]
+pub struct Keybindings {
+    sections: BTreeMap<String, BTreeMap<Action, Vec<String>>>,
+}
+
+impl Keybindings {
+    // Reads the config from the config directory, a missing file means the default bindings.
+    pub fn load() -> Result<Self> {
+        match dirs::config_dir() {
+            Some(directory) => {
+                Self::from_file(&directory.join("tgames").join(KEYBINDINGS_FILE_NAME))
+            }
+            None => Ok(Self::default()),
+        }
+    }
+
+    pub fn from_file(path: &Path) -> Result<Self> {
+        let content = match fs::read_to_string(path) {
+            Ok(content) => content,
+            Err(error) if error.kind() == ErrorKind::NotFound => return Ok(Self::default()),
+            Err(error) => return Err(error),
+        };
+        Self::from_toml(&content).map_err(|error| {
+            Error::new(
+                ErrorKind::InvalidData,
+                format!("{}: {error}", path.display()),
+            )
+        })
+    }
+
+    pub fn from_toml(content: &str) -> Result<Self> {
+        let sections: BTreeMap<String, BTreeMap<Action, Vec<String>>> =
+            toml::from_str(content).map_err(|error| Error::new(ErrorKind::InvalidData, error))?;
+        for (section, bindings) in &sections {
+            let known = Self::sections();
+            if !known.contains(&section.as_str()) {
+                return Err(Error::new(
+                    ErrorKind::InvalidData,
+                    format!(
+                        "unknown section [{section}], expected one of: {}",
+                        known.join(", ")
+                    ),
+                ));
+            }
+            for (action, keys) in bindings {
+                if let Some(key) = keys.iter().find(|key| KeyBinding::parse(key).is_none()) {
+                    return Err(Error::new(
+                        ErrorKind::InvalidData,
+                        format!("invalid key \"{key}\" for {action} in [{section}]"),
+                    ));
+                }
+            }
+        }
+        Ok(Self { sections })
+    }
+
+    // The config as it was read, so a recording can replay it whatever the local config says.
+    pub fn to_toml(&self) -> Result<String> {
+        toml::to_string(&self.sections).map_err(|error| Error::new(ErrorKind::InvalidData, error))
+    }
+
+    // `[default]` plus one section per game.
+    fn sections() -> Vec<&'static str> {
+        let games = Games::iter().filter(|game| *game != Games::None);
+        [DEFAULT_SECTION]
+            .into_iter()
+            .chain(games.map(|game| game.id()))
+            .collect()
+    }
+
+    // Bindings for `section`, falling back to the `[default]` section and then to the built-in
+    // keys for every action that isn't overridden.
+    pub fn keymap(&self, section: &str) -> Keymap {
+        let mut bindings = BTreeMap::new();
+        for action in Action::iter() {
+            let keys = [section, DEFAULT_SECTION]
+                .iter()
+                .find_map(|section| self.sections.get(*section)?.get(&action))
+                .map(|keys| keys.iter().map(String::as_str).collect())
+                .unwrap_or_else(|| action.default_keys().to_vec());
+            bindings.insert(
+                action,
+                keys.into_iter().filter_map(KeyBinding::parse).collect(),
+            );
+        }
+        Keymap { bindings }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ACTIONS: ActionGuide = &[(Action::Reveal, "Reveal"), (Action::Mark, "Mark")];
+
+    #[test]
+    fn keys_shared_inside_a_game_conflict() {
+        let keybindings = Keybindings::from_toml("[minesweeper]\nmark = [\"r\"]\n").unwrap();
+        let conflicts = keybindings.keymap("minesweeper").conflicts(ACTIONS);
+        assert_eq!(conflicts, ["r is bound to both reveal and mark"]);
+        assert!(keybindings.keymap("snake").conflicts(ACTIONS).is_empty());
+    }
+
+    #[test]
+    fn ctrl_c_is_reserved() {
+        let keybindings = Keybindings::from_toml("[default]\nreveal = [\"ctrl+c\"]\n").unwrap();
+        let conflicts = keybindings.keymap("minesweeper").conflicts(ACTIONS);
+        assert_eq!(
+            conflicts,
+            ["CTRL-c is reserved to end the execution, it can't be bound to reveal"]
+        );
+    }
+
+    #[test]
+    fn games_fall_back_to_the_default_section() {
+        let content = "[default]\nquit = [\"x\"]\n\n[snake]\nquit = [\"z\"]\n";
+        let keybindings = Keybindings::from_toml(content).unwrap();
+        let press = |key| parse_event(key).unwrap();
+        let snake = keybindings.keymap("snake");
+        assert!(snake.matches(Action::Quit, &press("z")));
+        assert!(!snake.matches(Action::Quit, &press("x")));
+        let tetris = keybindings.keymap("tetris");
+        assert!(tetris.matches(Action::Quit, &press("x")));
+        assert!(!tetris.matches(Action::Quit, &press("esc")));
+        // Actions the config doesn't mention keep their built-in keys.
+        assert!(tetris.matches(Action::Pause, &press("p")));
+    }
+
+    #[test]
+    fn invalid_keys_are_rejected() {
+        let error = Keybindings::from_toml("[tetris]\nrotate = [\"up\", \"hyper+x\"]\n")
+            .err()
+            .unwrap();
+        assert_eq!(
+            error.to_string(),
+            "invalid key \"hyper+x\" for rotate in [tetris]"
+        );
+    }
+
+    #[test]
+    fn unknown_sections_are_rejected() {
+        let error = Keybindings::from_toml("[tetirs]\nrotate = [\"up\"]\n")
+            .err()
+            .unwrap();
+        assert!(error
+            .to_string()
+            .starts_with("unknown section [tetirs], expected one of: default, snake"));
+    }
+
+    #[test]
+    fn toml_round_trip() {
+        let content = "[default]\nquit = [\"x\"]\n\n[snake]\nmove_up = [\"w\", \"space\"]\n";
+        let keybindings = Keybindings::from_toml(content).unwrap();
+        let read = Keybindings::from_toml(&keybindings.to_toml().unwrap()).unwrap();
+        assert!(read.sections == keybindings.sections);
+    }
+}
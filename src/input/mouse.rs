@@ -0,0 +1,73 @@
+use super::keybindings::{ActionGuide, Keymap};
+use crossterm::event::{Event, MouseButton, MouseEvent, MouseEventKind};
+use ratatui::layout::{Margin, Position, Rect};
+
+// Column and row of the cell clicked with `button`.
+pub fn clicked(event: &Event, button: MouseButton) -> Option<(u16, u16)> {
+    match event {
+        Event::Mouse(MouseEvent {
+            kind: MouseEventKind::Down(pressed),
+            column,
+            row,
+            ..
+        }) if *pressed == button => Some((*column, *row)),
+        _ => None,
+    }
+}
+
+// Line of `area` clicked with the left button, counted from its top.
+pub fn clicked_line(event: &Event, area: Rect) -> Option<usize> {
+    let (column, row) = clicked(event, MouseButton::Left)?;
+    area.contains(Position::new(column, row))
+        .then(|| (row - area.y) as usize)
+}
+
+// Inside of a panel drawn with borders on `area`.
+pub fn inner_area(area: Rect) -> Rect {
+    area.inner(Margin::new(1, 1))
+}
+
+// Turns a click on a line of the key guide drawn on `area` into a press of the first key bound to
+// that action, so everything a guide lists can also be done with the mouse. Any other event is
+// returned as it is.
+pub fn click_to_key(event: Event, area: Rect, actions: ActionGuide, keymap: &Keymap) -> Event {
+    clicked_line(&event, area)
+        .and_then(|line| actions.get(line))
+        .and_then(|(action, _)| keymap.key_event(*action))
+        .unwrap_or(event)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::input::{event_source::parse_event, keybindings::Action};
+
+    fn click(button: &str, column: u16, row: u16) -> Event {
+        parse_event(&format!("click {button} {column} {row}")).unwrap()
+    }
+
+    #[test]
+    fn lines_are_counted_inside_the_area() {
+        let area = Rect::new(10, 5, 20, 3);
+        assert_eq!(clicked_line(&click("left", 10, 5), area), Some(0));
+        assert_eq!(clicked_line(&click("left", 29, 7), area), Some(2));
+        assert_eq!(clicked_line(&click("left", 9, 5), area), None);
+        assert_eq!(clicked_line(&click("left", 30, 5), area), None);
+        assert_eq!(clicked_line(&click("left", 10, 8), area), None);
+        assert_eq!(clicked_line(&click("right", 10, 5), area), None);
+    }
+
+    #[test]
+    fn guide_lines_become_their_keys() {
+        let actions: ActionGuide = &[(Action::Play, "Play"), (Action::Quit, "Quit")];
+        let keymap = Keymap::default();
+        let area = Rect::new(0, 0, 20, 2);
+        let key = click_to_key(click("left", 3, 1), area, actions, &keymap);
+        assert!(keymap.matches(Action::Quit, &key));
+        let outside = click("left", 3, 2);
+        assert_eq!(
+            click_to_key(outside.clone(), area, actions, &keymap),
+            outside
+        );
+    }
+}
@@ -0,0 +1,318 @@
+use super::{
+    event_source::{format_event, parse_event, EventSource, ThreadedEventSource},
+    keybindings::Keybindings,
+};
+use crate::tgames::game_manager::{GameOptions, GameSettings};
+use crossterm::event::{Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+use std::{
+    collections::VecDeque,
+    fs::{self, File},
+    io::{BufWriter, Error, ErrorKind, Result, Write},
+    path::Path,
+    time::{Duration, Instant},
+};
+
+pub const RECORDING_HEADER: &str = "# tgames recording";
+const MIN_SPEED: f64 = 0.25;
+const MAX_SPEED: f64 = 16.0;
+// Longest time spent waiting without checking the replay controls.
+const CONTROLS_POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+// Writes every event read from `inner` to a file, so the session can be replayed later. A poll
+// that finds nothing is written as `idle`, which is how timed games advance a frame without
+// input. Each line starts with the milliseconds elapsed since the recording started.
+pub struct RecordingEventSource {
+    inner: Box<dyn EventSource>,
+    writer: BufWriter<File>,
+    start: Instant,
+}
+
+impl RecordingEventSource {
+    pub fn create(
+        path: &Path,
+        inner: Box<dyn EventSource>,
+        seed: u64,
+        game: Option<&str>,
+        screen_size: Option<(u16, u16)>,
+        settings: &GameSettings,
+    ) -> Result<Self> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        writeln!(writer, "{RECORDING_HEADER}")?;
+        writeln!(writer, "seed {seed}")?;
+        if let Some(game) = game {
+            writeln!(writer, "game {game}")?;
+        }
+        if let Some((columns, rows)) = screen_size {
+            writeln!(writer, "size {columns} {rows}")?;
+        }
+        // The replay uses these instead of the local keybindings config and the command line.
+        for line in settings.keybindings.to_toml()?.lines() {
+            writeln!(writer, "keys {line}")?;
+        }
+        let options = toml::to_string(&settings.options)
+            .map_err(|error| Error::new(ErrorKind::InvalidData, error))?;
+        for line in options.lines() {
+            writeln!(writer, "options {line}")?;
+        }
+        writer.flush()?;
+        Ok(Self {
+            inner,
+            writer,
+            start: Instant::now(),
+        })
+    }
+
+    // Lines are flushed right away, so a crash still leaves a usable recording behind.
+    fn write(&mut self, entry: &str) -> Result<()> {
+        writeln!(self.writer, "{} {entry}", self.start.elapsed().as_millis())?;
+        self.writer.flush()
+    }
+}
+
+impl EventSource for RecordingEventSource {
+    fn read(&mut self) -> Result<Event> {
+        let event = self.inner.read()?;
+        self.write(&format_event(&event))?;
+        Ok(event)
+    }
+
+    fn poll(&mut self, timeout: Duration) -> Result<bool> {
+        let available = self.inner.poll(timeout)?;
+        if !available {
+            self.write("idle")?;
+        }
+        Ok(available)
+    }
+}
+
+struct ReplayEntry {
+    time: Duration,
+    event: Option<Event>,
+}
+
+// Plays a recording back with the original timing. While replaying, the keyboard controls the
+// playback: SPACE pauses, n steps one entry while paused, + and - change the speed and ESC or q
+// stops the replay. Once the recording runs out a CTRL-c is produced, so every game ends cleanly.
+pub struct ReplayEventSource {
+    entries: VecDeque<ReplayEntry>,
+    seed: Option<u64>,
+    game: Option<String>,
+    // Follows the resizes in the recording, starting from the size it was recorded on.
+    screen_size: Option<(u16, u16)>,
+    // Keybindings and game options the session was recorded with.
+    keybindings: Keybindings,
+    options: Option<GameOptions>,
+    controls: Box<dyn EventSource>,
+    speed: f64,
+    paused: bool,
+    steps: u32,
+    // Position in the recording, it only advances while not paused.
+    clock: Duration,
+    last_tick: Instant,
+}
+
+impl ReplayEventSource {
+    pub fn load(path: &Path) -> Result<Self> {
+        let invalid = |message: String| Error::new(ErrorKind::InvalidData, message);
+        let mut entries = VecDeque::new();
+        let mut seed = None;
+        let mut game = None;
+        let mut screen_size = None;
+        let mut keys = String::new();
+        let mut options = None;
+        for (number, line) in fs::read_to_string(path)?.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let (key, value) = line.split_once(' ').unwrap_or((line, ""));
+            if key == "seed" {
+                seed = Some(
+                    value
+                        .parse()
+                        .map_err(|_| invalid(format!("invalid seed on line {}", number + 1)))?,
+                );
+                continue;
+            }
+            if key == "game" {
+                game = Some(value.to_string());
+                continue;
+            }
+            if key == "size" {
+                screen_size = Some(
+                    value
+                        .split_once(' ')
+                        .and_then(|(columns, rows)| {
+                            Some((columns.parse().ok()?, rows.parse().ok()?))
+                        })
+                        .ok_or_else(|| invalid(format!("invalid size on line {}", number + 1)))?,
+                );
+                continue;
+            }
+            if key == "keys" {
+                keys += value;
+                keys.push('\n');
+                continue;
+            }
+            if key == "options" {
+                let options: &mut String = options.get_or_insert_default();
+                *options += value;
+                options.push('\n');
+                continue;
+            }
+            let time = key
+                .parse()
+                .map_err(|_| invalid(format!("invalid time \"{key}\" on line {}", number + 1)))?;
+            let event = if value == "idle" {
+                None
+            } else {
+                Some(parse_event(value).ok_or_else(|| {
+                    invalid(format!("invalid event \"{value}\" on line {}", number + 1))
+                })?)
+            };
+            entries.push_back(ReplayEntry {
+                time: Duration::from_millis(time),
+                event,
+            });
+        }
+        let keybindings = Keybindings::from_toml(&keys)
+            .map_err(|error| invalid(format!("invalid keybindings: {error}")))?;
+        let options = options
+            .map(|options| toml::from_str(&options))
+            .transpose()
+            .map_err(|error| invalid(format!("invalid options: {error}")))?;
+        Ok(Self {
+            entries,
+            seed,
+            game,
+            screen_size,
+            keybindings,
+            options,
+            controls: Box::new(ThreadedEventSource::new()),
+            speed: 1.0,
+            paused: false,
+            steps: 0,
+            clock: Duration::ZERO,
+            last_tick: Instant::now(),
+        })
+    }
+
+    pub fn seed(&self) -> Option<u64> {
+        self.seed
+    }
+
+    pub fn game(&self) -> Option<&str> {
+        self.game.as_deref()
+    }
+
+    // Speeds that aren't finite are ignored, the clock can't be scaled by them.
+    pub fn keybindings(&self) -> &Keybindings {
+        &self.keybindings
+    }
+
+    // None for recordings made before the options were kept.
+    pub fn options(&self) -> Option<&GameOptions> {
+        self.options.as_ref()
+    }
+
+    pub fn set_speed(&mut self, speed: f64) {
+        if speed.is_finite() {
+            self.speed = speed.clamp(MIN_SPEED, MAX_SPEED);
+        }
+    }
+
+    // Waits until the next entry is due, handling the playback controls meanwhile.
+    fn wait_next_entry(&mut self) -> Result<()> {
+        loop {
+            let now = Instant::now();
+            if !self.paused {
+                self.clock += now.duration_since(self.last_tick).mul_f64(self.speed);
+            }
+            self.last_tick = now;
+
+            let due = match self.entries.front() {
+                Some(entry) => entry.time,
+                None => return Ok(()),
+            };
+            if self.steps > 0 {
+                self.steps -= 1;
+                self.clock = self.clock.max(due);
+                return Ok(());
+            }
+            let timeout = if self.paused {
+                CONTROLS_POLL_INTERVAL
+            } else if self.clock >= due {
+                return Ok(());
+            } else {
+                (due - self.clock)
+                    .div_f64(self.speed)
+                    .min(CONTROLS_POLL_INTERVAL)
+            };
+            if self.controls.poll(timeout)? {
+                let event = self.controls.read()?;
+                self.handle_control(&event);
+            }
+        }
+    }
+
+    fn handle_control(&mut self, event: &Event) {
+        if let Event::Key(KeyEvent {
+            code,
+            modifiers,
+            kind: KeyEventKind::Press,
+            ..
+        }) = event
+        {
+            match code {
+                KeyCode::Char(' ') => self.paused = !self.paused,
+                KeyCode::Char('n') if self.paused => self.steps += 1,
+                KeyCode::Char('+') => self.set_speed(self.speed * 2.0),
+                KeyCode::Char('-') => self.set_speed(self.speed / 2.0),
+                KeyCode::Char('c') if modifiers.contains(KeyModifiers::CONTROL) => {
+                    self.entries.clear()
+                }
+                KeyCode::Char('q') | KeyCode::Esc => self.entries.clear(),
+                _ => (),
+            }
+        }
+    }
+
+    fn force_quit() -> Event {
+        Event::Key(KeyEvent::new(KeyCode::Char('c'), KeyModifiers::CONTROL))
+    }
+}
+
+impl EventSource for ReplayEventSource {
+    fn read(&mut self) -> Result<Event> {
+        loop {
+            self.wait_next_entry()?;
+            match self.entries.pop_front() {
+                Some(ReplayEntry {
+                    event: Some(event), ..
+                }) => {
+                    if let Event::Resize(columns, rows) = event {
+                        self.screen_size = Some((columns, rows));
+                    }
+                    return Ok(event);
+                }
+                Some(ReplayEntry { event: None, .. }) => (),
+                None => return Ok(Self::force_quit()),
+            }
+        }
+    }
+
+    fn poll(&mut self, _timeout: Duration) -> Result<bool> {
+        self.wait_next_entry()?;
+        match self.entries.front() {
+            Some(ReplayEntry { event: None, .. }) => {
+                self.entries.pop_front();
+                Ok(false)
+            }
+            Some(_) | None => Ok(true),
+        }
+    }
+
+    fn screen_size(&self) -> Option<(u16, u16)> {
+        self.screen_size
+    }
+}
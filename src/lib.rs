@@ -0,0 +1,15 @@
+//! A collection of little terminal games.
+//!
+//! Every game implements [`GameManager`] and can be driven on any ratatui backend with any
+//! [`input::event_source::EventSource`]. The rules of each game live in its `board` module,
+//! independent from rendering and input, so they can be reused on their own.
+
+pub mod input;
+pub mod terminal;
+mod tgames;
+
+pub use tgames::{
+    check_keybindings, flappy_bird_gm, g2048_gm,
+    game_manager::{self, GameBackend, GameManager},
+    glyphs, leaderboard, minesweeper_gm, records, run, snake_gm, tetris_gm, theme, Games,
+};
@@ -1,62 +1,449 @@
-mod input;
-mod tgames;
-
-use crossterm::{
-    style::Stylize,
-    terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
-    ExecutableCommand,
-};
+use clap::{Args, Parser, Subcommand, ValueEnum};
+use crossterm::style::Stylize;
 use ratatui::prelude::{CrosstermBackend, Terminal};
 use std::{
-    env::{self},
-    io::{self, stdout, Result},
+    cell::RefCell,
+    io::{stdout, Error, ErrorKind, IsTerminal, Result},
+    path::{Path, PathBuf},
+    process::ExitCode,
+    rc::Rc,
+};
+use strum::IntoEnumIterator;
+use tgames::{
+    check_keybindings,
+    game_manager::{session_seed, Difficult, GameSettings},
+    glyphs::{GlyphSet, Glyphs},
+    input::{
+        event_source::{EventSource, ScriptedEventSource, ThreadedEventSource},
+        keybindings::Keybindings,
+        replay::{RecordingEventSource, ReplayEventSource},
+    },
+    leaderboard::{leaderboard_text, modes},
+    minesweeper_gm::{check_custom_board, read_board_file},
+    records::RecordsStore,
+    terminal::{crash_log_path, install_panic_hook, TerminalGuard},
+    theme::Themes,
+    Games,
 };
-use tgames::Games;
-
-fn main() -> Result<()> {
-    let mut game = Games::None;
-    let argv: Vec<String> = env::args().collect();
-    if argv.len() > 1 {
-        if argv[1] == "snake" {
-            game = Games::Snake;
-        } else if argv[1] == "tetris" {
-            game = Games::Tetris;
-        } else if argv[1] == "2048" {
-            game = Games::G2048;
-        } else if argv[1] == "flappybird" {
-            game = Games::FlappyBird;
-        } else if argv[1] == "minesweeper" {
-            game = Games::Minesweeper;
-        } else {
-            print_help_message();
-            let mut input: String = String::new();
-            io::stdin()
-                .read_line(&mut input)
-                .expect("Failed to read line");
-        }
-    }
-
-    stdout().execute(EnterAlternateScreen)?;
-    enable_raw_mode()?;
-    let mut terminal = Terminal::new(CrosstermBackend::new(stdout()))?;
-    terminal.clear()?;
-
-    tgames::run(terminal, game)?;
-
-    stdout().execute(LeaveAlternateScreen)?;
-    disable_raw_mode()?;
-    print_goodbye_message();
+
+#[derive(Parser)]
+#[command(
+    version,
+    about = "A collection of little terminal games",
+    after_help = "Run without a command to choose the game from the main menu."
+)]
+struct Cli {
+    #[command(subcommand)]
+    command: Option<Command>,
+    #[command(flatten)]
+    session: SessionArgs,
+}
+
+#[derive(Args)]
+struct SessionArgs {
+    #[arg(
+        long,
+        global = true,
+        help = "Seed every game session with this value, so runs can be reproduced"
+    )]
+    seed: Option<u64>,
+    #[arg(
+        long,
+        global = true,
+        value_name = "FILE",
+        help = "Read the input from a file instead of the keyboard, one event per line"
+    )]
+    events: Option<PathBuf>,
+    #[arg(
+        long,
+        global = true,
+        value_name = "FILE",
+        conflicts_with = "replay",
+        help = "Record the session to a file"
+    )]
+    record: Option<PathBuf>,
+    #[arg(
+        long,
+        global = true,
+        value_name = "FILE",
+        help = "Replay a recorded session"
+    )]
+    replay: Option<PathBuf>,
+    #[arg(
+        long,
+        global = true,
+        default_value_t = 1.0,
+        requires = "replay",
+        value_parser = parse_speed,
+        help = "Speed of the replay, 2 plays it twice as fast"
+    )]
+    speed: f64,
+    #[arg(
+        long,
+        global = true,
+        value_name = "NAME",
+        help = "Colors to use, a built-in theme or one from themes.toml"
+    )]
+    theme: Option<String>,
+    #[arg(
+        long,
+        global = true,
+        value_name = "SET",
+        value_parser = parse_glyph_set,
+        help = "Symbols to draw with: nerd-font, unicode or ascii [default: detected]"
+    )]
+    glyphs: Option<GlyphSet>,
+    #[arg(
+        long,
+        global = true,
+        help = "Don't write a crash log to the data directory if tgames crashes"
+    )]
+    no_crash_log: bool,
+    #[arg(
+        long,
+        global = true,
+        help = "Print how fast the games drew their frames when tgames exits"
+    )]
+    frame_stats: bool,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    #[command(about = "List the available games")]
+    List,
+    #[command(about = "Print the leaderboards")]
+    Scores {
+        #[arg(value_parser = parse_game, help = "Only print the leaderboards of this game")]
+        game: Option<Games>,
+        #[arg(
+            long,
+            requires = "game",
+            help = "Only print the leaderboard of this mode"
+        )]
+        mode: Option<String>,
+    },
+    #[command(about = "Play Snake")]
+    Snake {
+        #[arg(long, value_parser = clap::value_parser!(u64).range(1..=120), help = "Frames per second")]
+        fps: Option<u64>,
+        #[arg(long, value_parser = clap::value_parser!(u16).range(5..=100), help = "Height of the board")]
+        height: Option<u16>,
+        #[arg(long, value_parser = clap::value_parser!(u16).range(5..=100), help = "Width of the board")]
+        width: Option<u16>,
+    },
+    #[command(about = "Play Tetris")]
+    Tetris,
+    #[command(name = "2048", about = "Play 2048")]
+    G2048,
+    #[command(about = "Play Minesweeper")]
+    Minesweeper {
+        #[arg(long, value_enum, help = "Difficulty of the game")]
+        difficulty: Option<Difficulty>,
+        #[arg(
+            long,
+            requires_all = ["width", "mines"],
+            conflicts_with = "difficulty",
+            help = "Height of a custom board"
+        )]
+        height: Option<usize>,
+        #[arg(long, requires_all = ["height", "mines"], help = "Width of a custom board")]
+        width: Option<usize>,
+        #[arg(long, requires_all = ["height", "width"], help = "Mines on a custom board")]
+        mines: Option<usize>,
+        #[arg(
+            long,
+            help = "Open a random empty area at the start, instead of waiting for the first square revealed"
+        )]
+        auto_open: bool,
+        #[arg(
+            long,
+            help = "Only deal boards that can be cleared without guessing, out of 200 tries at most"
+        )]
+        no_guess: bool,
+        #[arg(
+            long,
+            value_name = "FILE",
+            conflicts_with_all = ["difficulty", "height", "auto_open", "no_guess"],
+            help = "Play the board saved in a file, as text or .mbf"
+        )]
+        board: Option<PathBuf>,
+    },
+    #[command(name = "flappybird", about = "Play Flappy bird")]
+    FlappyBird {
+        #[arg(long, value_parser = clap::value_parser!(u64).range(1..=120), help = "Frames per second")]
+        fps: Option<u64>,
+    },
+}
+
+#[derive(Clone, ValueEnum)]
+enum Difficulty {
+    Easy,
+    Medium,
+    Hard,
+}
+
+fn main() -> ExitCode {
+    let cli = Cli::parse();
+    install_panic_hook(if cli.session.no_crash_log {
+        None
+    } else {
+        crash_log_path()
+    });
+    match run(cli) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(error) => {
+            eprintln!("tgames: {error}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn run(cli: Cli) -> Result<()> {
+    let mut settings = GameSettings::default();
+    let mut board_file = None;
+    let mut game = match cli.command {
+        Some(Command::List) => {
+            print_games();
+            return Ok(());
+        }
+        Some(Command::Scores { game, mode }) => return print_scores(game, mode),
+        Some(Command::Snake { fps, height, width }) => {
+            let (default_height, default_width) = (12, 20);
+            if height.is_some() || width.is_some() {
+                settings.options.board_size = Some((
+                    height.map_or(default_height, usize::from),
+                    width.map_or(default_width, usize::from),
+                ));
+            }
+            settings.options.fps = fps;
+            Games::Snake
+        }
+        Some(Command::Tetris) => Games::Tetris,
+        Some(Command::G2048) => Games::G2048,
+        Some(Command::Minesweeper {
+            difficulty,
+            height,
+            width,
+            mines,
+            auto_open,
+            no_guess,
+            board,
+        }) => {
+            settings.options.auto_open = auto_open;
+            settings.options.no_guess = no_guess;
+            settings.options.difficult = difficulty.map(|difficulty| match difficulty {
+                Difficulty::Easy => Difficult::Easy,
+                Difficulty::Medium => Difficult::Medium,
+                Difficulty::Hard => Difficult::Hard,
+            });
+            if let (Some(height), Some(width), Some(mines)) = (height, width, mines) {
+                settings.options.difficult = Some(Difficult::Custom {
+                    height,
+                    width,
+                    mines,
+                });
+            }
+            board_file = board;
+            Games::Minesweeper
+        }
+        Some(Command::FlappyBird { fps }) => {
+            settings.options.fps = fps;
+            Games::FlappyBird
+        }
+        None => Games::None,
+    };
+
+    // Replays use the keybindings they were recorded with instead, whatever the local config says.
+    if cli.session.replay.is_none() {
+        settings.keybindings = Keybindings::load()
+            .and_then(|keybindings| {
+                check_keybindings(&keybindings)?;
+                Ok(keybindings)
+            })
+            .map_err(|error| {
+                Error::new(error.kind(), format!("invalid keybindings config: {error}"))
+            })?;
+    }
+    let themes = Themes::load()
+        .map_err(|error| Error::new(error.kind(), format!("invalid themes config: {error}")))?;
+    let theme = match &cli.session.theme {
+        Some(name) => themes.get(name)?,
+        None => themes.default_theme(),
+    };
+    settings.theme = Rc::new(RefCell::new(theme.clone()));
+    // Without a choice, plain ASCII is used where the terminal can't show Unicode.
+    settings.glyphs = cli
+        .session
+        .glyphs
+        .or(themes.glyph_set())
+        .unwrap_or_else(GlyphSet::detect)
+        .glyphs();
+    settings.themes = themes;
+    // Custom boards must fit drawn with these glyphs. Without a terminal to tell its size, they are
+    // checked once the game is drawn.
+    let screen = crossterm::terminal::size().ok();
+    if let Some(Difficult::Custom {
+        height,
+        width,
+        mines,
+    }) = settings.options.difficult
+    {
+        check_custom_board(height, width, mines, screen, &settings.glyphs)
+            .map_err(|error| Error::new(error.kind(), format!("invalid board: {error}")))?;
+    }
+    if let Some(path) = board_file {
+        let board = read_board_file(&path, screen, &settings.glyphs)
+            .map_err(|error| cant_play(&path, error))?;
+        settings.options.board = Some(board);
+    }
+    // A fixed seed makes every session of a game play out the same way.
+    settings.seed = cli.session.seed;
+    // Events can be read from a file instead of the keyboard, one per line.
+    let mut events: Box<dyn EventSource> = match &cli.session.events {
+        Some(path) => {
+            Box::new(ScriptedEventSource::from_file(path).map_err(|error| cant_play(path, error))?)
+        }
+        None => Box::new(ThreadedEventSource::new()),
+    };
+    // Recorded and replayed sessions keep their records in memory, so a replay always sees the
+    // same leaderboards the recording did.
+    let mut records = RecordsStore::load()?;
+    if let Some(path) = &cli.session.replay {
+        let mut replay = ReplayEventSource::load(path).map_err(|error| cant_play(path, error))?;
+        replay.set_speed(cli.session.speed);
+        settings.seed = replay.seed().or(settings.seed);
+        settings.keybindings = replay.keybindings().clone();
+        if let Some(options) = replay.options() {
+            settings.options = options.clone();
+        }
+        if let Some(chosen) = replay.game().and_then(Games::from_id) {
+            game = chosen;
+        }
+        events = Box::new(replay);
+        records = RecordsStore::in_memory();
+    } else if let Some(path) = &cli.session.record {
+        let seed = session_seed(settings.seed);
+        settings.seed = Some(seed);
+        events = Box::new(RecordingEventSource::create(
+            path,
+            events,
+            seed,
+            Some(game.id()).filter(|id| !id.is_empty()),
+            crossterm::terminal::size().ok(),
+            &settings,
+        )?);
+        records = RecordsStore::in_memory();
+    }
+
+    let glyphs = settings.glyphs;
+    // The guard restores the terminal however this block is left, errors included.
+    let frame_stats = {
+        let _guard = TerminalGuard::enter()?;
+        let mut terminal = Terminal::new(CrosstermBackend::new(stdout()))?;
+        terminal.clear()?;
+        tgames::run(terminal, events, game, settings, records)?
+    };
+    print_goodbye_message(&glyphs);
+    if cli.session.frame_stats {
+        println!("{}", frame_stats.report());
+    }
     Ok(())
 }
 
-fn print_help_message() {
-    println!("Invalid command-line argument!");
-    println!("Press enter to continue or CRTL-c to end execution.");
+fn cant_play(path: &Path, error: Error) -> Error {
+    Error::new(
+        error.kind(),
+        format!("can't play {}: {error}", path.display()),
+    )
 }
 
-fn print_goodbye_message() {
-    println!(
-        "{}",
-        "Thanks for playing tgames󰊴, hope you enjoyed it!".green()
+fn parse_game(id: &str) -> std::result::Result<Games, String> {
+    Games::from_id(id).ok_or_else(|| {
+        let ids: Vec<&str> = Games::iter()
+            .filter(|game| *game != Games::None)
+            .map(|game| game.id())
+            .collect();
+        format!("unknown game, expected one of: {}", ids.join(", "))
+    })
+}
+
+fn parse_glyph_set(name: &str) -> std::result::Result<GlyphSet, String> {
+    GlyphSet::from_name(name).map_err(|error| error.to_string())
+}
+
+fn parse_speed(speed: &str) -> std::result::Result<f64, String> {
+    match speed.parse::<f64>() {
+        Ok(speed) if speed.is_finite() && speed > 0.0 => Ok(speed),
+        Ok(_) => Err(String::from("the speed must be a finite number above 0")),
+        Err(error) => Err(error.to_string()),
+    }
+}
+
+fn print_games() {
+    for game in Games::iter().filter(|game| *game != Games::None) {
+        println!("{:<12} {game}", game.id());
+    }
+}
+
+fn print_scores(game: Option<Games>, mode: Option<String>) -> Result<()> {
+    let records = RecordsStore::load()?;
+    let games: Vec<Games> = match game {
+        Some(game) => vec![game],
+        None => Games::iter().filter(|game| *game != Games::None).collect(),
+    };
+    let mut first = true;
+    for game in games {
+        let mut game_modes = modes(&game);
+        for recorded in records.modes(&game) {
+            if !game_modes.contains(&recorded) {
+                game_modes.push(recorded);
+            }
+        }
+        let selected: Vec<&String> = match &mode {
+            Some(mode) => {
+                let selected: Vec<&String> = game_modes
+                    .iter()
+                    .filter(|current| current.eq_ignore_ascii_case(mode))
+                    .collect();
+                if selected.is_empty() {
+                    return Err(Error::new(
+                        ErrorKind::InvalidInput,
+                        format!(
+                            "{game} has no mode \"{mode}\", expected one of: {}",
+                            game_modes.join(", ")
+                        ),
+                    ));
+                }
+                selected
+            }
+            None => game_modes.iter().collect(),
+        };
+        for mode in selected {
+            if !first {
+                println!();
+            }
+            first = false;
+            let title = format!("{game} - {mode}");
+            // Escape codes would only get in the way when the scores are piped or saved.
+            if stdout().is_terminal() {
+                println!("{}", title.bold());
+            } else {
+                println!("{title}");
+            }
+            print!("{}", leaderboard_text(&records, &game, mode));
+        }
+    }
+    Ok(())
+}
+
+fn print_goodbye_message(glyphs: &Glyphs) {
+    let message = format!(
+        "Thanks for playing tgames{}, hope you enjoyed it!",
+        glyphs.logo
     );
+    if stdout().is_terminal() {
+        println!("{}", message.green());
+    } else {
+        println!("{message}");
+    }
 }
@@ -0,0 +1,124 @@
+use crossterm::{
+    cursor::Show,
+    event::{DisableMouseCapture, EnableMouseCapture},
+    terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
+    ExecutableCommand,
+};
+use std::{
+    backtrace::Backtrace,
+    fs,
+    io::{stdout, Result},
+    panic::{self, PanicHookInfo},
+    path::{Path, PathBuf},
+    sync::atomic::{AtomicBool, Ordering},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+const CRASH_LOG_FILE_NAME: &str = "crash.log";
+const ISSUES_URL: &str = "https://github.com/enfmarinho/tgames/issues";
+
+// Whether the terminal is currently in raw mode on the alternate screen, so restoring it more than
+// once, from the panic hook and then from the guard, is harmless.
+static ACTIVE: AtomicBool = AtomicBool::new(false);
+
+// Puts the terminal in raw mode on the alternate screen, with the mouse reported as input, and
+// restores it when dropped, so every error path and every panic that unwinds leaves the shell
+// usable.
+pub struct TerminalGuard {
+    _private: (),
+}
+
+impl TerminalGuard {
+    pub fn enter() -> Result<Self> {
+        // Built first, so a failure halfway through still undoes what was already done.
+        let guard = Self { _private: () };
+        ACTIVE.store(true, Ordering::SeqCst);
+        stdout().execute(EnterAlternateScreen)?;
+        stdout().execute(EnableMouseCapture)?;
+        enable_raw_mode()?;
+        Ok(guard)
+    }
+}
+
+impl Drop for TerminalGuard {
+    fn drop(&mut self) {
+        let _ = restore();
+    }
+}
+
+// Leaves raw mode, the mouse capture and the alternate screen, if tgames entered them.
+pub fn restore() -> Result<()> {
+    if !ACTIVE.swap(false, Ordering::SeqCst) {
+        return Ok(());
+    }
+    let raw_mode = disable_raw_mode();
+    let mut stdout = stdout();
+    stdout.execute(DisableMouseCapture)?;
+    stdout.execute(LeaveAlternateScreen)?;
+    stdout.execute(Show)?;
+    raw_mode
+}
+
+// Default place of the crash log, in the data directory next to the records.
+pub fn crash_log_path() -> Option<PathBuf> {
+    dirs::data_dir().map(|directory| directory.join("tgames").join(CRASH_LOG_FILE_NAME))
+}
+
+// Restores the terminal before anything is printed on a panic, then reports what happened in a
+// readable way instead of the message getting lost in the alternate screen. With `crash_log`, the
+// full report, backtrace included, is also written to that file.
+pub fn install_panic_hook(crash_log: Option<PathBuf>) {
+    panic::set_hook(Box::new(move |info| {
+        let _ = restore();
+        let report = crash_report(info);
+        eprintln!("tgames crashed, sorry about that!\n");
+        eprintln!("{report}");
+        match crash_log
+            .as_deref()
+            .map(|path| write_crash_log(path, &report))
+        {
+            Some(Ok(path)) => eprintln!("\nThe full report was saved to {}", path.display()),
+            Some(Err(error)) => eprintln!("\nCouldn't save the crash log: {error}"),
+            None => (),
+        }
+        eprintln!("Please report it at {ISSUES_URL}");
+    }));
+}
+
+fn crash_report(info: &PanicHookInfo) -> String {
+    let message = info
+        .payload()
+        .downcast_ref::<&str>()
+        .map(|message| message.to_string())
+        .or_else(|| info.payload().downcast_ref::<String>().cloned())
+        .filter(|message| !message.is_empty())
+        .unwrap_or_else(|| String::from("explicit panic"));
+    let location = info
+        .location()
+        .map(|location| format!("{}:{}", location.file(), location.line()))
+        .unwrap_or_else(|| String::from("unknown"));
+    format!(
+        "version:  {}\nmessage:  {message}\nlocation: {location}",
+        env!("CARGO_PKG_VERSION")
+    )
+}
+
+fn write_crash_log<'a>(path: &'a Path, report: &str) -> Result<&'a Path> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let time = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or(0);
+    let arguments: Vec<String> = std::env::args().collect();
+    fs::write(
+        path,
+        format!(
+            "{report}\ntime:     {time}\ncommand:  {}\n\n{}\n",
+            arguments.join(" "),
+            Backtrace::force_capture()
+        ),
+    )?;
+    Ok(path)
+}
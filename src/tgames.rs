@@ -1,30 +1,41 @@
-mod flappy_bird_gm;
-mod g2048_gm;
-mod game_manager;
-mod minesweeper_gm;
-mod snake_gm;
-mod tetris_gm;
+pub mod flappy_bird_gm;
+pub mod g2048_gm;
+pub mod game_manager;
+pub mod glyphs;
+pub mod leaderboard;
+pub mod minesweeper_gm;
+pub mod records;
+pub mod snake_gm;
+pub mod tetris_gm;
+pub mod theme;
 
 use self::{
-    flappy_bird_gm::FlappyBirdGameManager, g2048_gm::G2048GameManager,
-    minesweeper_gm::MinesweeperGameManager, snake_gm::SnakeGameManager,
+    flappy_bird_gm::FlappyBirdGameManager,
+    g2048_gm::G2048GameManager,
+    game_manager::{draw, FrameStats, GameBackend, GameManager, GameSettings},
+    minesweeper_gm::MinesweeperGameManager,
+    records::{RecordsStore, SharedRecords},
+    snake_gm::SnakeGameManager,
     tetris_gm::TetrisGameManager,
 };
-use super::input::{should_force_quit, should_move_down, should_move_up, should_play, should_quit};
-use crossterm::event;
+use super::input::{
+    event_source::EventSource,
+    is_resize,
+    keybindings::{Action, ActionGuide, Keybindings, Keymap},
+    mouse, should_force_quit,
+};
 use ratatui::{
-    backend::CrosstermBackend,
-    layout::{Alignment, Constraint, Direction, Layout},
-    style::{Style, Stylize},
+    layout::{Alignment, Constraint, Direction, Layout, Rect},
+    style::Style,
     text::{Line, Span},
     widgets::{Block, Borders, Paragraph},
     Terminal,
 };
-use std::io::{Result, Stdout};
+use std::io::{Error, ErrorKind, Result};
 use strum::{EnumCount, IntoEnumIterator};
 use strum_macros::{Display, EnumCount as EnumCountMacro, EnumIter, FromRepr};
 
-#[derive(EnumIter, FromRepr, Display, EnumCountMacro, PartialEq)]
+#[derive(Clone, Copy, EnumIter, FromRepr, Display, EnumCountMacro, PartialEq)]
 pub enum Games {
     Snake,
     Tetris,
@@ -37,8 +48,89 @@ pub enum Games {
     None,
 }
 
-pub fn run(terminal: Terminal<CrosstermBackend<Stdout>>, game: Games) -> Result<()> {
-    let mut game_instance = TGamesManager::new(terminal);
+impl Games {
+    // Name used on the command line, in the config and in file names.
+    pub fn id(&self) -> &'static str {
+        match self {
+            Games::Snake => "snake",
+            Games::Tetris => "tetris",
+            Games::G2048 => "2048",
+            Games::Minesweeper => "minesweeper",
+            Games::FlappyBird => "flappybird",
+            Games::None => "",
+        }
+    }
+
+    pub fn from_id(id: &str) -> Option<Games> {
+        Games::iter().find(|game| game != &Games::None && game.id() == id)
+    }
+}
+
+const MAIN_MENU_ACTIONS: ActionGuide = &[
+    (Action::Play, "Play"),
+    (Action::MoveUp, "Go up"),
+    (Action::MoveDown, "Go down"),
+    (Action::ChangeTheme, "Change theme"),
+    (Action::Quit, "Quit"),
+];
+
+// Fails with a description of every key bound to two actions that are read at the same time.
+pub fn check_keybindings(keybindings: &Keybindings) -> Result<()> {
+    let mut contexts = vec![(
+        "main menu",
+        keybindings.keymap("default"),
+        MAIN_MENU_ACTIONS,
+    )];
+    for (game, menu_actions, play_actions) in [
+        (Games::Snake, snake_gm::MENU_ACTIONS, snake_gm::PLAY_ACTIONS),
+        (
+            Games::Tetris,
+            tetris_gm::MENU_ACTIONS,
+            tetris_gm::PLAY_ACTIONS,
+        ),
+        (Games::G2048, g2048_gm::MENU_ACTIONS, g2048_gm::PLAY_ACTIONS),
+        (
+            Games::Minesweeper,
+            minesweeper_gm::MENU_ACTIONS,
+            minesweeper_gm::PLAY_ACTIONS,
+        ),
+        (
+            Games::FlappyBird,
+            flappy_bird_gm::MENU_ACTIONS,
+            flappy_bird_gm::PLAY_ACTIONS,
+        ),
+    ] {
+        let keymap = keybindings.keymap(game.id());
+        contexts.push((game.id(), keymap.clone(), menu_actions));
+        contexts.push((game.id(), keymap, play_actions));
+    }
+    let conflicts: Vec<String> = contexts
+        .iter()
+        .flat_map(|(name, keymap, actions)| {
+            keymap
+                .conflicts(actions)
+                .into_iter()
+                .map(move |conflict| format!("[{name}] {conflict}"))
+        })
+        .collect();
+    if conflicts.is_empty() {
+        Ok(())
+    } else {
+        Err(Error::new(
+            ErrorKind::InvalidInput,
+            format!("conflicting keybindings:\n{}", conflicts.join("\n")),
+        ))
+    }
+}
+
+pub fn run<B: GameBackend>(
+    terminal: Terminal<B>,
+    events: Box<dyn EventSource>,
+    game: Games,
+    settings: GameSettings,
+    records: RecordsStore,
+) -> Result<FrameStats> {
+    let mut game_instance = TGamesManager::new(terminal, events, settings, records);
     if Games::None != game {
         game_instance.game_index = Games::iter()
             .position(|current| current == game)
@@ -50,7 +142,7 @@ pub fn run(terminal: Terminal<CrosstermBackend<Stdout>>, game: Games) -> Result<
         game_instance.update()?;
         game_instance.render()?;
     }
-    Ok(())
+    Ok(game_instance.frame_stats)
 }
 
 enum MainMenuOpts {
@@ -58,6 +150,7 @@ enum MainMenuOpts {
     Quit,
     Up,
     Down,
+    ChangeTheme,
     None,
 }
 
@@ -67,28 +160,48 @@ enum TGamesState {
     Quitting,
 }
 
-struct TGamesManager {
-    terminal: Terminal<CrosstermBackend<Stdout>>,
+struct TGamesManager<B: GameBackend> {
+    terminal: Terminal<B>,
+    events: Box<dyn EventSource>,
     execution_state: TGamesState,
     main_menu_opts: MainMenuOpts,
     game_index: usize,
-    game_instance: Vec<Option<Box<dyn game_manager::GameManager>>>,
+    game_instance: Vec<Option<Box<dyn GameManager<B>>>>,
+    frame_stats: FrameStats,
+    records: SharedRecords,
+    settings: GameSettings,
+    keymap: Keymap,
     kill_execution: bool,
+    // Where the list of games and the key guide were last drawn, so they can be clicked.
+    games_area: Rect,
+    guide_area: Rect,
 }
 
-impl TGamesManager {
-    fn new(terminal: Terminal<CrosstermBackend<Stdout>>) -> TGamesManager {
+impl<B: GameBackend> TGamesManager<B> {
+    fn new(
+        terminal: Terminal<B>,
+        events: Box<dyn EventSource>,
+        settings: GameSettings,
+        records: RecordsStore,
+    ) -> TGamesManager<B> {
         let mut game_instance = Vec::new();
         for _ in 0..Games::COUNT {
             game_instance.push(None);
         }
         TGamesManager {
             terminal,
+            events,
             execution_state: TGamesState::Starting,
             main_menu_opts: MainMenuOpts::None,
             game_index: 0,
             game_instance,
+            frame_stats: FrameStats::default(),
+            records: records.shared(),
+            keymap: settings.keybindings.keymap("default"),
+            settings,
             kill_execution: false,
+            games_area: Rect::default(),
+            guide_area: Rect::default(),
         }
     }
 
@@ -118,6 +231,14 @@ impl TGamesManager {
                         self.game_index += 1;
                     }
                 }
+                MainMenuOpts::ChangeTheme => {
+                    let next = self
+                        .settings
+                        .themes
+                        .next(&self.settings.theme.borrow().name)
+                        .clone();
+                    *self.settings.theme.borrow_mut() = next;
+                }
                 MainMenuOpts::None => (),
             },
             TGamesState::Quitting => (),
@@ -141,21 +262,27 @@ impl TGamesManager {
     }
 
     fn display_main_menu(&mut self) -> Result<()> {
+        let theme = self.settings.theme.clone();
+        let theme = theme.borrow();
+        let glyphs = self.settings.glyphs;
         let mut lines: Vec<Line> = Vec::new();
         for (index, opts) in Games::iter().enumerate() {
             if index == self.game_index {
                 lines.push(Line::from(Span::styled(
                     "> ".to_owned() + &opts.to_string() + " <",
-                    Style::default().green(),
+                    Style::default().fg(theme.highlight),
                 )));
             } else {
-                lines.push(Line::from(
-                    Span::styled(opts.to_string(), Style::default()).gray(),
-                ));
+                lines.push(Line::from(Span::styled(
+                    opts.to_string(),
+                    Style::default().fg(theme.text),
+                )));
             }
-            lines.push(Line::from(Span::styled("\n\n", Style::default()).gray()));
+            lines.push(Line::from(Span::styled("\n\n", Style::default())));
         }
-        self.terminal.draw(|frame| {
+        let keybindings_guide = self.keybindings_guide();
+        let (mut games_area, mut guide_area) = (Rect::default(), Rect::default());
+        draw(&mut self.terminal, |frame| {
             let layout = Layout::default()
                 .direction(Direction::Horizontal)
                 .constraints([Constraint::Percentage(40), Constraint::Percentage(60)])
@@ -173,16 +300,20 @@ impl TGamesManager {
                 Paragraph::new(lines).block(
                     Block::new()
                         .borders(Borders::ALL)
+                        .border_set(glyphs.border)
                         .title("tgames")
+                        .title_bottom(format!("Theme {}", theme.name))
                         .title_alignment(Alignment::Center),
                 ),
                 layout[0],
             );
+            games_area = mouse::inner_area(layout[0]);
 
             frame.render_widget(
                 Paragraph::new(Self::tips_message()).block(
                     Block::new()
                         .borders(Borders::ALL)
+                        .border_set(glyphs.border)
                         .title("Tips")
                         .title_alignment(Alignment::Center),
                 ),
@@ -190,44 +321,46 @@ impl TGamesManager {
             );
 
             frame.render_widget(
-                Paragraph::new(Self::keybindings_guide()).block(
+                Paragraph::new(keybindings_guide).block(
                     Block::new()
                         .borders(Borders::ALL)
+                        .border_set(glyphs.border)
                         .title("Keybindings")
                         .title_alignment(Alignment::Center),
                 ),
                 sub_layout[1],
             );
+            guide_area = mouse::inner_area(sub_layout[1]);
 
             frame.render_widget(
                 Paragraph::new(Self::developer_message()).block(
                     Block::new()
                         .borders(Borders::ALL)
+                        .border_set(glyphs.border)
                         .title("Notice")
                         .title_alignment(Alignment::Center),
                 ),
                 sub_layout[2],
             );
         })?;
+        self.games_area = games_area;
+        self.guide_area = guide_area;
         Ok(())
     }
 
-    fn keybindings_guide() -> String {
-        String::from(
-            "ENTER       - Play\nw or k or  - Go up\ns or j or  - Go down\nESC or q    - Quit\n",
-        )
+    fn keybindings_guide(&self) -> String {
+        self.keymap.guide(MAIN_MENU_ACTIONS, &self.settings.glyphs)
     }
 
     fn tips_message() -> String {
         String::from(
-            "Some of the games use elements from nerd fonts,
-so try using one for the cool symbols. Also, 
+            "The games look best with a nerd font, on other
+fonts try --glyphs unicode, or ascii. Also, 
 the size of everything is related to the size 
 of your font, so try adjusting it to make things
-fit. Note that the screen needs to be re-rendered 
-to fit with the different font size, so press any 
-valid key to do that, avoiding the ones that quit 
-the application (obviously).",
+fit. The screen is redrawn as soon as the window 
+changes size, and a game that doesn't fit waits, 
+paused, until there is room for it.",
         )
     }
 
@@ -244,22 +377,42 @@ consider giving it a start on github!",
 
     fn read_main_menu_input(&mut self) -> Result<()> {
         loop {
-            let event = event::read()?;
-            if should_quit(&event) {
+            let event = mouse::click_to_key(
+                self.events.read()?,
+                self.guide_area,
+                MAIN_MENU_ACTIONS,
+                &self.keymap,
+            );
+            // Every game takes a line followed by an empty one.
+            let clicked_game = mouse::clicked_line(&event, self.games_area)
+                .filter(|line| line % 2 == 0 && line / 2 < Games::COUNT - 1)
+                .map(|line| line / 2);
+            if let Some(index) = clicked_game {
+                self.game_index = index;
+                self.main_menu_opts = MainMenuOpts::Play;
+                break;
+            } else if is_resize(&event) {
+                // Nothing to do, the menu is drawn again for the new size.
+                self.main_menu_opts = MainMenuOpts::None;
+                break;
+            } else if self.keymap.matches(Action::Quit, &event) {
                 self.main_menu_opts = MainMenuOpts::Quit;
                 break;
             } else if should_force_quit(&event) {
                 self.kill_execution = true;
                 break;
-            } else if should_play(&event) {
+            } else if self.keymap.matches(Action::Play, &event) {
                 self.main_menu_opts = MainMenuOpts::Play;
                 break;
-            } else if should_move_up(&event) {
+            } else if self.keymap.matches(Action::MoveUp, &event) {
                 self.main_menu_opts = MainMenuOpts::Up;
                 break;
-            } else if should_move_down(&event) {
+            } else if self.keymap.matches(Action::MoveDown, &event) {
                 self.main_menu_opts = MainMenuOpts::Down;
                 break;
+            } else if self.keymap.matches(Action::ChangeTheme, &event) {
+                self.main_menu_opts = MainMenuOpts::ChangeTheme;
+                break;
             }
         }
         Ok(())
@@ -268,24 +421,70 @@ consider giving it a start on github!",
     fn run_game(&mut self) -> Result<()> {
         match Games::from_repr(self.game_index) {
             Some(game) => {
-                if let Ok(true) = match game {
+                let force_quit = match game {
                     Games::Snake => self.game_instance[self.game_index]
-                        .get_or_insert_with(|| Box::new(SnakeGameManager::new()))
-                        .run(&mut self.terminal),
+                        .get_or_insert_with(|| {
+                            Box::new(SnakeGameManager::new(
+                                self.records.clone(),
+                                self.settings.clone(),
+                            ))
+                        })
+                        .run(
+                            &mut self.terminal,
+                            self.events.as_mut(),
+                            &mut self.frame_stats,
+                        ),
                     Games::Tetris => self.game_instance[self.game_index]
-                        .get_or_insert_with(|| Box::new(TetrisGameManager::new()))
-                        .run(&mut self.terminal),
+                        .get_or_insert_with(|| {
+                            Box::new(TetrisGameManager::new(
+                                self.records.clone(),
+                                self.settings.clone(),
+                            ))
+                        })
+                        .run(
+                            &mut self.terminal,
+                            self.events.as_mut(),
+                            &mut self.frame_stats,
+                        ),
                     Games::G2048 => self.game_instance[self.game_index]
-                        .get_or_insert_with(|| Box::new(G2048GameManager::new()))
-                        .run(&mut self.terminal),
+                        .get_or_insert_with(|| {
+                            Box::new(G2048GameManager::new(
+                                self.records.clone(),
+                                self.settings.clone(),
+                            ))
+                        })
+                        .run(
+                            &mut self.terminal,
+                            self.events.as_mut(),
+                            &mut self.frame_stats,
+                        ),
                     Games::Minesweeper => self.game_instance[self.game_index]
-                        .get_or_insert_with(|| Box::new(MinesweeperGameManager::new()))
-                        .run(&mut self.terminal),
+                        .get_or_insert_with(|| {
+                            Box::new(MinesweeperGameManager::new(
+                                self.records.clone(),
+                                self.settings.clone(),
+                            ))
+                        })
+                        .run(
+                            &mut self.terminal,
+                            self.events.as_mut(),
+                            &mut self.frame_stats,
+                        ),
                     Games::FlappyBird => self.game_instance[self.game_index]
-                        .get_or_insert_with(|| Box::new(FlappyBirdGameManager::new()))
-                        .run(&mut self.terminal),
+                        .get_or_insert_with(|| {
+                            Box::new(FlappyBirdGameManager::new(
+                                self.records.clone(),
+                                self.settings.clone(),
+                            ))
+                        })
+                        .run(
+                            &mut self.terminal,
+                            self.events.as_mut(),
+                            &mut self.frame_stats,
+                        ),
                     Games::None => Ok(false),
-                } {
+                }?;
+                if force_quit {
                     self.kill_execution = true;
                 }
             }
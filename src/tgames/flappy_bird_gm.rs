@@ -1,26 +1,28 @@
-mod board;
+pub mod board;
 
 use super::{
     super::input::{
-        read_confirmation, read_key, should_decrease_fps, should_force_quit, should_help,
-        should_increase_fps, should_move_up, should_pause, should_play, should_quit,
+        event_source::EventSource,
+        is_resize,
+        keybindings::{Action, ActionGuide, Keymap},
+        mouse, read_confirmation, read_key, should_force_quit,
     },
-    game_manager::{self, GameManager},
+    game_manager::{self, draw, session_seed, GameBackend, GameManager, GameSettings},
+    glyphs::Glyphs,
+    leaderboard::{display_leaderboard, modes, stat_title, NamePrompt, NamePromptStatus},
+    records::{LeaderboardEntry, SharedRecords, DEFAULT_MODE},
+    Games,
 };
 use board::Board;
-use crossterm::event::{poll, read, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
 use ratatui::{
-    backend::CrosstermBackend,
-    layout::{Alignment, Constraint, Direction, Layout},
+    layout::{Alignment, Constraint, Direction, Layout, Rect},
     style::Color,
     style::Stylize,
     widgets::{Block, Borders, Paragraph},
     Terminal,
 };
-use std::{
-    io::{Result, Stdout},
-    time::Duration,
-};
+use serde::{Deserialize, Serialize};
+use std::{io::Result, time::Duration};
 
 const FPS_CHANGE: u64 = 4;
 
@@ -31,10 +33,28 @@ enum PlayOpt {
     None,
 }
 
+pub const MENU_ACTIONS: ActionGuide = &[
+    (Action::Play, "Play"),
+    (Action::Continue, "Continue saved game"),
+    (Action::DecreaseFps, "Decrease fps"),
+    (Action::IncreaseFps, "Increase fps"),
+    (Action::Help, "Display game rules"),
+    (Action::Leaderboard, "Display leaderboard"),
+    (Action::Quit, "Go to main menu"),
+];
+
+pub const PLAY_ACTIONS: ActionGuide = &[
+    (Action::Jump, "Jump"),
+    (Action::Pause, "Pause game"),
+    (Action::Quit, "Go back to menu"),
+];
+
 enum MenuOpt {
     Play,
+    Continue,
     Quit,
     Help,
+    Leaderboard,
     IncreaseFPS,
     DecreaseFPS,
     None,
@@ -48,10 +68,19 @@ enum GameState {
     Helping,
     Pause,
     Lost,
+    EnteringName,
+    Leaderboard,
     AskingToQuit,
     Quitting,
 }
 
+// Everything needed to resume a game left in the middle.
+#[derive(Serialize, Deserialize)]
+struct SavedGame {
+    board: Board,
+    fps: u64,
+}
+
 pub struct FlappyBirdGameManager {
     game_state: GameState,
     menu_opt: MenuOpt,
@@ -59,21 +88,41 @@ pub struct FlappyBirdGameManager {
     confirmed: bool,
     board: Board,
     record: u32,
+    records: SharedRecords,
+    keymap: Keymap,
+    settings: GameSettings,
+    name_prompt: NamePrompt,
     fps: u64,
     kill_execution: bool,
+    resized: bool,
+    // Where the key guide was last drawn, so its entries can be clicked.
+    help_area: Rect,
 }
 
-impl GameManager for FlappyBirdGameManager {
-    fn process_events(&mut self) -> Result<()> {
+impl<B: GameBackend> GameManager<B> for FlappyBirdGameManager {
+    fn process_events(&mut self, events: &mut dyn EventSource) -> Result<()> {
         match self.game_state {
             GameState::Starting => (),
-            GameState::Menu | GameState::Lost => self.read_menu_input()?,
-            GameState::Playing => self.read_play_input()?,
-            GameState::Helping | GameState::Pause => read_key()?,
+            GameState::Menu | GameState::Lost => self.read_menu_input(events)?,
+            GameState::Playing => self.read_play_input(events)?,
+            GameState::Helping | GameState::Leaderboard => {
+                self.resized = is_resize(&read_key(events)?)
+            }
+            GameState::Pause => {
+                let event = events.read()?;
+                self.kill_execution = should_force_quit(&event);
+                self.resized = is_resize(&event);
+            }
+            GameState::EnteringName => {
+                let event = events.read()?;
+                self.kill_execution = should_force_quit(&event);
+                self.name_prompt.handle_event(&event);
+            }
             GameState::AskingToQuit => {
-                let event = read()?;
+                let event = events.read()?;
                 self.kill_execution = should_force_quit(&event);
                 self.confirmed = read_confirmation(&event);
+                self.resized = is_resize(&event);
             }
             GameState::Quitting => (),
         }
@@ -81,43 +130,78 @@ impl GameManager for FlappyBirdGameManager {
     }
 
     fn update(&mut self) -> Result<()> {
+        // A resize only needs the screen drawn again.
+        if self.resized {
+            self.resized = false;
+            return Ok(());
+        }
         if self.kill_execution {
+            if matches!(
+                self.game_state,
+                GameState::Playing | GameState::Pause | GameState::AskingToQuit
+            ) {
+                self.suspend_game()?;
+            }
             self.game_state = GameState::Quitting;
         }
         match self.game_state {
-            GameState::Starting => self.game_state = GameState::Playing,
+            GameState::Starting => {
+                // Let the player choose between the suspended game and a new one.
+                if self.records.borrow().has_saved_game(&Games::FlappyBird) {
+                    self.game_state = GameState::Menu;
+                } else {
+                    self.game_state = GameState::Playing;
+                }
+            }
             GameState::Menu | GameState::Lost => match self.menu_opt {
                 MenuOpt::Play => {
                     if matches!(self.game_state, GameState::Lost) {
-                        self.board.reset_board();
+                        self.board.reset_board(session_seed(self.settings.seed));
                     }
                     self.game_state = GameState::Playing;
                 }
+                MenuOpt::Continue => self.resume_game(),
                 MenuOpt::Help => self.game_state = GameState::Helping,
+                MenuOpt::Leaderboard => self.game_state = GameState::Leaderboard,
                 MenuOpt::IncreaseFPS => self.increase_fps(),
                 MenuOpt::DecreaseFPS => self.decrease_fps(),
                 MenuOpt::Quit => self.game_state = GameState::Quitting,
                 MenuOpt::None => (),
             },
-            GameState::Playing => {
-                match self.play_opt {
-                    PlayOpt::None => self.board.advance(false),
-                    PlayOpt::Jump => self.board.advance(true),
-                    PlayOpt::Pause => self.game_state = GameState::Pause,
-                    PlayOpt::Quit => self.game_state = GameState::AskingToQuit,
-                }
-                if self.board.lost() {
-                    self.game_state = GameState::Lost;
+            // A jump waits for the next tick.
+            GameState::Playing => match self.play_opt {
+                PlayOpt::Pause => {
+                    self.game_state = GameState::Pause;
+                    self.play_opt = PlayOpt::None;
                 }
-                if self.record < self.board.consult_score() {
-                    self.record = self.board.consult_score();
+                PlayOpt::Quit => {
+                    self.game_state = GameState::AskingToQuit;
+                    self.play_opt = PlayOpt::None;
                 }
-            }
-            GameState::Helping => self.game_state = GameState::Menu,
+                PlayOpt::Jump | PlayOpt::None => (),
+            },
+            GameState::Helping | GameState::Leaderboard => self.game_state = GameState::Menu,
             GameState::Pause => self.game_state = GameState::Playing,
+            GameState::EnteringName => match self.name_prompt.status() {
+                NamePromptStatus::Typing => (),
+                NamePromptStatus::Confirmed => {
+                    self.records.borrow_mut().add_leaderboard_entry(
+                        &Games::FlappyBird,
+                        DEFAULT_MODE,
+                        LeaderboardEntry::new(
+                            self.name_prompt.name(),
+                            self.board.consult_score(),
+                            None,
+                        ),
+                    );
+                    self.game_state = GameState::Lost;
+                }
+                NamePromptStatus::Skipped => self.game_state = GameState::Lost,
+            },
             GameState::AskingToQuit => match self.confirmed {
                 true => {
-                    self.board.reset_board();
+                    self.suspend_game()?;
+                    self.board.reset_board(session_seed(self.settings.seed));
                     self.game_state = GameState::Menu;
                 }
                 false => self.game_state = GameState::Playing,
@@ -127,45 +211,85 @@ impl GameManager for FlappyBirdGameManager {
         Ok(())
     }
 
-    fn render(&mut self, terminal: &mut Terminal<CrosstermBackend<Stdout>>) -> Result<()> {
+    fn tick(&mut self) -> Result<()> {
+        self.board.advance(matches!(self.play_opt, PlayOpt::Jump));
+        self.play_opt = PlayOpt::None;
+        if self.board.lost() {
+            self.finish_game();
+        }
+        if self.record < self.board.consult_score() {
+            self.record = self.board.consult_score();
+            let record = self.record;
+            self.records
+                .borrow_mut()
+                .update(|records| records.flappy_bird = record);
+        }
+        Ok(())
+    }
+
+    fn tick_rate(&self) -> Option<Duration> {
+        match self.game_state {
+            GameState::Playing => Some(Duration::from_secs(1) / self.fps as u32),
+            _ => None,
+        }
+    }
+
+    fn render(&mut self, terminal: &mut Terminal<B>) -> Result<()> {
+        let theme = self.settings.theme.clone();
+        let theme = theme.borrow();
         match self.game_state {
             GameState::Starting => (),
             GameState::Menu => self.display_screen(
                 terminal,
                 self.record,
-                Self::menu_guide(),
+                self.menu_guide(),
                 "Menu",
                 "Record",
                 "",
-                Color::default(),
+                theme.text,
             )?,
             GameState::Playing => self.display_screen(
                 terminal,
                 self.board.consult_score(),
-                Self::play_guide(),
+                self.play_guide(),
                 "Game board",
                 "Score",
                 "",
-                Color::default(),
+                theme.text,
             )?,
             GameState::Lost => self.display_screen(
                 terminal,
                 self.record,
-                Self::menu_guide(),
+                self.menu_guide(),
                 "Menu",
                 "Record",
                 "You lost!",
-                Color::Red,
+                theme.failure,
             )?,
             GameState::Helping => self.display_game_rules(terminal)?,
             GameState::Pause => self.display_screen(
                 terminal,
                 self.board.consult_score(),
-                Self::play_guide(),
+                self.play_guide(),
                 "Game board",
                 "Score",
                 "Game is paused.",
-                Color::default(),
+                theme.text,
+            )?,
+            GameState::EnteringName => self.name_prompt.display(
+                terminal,
+                self.board.consult_score(),
+                &theme,
+                &self.settings.glyphs,
+            )?,
+            GameState::Leaderboard => display_leaderboard(
+                terminal,
+                &self.records.borrow(),
+                &Games::FlappyBird,
+                &modes(&Games::FlappyBird),
+                stat_title(&Games::FlappyBird),
+                &theme,
+                &self.settings.glyphs,
             )?,
             GameState::AskingToQuit => self.display_screen(
                 terminal,
@@ -174,7 +298,7 @@ impl GameManager for FlappyBirdGameManager {
                 "Quitting",
                 "Score",
                 "Are you sure you want to quit?",
-                Color::Yellow,
+                theme.warning,
             )?,
             GameState::Quitting => (),
         }
@@ -183,7 +307,7 @@ impl GameManager for FlappyBirdGameManager {
 
     fn reset(&mut self) {
         self.game_state = GameState::Starting;
-        self.board.reset_board();
+        self.board.reset_board(session_seed(self.settings.seed));
     }
 
     fn ended(&self) -> bool {
@@ -194,29 +318,51 @@ impl GameManager for FlappyBirdGameManager {
         self.kill_execution
     }
 
-    fn limit_fps(&self) {
-        std::thread::sleep(Duration::from_millis(1000 / self.fps));
+    fn force_quit(&mut self) {
+        self.kill_execution = true;
+    }
+
+    fn min_size(&self) -> (u16, u16) {
+        game_manager::min_size_for(
+            &self.board.display_board(
+                String::new(),
+                Color::Reset,
+                &self.settings.theme.borrow(),
+                &self.settings.glyphs,
+            ),
+            100,
+            80,
+        )
+    }
+
+    fn glyphs(&self) -> Glyphs {
+        self.settings.glyphs
     }
 }
 
 impl FlappyBirdGameManager {
-    pub fn new() -> Self {
+    pub fn new(records: SharedRecords, settings: GameSettings) -> Self {
+        let record = records.borrow().records().flappy_bird;
+        let fps = settings.options.fps.unwrap_or(FPS_CHANGE * 2);
         Self {
             game_state: GameState::Starting,
             menu_opt: MenuOpt::None,
             play_opt: PlayOpt::None,
             confirmed: false,
-            board: Board::new(),
-            record: 0,
-            fps: FPS_CHANGE * 2,
+            board: Board::new(session_seed(settings.seed)),
+            record,
+            records,
+            keymap: settings.keybindings.keymap(Games::FlappyBird.id()),
+            settings,
+            name_prompt: NamePrompt::new(),
+            fps,
             kill_execution: false,
+            resized: false,
+            help_area: Rect::default(),
         }
     }
 
-    fn display_game_rules(
-        &mut self,
-        terminal: &mut Terminal<CrosstermBackend<Stdout>>,
-    ) -> Result<()> {
+    fn display_game_rules<B: GameBackend>(&mut self, terminal: &mut Terminal<B>) -> Result<()> {
         let message = String::from(
             "Imagine you're a little bird trying to navigate through a series of pipes. The game 
 starts with you flying in the sky, and with each jump, you flap your wings and ascend 
@@ -236,26 +382,28 @@ navigate through the narrow gaps between the pipes.
 So, to sum it up: flap your wings to fly, avoid the pipes, and see how far you can go. 
 It's a simple yet surprisingly addictive game that'll keep you entertained for hours!",
         );
-        terminal.draw(|frame| {
+        draw(terminal, |frame| {
             let area = frame.area();
-            frame.render_widget(Paragraph::new(message).white(), area)
+            frame.render_widget(
+                Paragraph::new(message).fg(self.settings.theme.borrow().text),
+                area,
+            )
         })?;
         Ok(())
     }
 
-    fn menu_guide() -> String {
-        String::from(
-            "ENTER or p - Play\nf          - Decrease fps\nF          - Increase fps\n?          - Display game rules\nESC or q   - Go to main menu\n",
-        )
+    fn menu_guide(&self) -> String {
+        self.keymap.guide(MENU_ACTIONS, &self.settings.glyphs)
     }
 
-    fn play_guide() -> String {
-        String::from("w or k or  or SPACE - Jump\np                    - Pause game\nESC or q             - Go back to menu")
+    fn play_guide(&self) -> String {
+        self.keymap.guide(PLAY_ACTIONS, &self.settings.glyphs)
     }
 
-    fn display_screen(
+    #[allow(clippy::too_many_arguments)]
+    fn display_screen<B: GameBackend>(
         &mut self,
-        terminal: &mut Terminal<CrosstermBackend<Stdout>>,
+        terminal: &mut Terminal<B>,
         score: u32,
         help_message: String,
         title: &str,
@@ -263,7 +411,11 @@ It's a simple yet surprisingly addictive game that'll keep you entertained for h
         message: &str,
         color: Color,
     ) -> Result<()> {
-        terminal.draw(|frame| {
+        let theme = self.settings.theme.clone();
+        let theme = theme.borrow();
+        let glyphs = self.settings.glyphs;
+        let mut help_area = Rect::default();
+        draw(terminal, |frame| {
             let layout = Layout::default()
                 .direction(Direction::Vertical)
                 .constraints([Constraint::Percentage(80), Constraint::Fill(1)])
@@ -274,10 +426,18 @@ It's a simple yet surprisingly addictive game that'll keep you entertained for h
                 .split(layout[1]);
 
             frame.render_widget(
-                Paragraph::new(self.board.display_board(message.to_string(), color)).block(
+                Paragraph::new(self.board.display_board(
+                    message.to_string(),
+                    color,
+                    &theme,
+                    &glyphs,
+                ))
+                .block(
                     Block::new()
                         .borders(Borders::ALL)
+                        .border_set(glyphs.border)
                         .title(title)
+                        .title_bottom(format!("Seed {}", self.board.seed()))
                         .title_alignment(Alignment::Center),
                 ),
                 layout[0],
@@ -287,6 +447,7 @@ It's a simple yet surprisingly addictive game that'll keep you entertained for h
                 Paragraph::new(score.to_string()).block(
                     Block::new()
                         .borders(Borders::ALL)
+                        .border_set(glyphs.border)
                         .title(score_title)
                         .title_alignment(Alignment::Center),
                 ),
@@ -297,34 +458,47 @@ It's a simple yet surprisingly addictive game that'll keep you entertained for h
                 Paragraph::new(help_message).block(
                     Block::new()
                         .borders(Borders::ALL)
+                        .border_set(glyphs.border)
                         .title("Help")
                         .title_alignment(Alignment::Center),
                 ),
                 sub_layout[1],
             );
+            help_area = mouse::inner_area(sub_layout[1]);
         })?;
+        self.help_area = help_area;
         Ok(())
     }
 
-    fn read_menu_input(&mut self) -> Result<()> {
+    fn read_menu_input(&mut self, events: &mut dyn EventSource) -> Result<()> {
         loop {
-            let event = read()?;
-            if should_quit(&event) {
+            let event =
+                mouse::click_to_key(events.read()?, self.help_area, MENU_ACTIONS, &self.keymap);
+            if is_resize(&event) {
+                self.resized = true;
+                break;
+            } else if self.keymap.matches(Action::Quit, &event) {
                 self.menu_opt = MenuOpt::Quit;
                 break;
             } else if should_force_quit(&event) {
                 self.kill_execution = true;
                 break;
-            } else if should_help(&event) {
+            } else if self.keymap.matches(Action::Help, &event) {
                 self.menu_opt = MenuOpt::Help;
                 break;
-            } else if should_play(&event) {
+            } else if self.keymap.matches(Action::Leaderboard, &event) {
+                self.menu_opt = MenuOpt::Leaderboard;
+                break;
+            } else if self.keymap.matches(Action::Play, &event) {
                 self.menu_opt = MenuOpt::Play;
                 break;
-            } else if should_increase_fps(&event) {
+            } else if self.keymap.matches(Action::Continue, &event) {
+                self.menu_opt = MenuOpt::Continue;
+                break;
+            } else if self.keymap.matches(Action::IncreaseFps, &event) {
                 self.menu_opt = MenuOpt::IncreaseFPS;
                 break;
-            } else if should_decrease_fps(&event) {
+            } else if self.keymap.matches(Action::DecreaseFps, &event) {
                 self.menu_opt = MenuOpt::DecreaseFPS;
                 break;
             }
@@ -332,34 +506,56 @@ It's a simple yet surprisingly addictive game that'll keep you entertained for h
         Ok(())
     }
 
-    fn read_play_input(&mut self) -> Result<()> {
-        if poll(Duration::from_millis(50))? {
-            let event = read()?;
-            if should_move_up(&event)
-                || matches!(
-                    event,
-                    Event::Key(KeyEvent {
-                        code: KeyCode::Char(' '),
-                        modifiers: KeyModifiers::NONE,
-                        kind: KeyEventKind::Press,
-                        ..
-                    })
-                )
-            {
-                self.play_opt = PlayOpt::Jump;
-            } else if should_pause(&event) {
-                self.play_opt = PlayOpt::Pause;
-            } else if should_quit(&event) {
-                self.play_opt = PlayOpt::Quit;
-            } else if should_force_quit(&event) {
-                self.kill_execution = true;
-            }
-        } else {
-            self.play_opt = PlayOpt::None;
+    fn read_play_input(&mut self, events: &mut dyn EventSource) -> Result<()> {
+        let event = events.read()?;
+        if self.keymap.matches(Action::Jump, &event) {
+            self.play_opt = PlayOpt::Jump;
+        } else if self.keymap.matches(Action::Pause, &event) {
+            self.play_opt = PlayOpt::Pause;
+        } else if self.keymap.matches(Action::Quit, &event) {
+            self.play_opt = PlayOpt::Quit;
+        } else if should_force_quit(&event) {
+            self.kill_execution = true;
         }
         Ok(())
     }
 
+    fn suspend_game(&mut self) -> Result<()> {
+        self.records.borrow_mut().store_saved_game(
+            &Games::FlappyBird,
+            SavedGame {
+                board: self.board.clone(),
+                fps: self.fps,
+            },
+        )
+    }
+
+    // The game is resumed paused, so the player has time to get ready.
+    fn resume_game(&mut self) {
+        let saved = self
+            .records
+            .borrow_mut()
+            .take_saved_game::<SavedGame>(&Games::FlappyBird);
+        if let Some(saved) = saved {
+            self.board = saved.board;
+            self.fps = saved.fps;
+            self.game_state = GameState::Pause;
+        }
+    }
+
+    fn finish_game(&mut self) {
+        if self.records.borrow().qualifies_for_leaderboard(
+            &Games::FlappyBird,
+            DEFAULT_MODE,
+            self.board.consult_score(),
+        ) {
+            self.name_prompt.clear();
+            self.game_state = GameState::EnteringName;
+        } else {
+            self.game_state = GameState::Lost;
+        }
+    }
+
     fn increase_fps(&mut self) {
         self.fps += FPS_CHANGE;
     }
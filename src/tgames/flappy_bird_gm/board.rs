@@ -1,7 +1,11 @@
+use super::super::game_manager::{new_rng, serde_rng, serde_seed, GameRng};
+use super::super::{glyphs::Glyphs, theme::Theme};
+use rand::Rng;
 use ratatui::{
     style::{Color, Style},
     text::{Line, Span},
 };
+use serde::{Deserialize, Serialize};
 use std::collections::LinkedList;
 
 const NUMBER_OF_PIPES: i32 = 10; // Should not be smaller than 2
@@ -13,6 +17,7 @@ const JUMP: i32 = 2;
 const BOARD_HEIGHT: i32 = 20;
 const BOARD_WIDTH: i32 = (PIPE_WIDTH + PIPES_DISTANCE) * NUMBER_OF_PIPES;
 
+#[derive(Clone, Serialize, Deserialize)]
 pub struct Board {
     score: u32,
     bird_height: i32,
@@ -21,10 +26,20 @@ pub struct Board {
     died_horizontally: bool,
     died_vertically: bool,
     in_pipe: i32,
+    #[serde(with = "serde_seed")]
+    seed: u64,
+    #[serde(with = "serde_rng")]
+    rng: GameRng,
+}
+
+impl Default for Board {
+    fn default() -> Self {
+        Self::new(rand::random())
+    }
 }
 
 impl Board {
-    pub fn new() -> Self {
+    pub fn new(seed: u64) -> Self {
         let mut board = Self {
             score: 0,
             bird_height: BOARD_HEIGHT / 2,
@@ -33,12 +48,20 @@ impl Board {
             died_horizontally: false,
             died_vertically: false,
             in_pipe: 0,
+            seed,
+            rng: new_rng(seed),
         };
-        board.reset_board();
+        board.reset_board(seed);
         board
     }
 
-    pub fn reset_board(&mut self) {
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    pub fn reset_board(&mut self, seed: u64) {
+        self.seed = seed;
+        self.rng = new_rng(seed);
         self.pipe_holes.clear();
         self.pipe_holes.push_front(BOARD_HEIGHT / 2);
         while self.pipe_holes.len() < NUMBER_OF_PIPES as usize {
@@ -92,44 +115,56 @@ impl Board {
         self.score
     }
 
-    fn push_horizontal_board(lines: &mut Vec<Line>, up: bool) {
+    fn push_horizontal_board(lines: &mut Vec<Line>, up: bool, theme: &Theme, glyphs: &Glyphs) {
         let mut spans: String = String::new();
         if up {
-            spans += "╭";
+            spans += glyphs.line.top_left;
         } else {
-            spans += "╰";
+            spans += glyphs.line.bottom_left;
         }
         for _ in 0..BOARD_WIDTH + PIPES_DISTANCE {
-            spans += "─";
+            spans += glyphs.line.horizontal;
         }
         if up {
-            spans += "╮";
+            spans += glyphs.line.top_right;
         } else {
-            spans += "╯";
+            spans += glyphs.line.bottom_right;
         }
         lines.push(Line::from(Span::styled(
             spans,
-            Style::default().fg(Color::DarkGray),
+            Style::default().fg(theme.dim),
         )));
     }
 
-    pub fn display_board(&self, message: String, color: Color) -> Vec<Line<'_>> {
+    pub fn display_board(
+        &self,
+        message: String,
+        color: Color,
+        theme: &Theme,
+        glyphs: &Glyphs,
+    ) -> Vec<Line<'_>> {
         let mut lines: Vec<Line> = Vec::new();
-        Self::push_horizontal_board(&mut lines, true);
+        Self::push_horizontal_board(&mut lines, true, theme, glyphs);
         for line in (0..BOARD_HEIGHT).rev() {
             let mut spans: Vec<Span> = Vec::new();
-            spans.push(Span::styled("│", Style::default().fg(Color::DarkGray)));
+            spans.push(Span::styled(
+                glyphs.line.vertical,
+                Style::default().fg(theme.dim),
+            ));
             let mut number_of_spaces = self.distance_to_next_pipe;
             if line == self.bird_height {
                 if self.died_vertically || self.died_horizontally {
-                    spans.push(Span::styled("󰯈", Style::default().fg(Color::Red)));
+                    spans.push(Span::styled(
+                        glyphs.dead_bird,
+                        Style::default().fg(theme.dead_bird),
+                    ));
                 } else {
-                    spans.push(Span::styled("󱗆", Style::default().fg(Color::Blue)));
+                    spans.push(Span::styled(glyphs.bird, Style::default().fg(theme.bird)));
                 }
                 number_of_spaces -= 1;
             }
             for _ in 0..number_of_spaces {
-                spans.push(Span::styled(" ", Style::default().fg(Color::Green)));
+                spans.push(Span::styled(" ", Style::default().fg(theme.pipes)));
             }
             let mut first_pipe = true;
             for hole in self.pipe_holes.iter() {
@@ -142,7 +177,7 @@ impl Board {
                         0
                     };
                     for _ in start..PIPE_WIDTH {
-                        spans.push(Span::styled("█", Style::default().fg(Color::Green)));
+                        spans.push(Span::styled(glyphs.block, Style::default().fg(theme.pipes)));
                     }
                 } else {
                     let start = if first_pipe
@@ -156,21 +191,24 @@ impl Board {
                         0
                     };
                     for _ in start..PIPE_WIDTH {
-                        spans.push(Span::styled(" ", Style::default().fg(Color::Gray)));
+                        spans.push(Span::styled(" ", Style::default()));
                     }
                 }
                 for _ in 0..PIPES_DISTANCE {
-                    spans.push(Span::styled(" ", Style::default().fg(Color::Gray)));
+                    spans.push(Span::styled(" ", Style::default()));
                 }
                 first_pipe = false;
             }
             for _ in self.distance_to_next_pipe..PIPES_DISTANCE + self.in_pipe {
-                spans.push(Span::styled(" ", Style::default().fg(Color::Gray)));
+                spans.push(Span::styled(" ", Style::default()));
             }
-            spans.push(Span::styled("│", Style::default().fg(Color::DarkGray)));
+            spans.push(Span::styled(
+                glyphs.line.vertical,
+                Style::default().fg(theme.dim),
+            ));
             lines.push(Line::from(spans));
         }
-        Self::push_horizontal_board(&mut lines, false);
+        Self::push_horizontal_board(&mut lines, false, theme, glyphs);
         if !message.is_empty() {
             lines.push(Line::from(Span::styled(message, color)));
             lines.push(Line::from(Span::styled(
@@ -182,7 +220,9 @@ impl Board {
     }
 
     fn generate_pipe(&mut self) {
-        let delta = rand::random_range(-MAX_DISTANCE_BETWEEN_HOLES..MAX_DISTANCE_BETWEEN_HOLES);
+        let delta = self
+            .rng
+            .random_range(-MAX_DISTANCE_BETWEEN_HOLES..MAX_DISTANCE_BETWEEN_HOLES);
         let new_pos = match self.pipe_holes.back() {
             Some(value) => *value + delta,
             None => BOARD_HEIGHT / 2,
@@ -1,22 +1,27 @@
-mod board;
+pub mod board;
 
 use self::board::Board;
 use super::{
     super::input::{
-        read_confirmation, read_key, should_force_quit, should_help, should_move_down,
-        should_move_left, should_move_right, should_move_up, should_play, should_quit,
+        event_source::EventSource,
+        is_resize,
+        keybindings::{Action, ActionGuide, Keymap},
+        mouse, read_confirmation, read_key, should_force_quit,
     },
-    game_manager::{self, Directions, GameManager},
+    game_manager::{self, draw, session_seed, Directions, GameBackend, GameManager, GameSettings},
+    glyphs::Glyphs,
+    leaderboard::{display_leaderboard, modes, stat_title, NamePrompt, NamePromptStatus},
+    records::{LeaderboardEntry, SharedRecords, DEFAULT_MODE},
+    Games,
 };
-use crossterm::event::read;
 use ratatui::{
-    backend::CrosstermBackend,
-    layout::{Alignment, Constraint, Direction, Layout},
+    layout::{Alignment, Constraint, Direction, Layout, Rect},
     style::{Color, Stylize},
     widgets::{Block, Borders, Paragraph},
     Terminal,
 };
-use std::io::{Result, Stdout};
+use serde::{Deserialize, Serialize};
+use std::io::Result;
 
 enum PlayOpt {
     Direction(Directions),
@@ -24,10 +29,28 @@ enum PlayOpt {
     Quit,
 }
 
+pub const MENU_ACTIONS: ActionGuide = &[
+    (Action::Play, "Play"),
+    (Action::Continue, "Continue saved game"),
+    (Action::Help, "Display game rules"),
+    (Action::Leaderboard, "Display leaderboard"),
+    (Action::Quit, "Go to main menu"),
+];
+
+pub const PLAY_ACTIONS: ActionGuide = &[
+    (Action::MoveUp, "Move up"),
+    (Action::MoveDown, "Move down"),
+    (Action::MoveRight, "Move right"),
+    (Action::MoveLeft, "Move left"),
+    (Action::Quit, "Go to menu"),
+];
+
 enum MenuOpt {
     Play,
+    Continue,
     Quit,
     Help,
+    Leaderboard,
     None,
 }
 
@@ -38,31 +61,54 @@ enum GameState {
     Playing,
     Helping,
     Lost,
+    EnteringName,
+    Leaderboard,
     AskingToQuit,
     Quitting,
 }
 
+// Everything needed to resume a game left in the middle.
+#[derive(Serialize, Deserialize)]
+struct SavedGame {
+    board: Board,
+}
+
 pub struct G2048GameManager {
     game_state: GameState,
     menu_opt: MenuOpt,
     play_opts: PlayOpt,
     confirmed: bool,
     record: u32,
+    records: SharedRecords,
+    keymap: Keymap,
+    settings: GameSettings,
+    name_prompt: NamePrompt,
     board: Board,
     kill_execution: bool,
+    resized: bool,
+    // Where the key guide was last drawn, so its entries can be clicked.
+    help_area: Rect,
 }
 
-impl GameManager for G2048GameManager {
-    fn process_events(&mut self) -> Result<()> {
+impl<B: GameBackend> GameManager<B> for G2048GameManager {
+    fn process_events(&mut self, events: &mut dyn EventSource) -> Result<()> {
         match self.game_state {
             GameState::Starting => (),
-            GameState::Helping => read_key()?,
-            GameState::Menu | GameState::Lost => self.read_menu_input()?,
-            GameState::Playing => self.read_play_input()?,
+            GameState::Helping | GameState::Leaderboard => {
+                self.resized = is_resize(&read_key(events)?)
+            }
+            GameState::Menu | GameState::Lost => self.read_menu_input(events)?,
+            GameState::Playing => self.read_play_input(events)?,
+            GameState::EnteringName => {
+                let event = events.read()?;
+                self.kill_execution = should_force_quit(&event);
+                self.name_prompt.handle_event(&event);
+            }
             GameState::AskingToQuit => {
-                let event = read()?;
+                let event = events.read()?;
                 self.kill_execution = should_force_quit(&event);
                 self.confirmed = read_confirmation(&event);
+                self.resized = is_resize(&event);
             }
             GameState::Quitting => (),
         }
@@ -70,21 +116,41 @@ impl GameManager for G2048GameManager {
     }
 
     fn update(&mut self) -> Result<()> {
+        // A resize only needs the screen drawn again.
+        if self.resized {
+            self.resized = false;
+            return Ok(());
+        }
         if self.kill_execution {
+            if matches!(
+                self.game_state,
+                GameState::Playing | GameState::AskingToQuit
+            ) {
+                self.suspend_game()?;
+            }
             self.game_state = GameState::Quitting;
         }
         match self.game_state {
-            GameState::Starting => self.game_state = GameState::Playing,
-            GameState::Helping => self.game_state = GameState::Menu,
+            GameState::Starting => {
+                // Let the player choose between the suspended game and a new one.
+                if self.records.borrow().has_saved_game(&Games::G2048) {
+                    self.game_state = GameState::Menu;
+                } else {
+                    self.game_state = GameState::Playing;
+                }
+            }
+            GameState::Helping | GameState::Leaderboard => self.game_state = GameState::Menu,
             GameState::Menu | GameState::Lost => match self.menu_opt {
                 MenuOpt::Play => {
                     if matches!(self.game_state, GameState::Lost) {
-                        self.board.reset_board();
+                        self.board.reset_board(session_seed(self.settings.seed));
                     }
                     self.board.start_game();
                     self.game_state = GameState::Playing;
                 }
+                MenuOpt::Continue => self.resume_game(),
                 MenuOpt::Help => self.game_state = GameState::Helping,
+                MenuOpt::Leaderboard => self.game_state = GameState::Leaderboard,
                 MenuOpt::Quit => self.game_state = GameState::Quitting,
                 MenuOpt::None => (),
             },
@@ -97,15 +163,36 @@ impl GameManager for G2048GameManager {
                     PlayOpt::None => (),
                 }
                 if self.board.defeated() {
-                    self.game_state = GameState::Lost;
+                    self.finish_game();
                 }
                 if self.record < self.board.consult_score() {
                     self.record = self.board.consult_score();
+                    let record = self.record;
+                    self.records
+                        .borrow_mut()
+                        .update(|records| records.g2048 = record);
                 }
             }
+            GameState::EnteringName => match self.name_prompt.status() {
+                NamePromptStatus::Typing => (),
+                NamePromptStatus::Confirmed => {
+                    self.records.borrow_mut().add_leaderboard_entry(
+                        &Games::G2048,
+                        DEFAULT_MODE,
+                        LeaderboardEntry::new(
+                            self.name_prompt.name(),
+                            self.board.consult_score(),
+                            Some(self.board.consult_number_of_moves()),
+                        ),
+                    );
+                    self.game_state = GameState::Lost;
+                }
+                NamePromptStatus::Skipped => self.game_state = GameState::Lost,
+            },
             GameState::AskingToQuit => match self.confirmed {
                 true => {
-                    self.board.reset_board();
+                    self.suspend_game()?;
+                    self.board.reset_board(session_seed(self.settings.seed));
                     self.game_state = GameState::Menu;
                 }
                 false => self.game_state = GameState::Playing,
@@ -115,36 +202,53 @@ impl GameManager for G2048GameManager {
         Ok(())
     }
 
-    fn render(&mut self, terminal: &mut Terminal<CrosstermBackend<Stdout>>) -> Result<()> {
+    fn render(&mut self, terminal: &mut Terminal<B>) -> Result<()> {
+        let theme = self.settings.theme.clone();
+        let theme = theme.borrow();
         match self.game_state {
             GameState::Starting => (),
             GameState::Helping => self.display_game_rules(terminal)?,
             GameState::Menu => self.display_screen(
                 terminal,
                 self.record,
-                G2048GameManager::menu_guide(),
+                self.menu_guide(),
                 "Menu",
                 "Record",
                 "",
-                Color::Gray,
+                theme.text,
             )?,
             GameState::Playing => self.display_screen(
                 terminal,
                 self.board.consult_score(),
-                G2048GameManager::play_guide(),
+                self.play_guide(),
                 "Game board",
                 "Score",
                 "",
-                Color::Gray,
+                theme.text,
             )?,
             GameState::Lost => self.display_screen(
                 terminal,
                 self.record,
-                G2048GameManager::menu_guide(),
+                self.menu_guide(),
                 "Menu",
                 "Record",
                 "You Lost!",
-                Color::Red,
+                theme.failure,
+            )?,
+            GameState::EnteringName => self.name_prompt.display(
+                terminal,
+                self.board.consult_score(),
+                &theme,
+                &self.settings.glyphs,
+            )?,
+            GameState::Leaderboard => display_leaderboard(
+                terminal,
+                &self.records.borrow(),
+                &Games::G2048,
+                &modes(&Games::G2048),
+                stat_title(&Games::G2048),
+                &theme,
+                &self.settings.glyphs,
             )?,
             GameState::AskingToQuit => self.display_screen(
                 terminal,
@@ -153,7 +257,7 @@ impl GameManager for G2048GameManager {
                 "Quitting",
                 "Score",
                 "Are you sure you want to quit?",
-                Color::Yellow,
+                theme.warning,
             )?,
             GameState::Quitting => (),
         }
@@ -162,7 +266,7 @@ impl GameManager for G2048GameManager {
 
     fn reset(&mut self) {
         self.game_state = GameState::Starting;
-        self.board.reset_board();
+        self.board.reset_board(session_seed(self.settings.seed));
         self.board.start_game();
     }
 
@@ -173,36 +277,61 @@ impl GameManager for G2048GameManager {
     fn kill_execution(&self) -> bool {
         self.kill_execution
     }
+
+    fn force_quit(&mut self) {
+        self.kill_execution = true;
+    }
+
+    fn min_size(&self) -> (u16, u16) {
+        game_manager::min_size_for(
+            &self.board.display_board(
+                String::new(),
+                Color::Reset,
+                &self.settings.theme.borrow(),
+                &self.settings.glyphs,
+            ),
+            50,
+            100,
+        )
+    }
+
+    fn glyphs(&self) -> Glyphs {
+        self.settings.glyphs
+    }
 }
 
 impl G2048GameManager {
-    pub fn new() -> Self {
+    pub fn new(records: SharedRecords, settings: GameSettings) -> Self {
+        let record = records.borrow().records().g2048;
         Self {
             game_state: GameState::Starting,
             menu_opt: MenuOpt::None,
             play_opts: PlayOpt::None,
             confirmed: false,
-            record: 0,
-            board: Board::new(),
+            record,
+            records,
+            name_prompt: NamePrompt::new(),
+            board: Board::new(session_seed(settings.seed)),
+            keymap: settings.keybindings.keymap(Games::G2048.id()),
+            settings,
             kill_execution: false,
+            resized: false,
+            help_area: Rect::default(),
         }
     }
 
-    fn menu_guide() -> String {
-        String::from(
-            "ENTER or p - Play\n?          - Display game rules\nESC or q   - Go to main menu\n",
-        )
+    fn menu_guide(&self) -> String {
+        self.keymap.guide(MENU_ACTIONS, &self.settings.glyphs)
     }
 
-    fn play_guide() -> String {
-        String::from(
-            "w or k or    - Move up\ns or j or    - Move down\nd or l or    - Move right\na or h or    - Move left\nESC or q      - Go to menu",
-        )
+    fn play_guide(&self) -> String {
+        self.keymap.guide(PLAY_ACTIONS, &self.settings.glyphs)
     }
 
-    fn display_screen(
+    #[allow(clippy::too_many_arguments)]
+    fn display_screen<B: GameBackend>(
         &mut self,
-        terminal: &mut Terminal<CrosstermBackend<Stdout>>,
+        terminal: &mut Terminal<B>,
         score: u32,
         help_message: String,
         title: &str,
@@ -210,7 +339,11 @@ impl G2048GameManager {
         message: &str,
         color: Color,
     ) -> Result<()> {
-        terminal.draw(|frame| {
+        let theme = self.settings.theme.clone();
+        let theme = theme.borrow();
+        let glyphs = self.settings.glyphs;
+        let mut help_area = Rect::default();
+        draw(terminal, |frame| {
             let layout = Layout::default()
                 .direction(Direction::Horizontal)
                 .constraints([Constraint::Percentage(50), Constraint::Percentage(50)])
@@ -225,10 +358,18 @@ impl G2048GameManager {
                 .split(layout[1]);
 
             frame.render_widget(
-                Paragraph::new(self.board.display_board(message.to_string(), color)).block(
+                Paragraph::new(self.board.display_board(
+                    message.to_string(),
+                    color,
+                    &theme,
+                    &glyphs,
+                ))
+                .block(
                     Block::new()
                         .borders(Borders::ALL)
+                        .border_set(glyphs.border)
                         .title(title)
+                        .title_bottom(format!("Seed {}", self.board.seed()))
                         .title_alignment(Alignment::Center),
                 ),
                 layout[0],
@@ -238,6 +379,7 @@ impl G2048GameManager {
                 Paragraph::new(score.to_string()).block(
                     Block::new()
                         .borders(Borders::ALL)
+                        .border_set(glyphs.border)
                         .title(score_title)
                         .title_alignment(Alignment::Center),
                 ),
@@ -248,6 +390,7 @@ impl G2048GameManager {
                 Paragraph::new(self.board.consult_number_of_moves().to_string()).block(
                     Block::new()
                         .borders(Borders::ALL)
+                        .border_set(glyphs.border)
                         .title("Number of moves")
                         .title_alignment(Alignment::Center),
                 ),
@@ -258,19 +401,19 @@ impl G2048GameManager {
                 Paragraph::new(help_message).block(
                     Block::new()
                         .borders(Borders::ALL)
+                        .border_set(glyphs.border)
                         .title("Help")
                         .title_alignment(Alignment::Center),
                 ),
                 sub_layout[2],
             );
+            help_area = mouse::inner_area(sub_layout[2]);
         })?;
+        self.help_area = help_area;
         Ok(())
     }
 
-    fn display_game_rules(
-        &mut self,
-        terminal: &mut Terminal<CrosstermBackend<Stdout>>,
-    ) -> Result<()> {
+    fn display_game_rules<B: GameBackend>(&mut self, terminal: &mut Terminal<B>) -> Result<()> {
         let message = String::from(
             "Picture a tiny grid filled with numbered tiles, and your job is to combine them to 
 reach the elusive number 2048.
@@ -293,49 +436,98 @@ see if you can reach that elusive 2048 tile!
 It's a simple yet addictive puzzle that'll have you sliding tiles and chasing that 
 elusive number for hours on end. Enjoy the challenge!",
         );
-        terminal.draw(|frame| {
+        draw(terminal, |frame| {
             let area = frame.area();
-            frame.render_widget(Paragraph::new(message).white(), area);
+            frame.render_widget(
+                Paragraph::new(message).fg(self.settings.theme.borrow().text),
+                area,
+            );
         })?;
         Ok(())
     }
 
-    fn read_menu_input(&mut self) -> Result<()> {
+    fn suspend_game(&mut self) -> Result<()> {
+        self.records.borrow_mut().store_saved_game(
+            &Games::G2048,
+            SavedGame {
+                board: self.board.clone(),
+            },
+        )
+    }
+
+    fn resume_game(&mut self) {
+        let saved = self
+            .records
+            .borrow_mut()
+            .take_saved_game::<SavedGame>(&Games::G2048);
+        if let Some(saved) = saved {
+            self.board = saved.board;
+            self.game_state = GameState::Playing;
+        }
+    }
+
+    fn finish_game(&mut self) {
+        if self.records.borrow().qualifies_for_leaderboard(
+            &Games::G2048,
+            DEFAULT_MODE,
+            self.board.consult_score(),
+        ) {
+            self.name_prompt.clear();
+            self.game_state = GameState::EnteringName;
+        } else {
+            self.game_state = GameState::Lost;
+        }
+    }
+
+    fn read_menu_input(&mut self, events: &mut dyn EventSource) -> Result<()> {
         loop {
-            let event = read()?;
-            if should_quit(&event) {
+            let event =
+                mouse::click_to_key(events.read()?, self.help_area, MENU_ACTIONS, &self.keymap);
+            if is_resize(&event) {
+                self.resized = true;
+                break;
+            } else if self.keymap.matches(Action::Quit, &event) {
                 self.menu_opt = MenuOpt::Quit;
                 break;
             } else if should_force_quit(&event) {
                 self.kill_execution = true;
                 break;
-            } else if should_play(&event) {
+            } else if self.keymap.matches(Action::Play, &event) {
                 self.menu_opt = MenuOpt::Play;
                 break;
-            } else if should_help(&event) {
+            } else if self.keymap.matches(Action::Continue, &event) {
+                self.menu_opt = MenuOpt::Continue;
+                break;
+            } else if self.keymap.matches(Action::Help, &event) {
                 self.menu_opt = MenuOpt::Help;
                 break;
+            } else if self.keymap.matches(Action::Leaderboard, &event) {
+                self.menu_opt = MenuOpt::Leaderboard;
+                break;
             }
         }
         Ok(())
     }
 
-    fn read_play_input(&mut self) -> Result<()> {
+    fn read_play_input(&mut self, events: &mut dyn EventSource) -> Result<()> {
         loop {
-            let event = read()?;
-            if should_move_up(&event) {
+            let event = events.read()?;
+            if is_resize(&event) {
+                self.resized = true;
+                break;
+            } else if self.keymap.matches(Action::MoveUp, &event) {
                 self.play_opts = PlayOpt::Direction(Directions::Up);
                 break;
-            } else if should_move_down(&event) {
+            } else if self.keymap.matches(Action::MoveDown, &event) {
                 self.play_opts = PlayOpt::Direction(Directions::Down);
                 break;
-            } else if should_move_left(&event) {
+            } else if self.keymap.matches(Action::MoveLeft, &event) {
                 self.play_opts = PlayOpt::Direction(Directions::Left);
                 break;
-            } else if should_move_right(&event) {
+            } else if self.keymap.matches(Action::MoveRight, &event) {
                 self.play_opts = PlayOpt::Direction(Directions::Right);
                 break;
-            } else if should_quit(&event) {
+            } else if self.keymap.matches(Action::Quit, &event) {
                 self.play_opts = PlayOpt::Quit;
                 break;
             } else if should_force_quit(&event) {
@@ -1,31 +1,53 @@
-use super::super::game_manager::Directions;
+use super::super::game_manager::{new_rng, serde_rng, serde_seed, Directions, GameRng};
+use super::super::{glyphs::Glyphs, theme::Theme};
+use rand::Rng;
 use ratatui::style::{Color, Style};
 use ratatui::text::{Line, Span};
+use serde::{Deserialize, Serialize};
 
 const NUMBER_OF_COLUMNS: usize = 4;
 const NUMBER_OF_LINES: usize = 4;
 
+#[derive(Clone, Serialize, Deserialize)]
 pub struct Board {
     board: Vec<u32>,
     number_of_moves: u32,
     score: u32,
     lost: bool,
+    #[serde(with = "serde_seed")]
+    seed: u64,
+    #[serde(with = "serde_rng")]
+    rng: GameRng,
+}
+
+impl Default for Board {
+    fn default() -> Self {
+        Self::new(rand::random())
+    }
 }
 
 impl Board {
-    pub fn new() -> Self {
+    pub fn new(seed: u64) -> Self {
         let mut board = Self {
             board: Vec::with_capacity(NUMBER_OF_LINES * NUMBER_OF_COLUMNS),
             number_of_moves: 0,
             score: 0,
             lost: false,
+            seed,
+            rng: new_rng(seed),
         };
         board.board.resize(NUMBER_OF_LINES * NUMBER_OF_COLUMNS, 0);
         board.start_game();
         board
     }
 
-    pub fn reset_board(&mut self) {
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    pub fn reset_board(&mut self, seed: u64) {
+        self.seed = seed;
+        self.rng = new_rng(seed);
         for index in 0..(NUMBER_OF_LINES * NUMBER_OF_COLUMNS) {
             self.board[index] = 0;
         }
@@ -56,42 +78,51 @@ impl Board {
         }
     }
 
-    pub fn display_board(&self, message: String, color: Color) -> Vec<Line<'_>> {
+    pub fn display_board(
+        &self,
+        message: String,
+        color: Color,
+        theme: &Theme,
+        glyphs: &Glyphs,
+    ) -> Vec<Line<'_>> {
         let mut lines: Vec<Line> = Vec::new();
         let mut spans: String = String::new();
-        spans += "╭";
+        spans += glyphs.line.top_left;
         for counter in 1..NUMBER_OF_COLUMNS * 7 {
             if counter % 7 == 0 {
-                spans += "┬";
+                spans += glyphs.line.horizontal_down;
             } else {
-                spans += "─";
+                spans += glyphs.line.horizontal;
             }
         }
-        spans += "╮";
+        spans += glyphs.line.top_right;
         lines.push(Line::from(Span::styled(
             spans,
-            Style::default().fg(Color::DarkGray),
+            Style::default().fg(theme.dim),
         )));
         let mut separator_line: String = String::new();
-        separator_line += "│";
+        separator_line += glyphs.line.vertical;
         for counter in 1..NUMBER_OF_COLUMNS * 7 {
             if counter % 7 == 0 {
-                separator_line += "┼";
+                separator_line += glyphs.line.cross;
             } else {
-                separator_line += "─";
+                separator_line += glyphs.line.horizontal;
             }
         }
-        separator_line += "│";
+        separator_line += glyphs.line.vertical;
         for line in 0..NUMBER_OF_LINES {
             if line != 0 {
                 lines.push(Line::from(Span::styled(
                     separator_line.clone(),
-                    Style::default().fg(Color::DarkGray),
+                    Style::default().fg(theme.dim),
                 )));
             }
             let mut spans: Vec<Span> = Vec::new();
 
-            spans.push(Span::styled("│", Style::default().fg(Color::DarkGray)));
+            spans.push(Span::styled(
+                glyphs.line.vertical,
+                Style::default().fg(theme.dim),
+            ));
             for column in 0..NUMBER_OF_COLUMNS {
                 let value = self.consult_board(line, column);
                 let number_of_spaces = if value == 0 {
@@ -106,46 +137,27 @@ impl Board {
                 if value != 0 {
                     word += &value.to_string();
                 }
-                match value {
-                    2 | 128 | 8192 => {
-                        spans.push(Span::styled(word, Style::default().fg(Color::Red)));
-                    }
-                    4 | 256 | 16384 => {
-                        spans.push(Span::styled(word, Style::default().fg(Color::Yellow)));
-                    }
-                    8 | 512 | 32768 => {
-                        spans.push(Span::styled(word, Style::default().fg(Color::Magenta)));
-                    }
-                    16 | 1024 | 65536 => {
-                        spans.push(Span::styled(word, Style::default().fg(Color::Blue)));
-                    }
-                    32 | 2048 | 131072 => {
-                        spans.push(Span::styled(word, Style::default().fg(Color::Cyan)));
-                    }
-                    64 | 4096 => {
-                        spans.push(Span::styled(word, Style::default().fg(Color::Green)));
-                    }
-                    _ => {
-                        spans.push(Span::styled(word, Style::default()));
-                    }
-                }
-                spans.push(Span::styled("│", Style::default().fg(Color::DarkGray)));
+                spans.push(Span::styled(word, Style::default().fg(theme.tile(value))));
+                spans.push(Span::styled(
+                    glyphs.line.vertical,
+                    Style::default().fg(theme.dim),
+                ));
             }
             lines.push(Line::from(spans));
         }
         let mut spans: String = String::new();
-        spans += "╰";
+        spans += glyphs.line.bottom_left;
         for counter in 1..NUMBER_OF_COLUMNS * 7 {
             if counter % 7 == 0 {
-                spans += "┴";
+                spans += glyphs.line.horizontal_up;
             } else {
-                spans += "─";
+                spans += glyphs.line.horizontal;
             }
         }
-        spans += "╯";
+        spans += glyphs.line.bottom_right;
         lines.push(Line::from(Span::styled(
             spans,
-            Style::default().fg(Color::DarkGray),
+            Style::default().fg(theme.dim),
         )));
         if !message.is_empty() {
             lines.push(Line::from(Span::styled(message, color)));
@@ -292,12 +304,14 @@ impl Board {
     }
 
     fn generate_block(&mut self) {
-        let mut index = rand::random_range(0..(NUMBER_OF_LINES * NUMBER_OF_COLUMNS));
+        let mut index = self
+            .rng
+            .random_range(0..(NUMBER_OF_LINES * NUMBER_OF_COLUMNS));
         while self.board[index] != 0 {
             index += 1;
             index %= NUMBER_OF_LINES * NUMBER_OF_COLUMNS;
         }
-        let random = rand::random_range(0..10);
+        let random = self.rng.random_range(0..10);
         if random == 4 {
             self.board[index] = 4;
         } else {
@@ -305,7 +319,7 @@ impl Board {
         }
     }
 
-    fn consult_board(&self, line: usize, column: usize) -> u32 {
+    pub fn consult_board(&self, line: usize, column: usize) -> u32 {
         self.board[line * NUMBER_OF_COLUMNS + column]
     }
 
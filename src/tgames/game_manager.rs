@@ -1,40 +1,388 @@
-use ratatui::{backend::CrosstermBackend, Terminal};
-use std::io::Result;
-use std::io::Stdout;
+use super::{
+    glyphs::Glyphs,
+    theme::{SharedTheme, Themes},
+};
+use crate::input::{event_source::EventSource, keybindings::Keybindings, should_force_quit};
+use rand::SeedableRng;
+use rand_chacha::ChaCha12Rng;
+use ratatui::{
+    backend::Backend,
+    layout::{Alignment, Constraint, Flex, Layout},
+    style::{Color, Style},
+    text::Line,
+    widgets::{Block, Borders, Paragraph},
+    Frame, Terminal,
+};
+use serde::{de::Error as _, Deserialize, Deserializer, Serialize, Serializer};
+use std::{
+    io::{Error, Result},
+    time::{Duration, Instant},
+};
+use strum_macros::{Display, EnumIter};
 
-pub trait GameManager {
-    fn run(&mut self, terminal: &mut Terminal<CrosstermBackend<Stdout>>) -> Result<bool> {
+// Any ratatui backend the games can draw on, its errors are reported as io errors.
+pub trait GameBackend: Backend<Error: Send + Sync + 'static> {}
+impl<B: Backend<Error: Send + Sync + 'static>> GameBackend for B {}
+
+// Shortest time between two frames drawn because of input while the game is ticking, the ticks
+// themselves are always drawn.
+const MIN_FRAME_INTERVAL: Duration = Duration::from_millis(16);
+// Ticks further behind than this are dropped instead of being caught up all at once, like after
+// the machine was suspended.
+const MAX_TICK_LAG: u32 = 5;
+
+pub trait GameManager<B: GameBackend> {
+    fn run(
+        &mut self,
+        terminal: &mut Terminal<B>,
+        events: &mut dyn EventSource,
+        frames: &mut FrameStats,
+    ) -> Result<bool> {
+        let mut clock: Option<TickClock> = None;
+        let mut last_frame: Option<Instant> = None;
+        let mut too_small = false;
         while !self.ended() {
-            self.process_events()?;
-            self.update()?;
-            self.render(terminal)?;
-            self.limit_fps();
+            let min_size = self.min_size();
+            let size = screen_size(terminal, events)?;
+            if size.0 < min_size.0 || size.1 < min_size.1 {
+                // Nothing advances until the window is large enough again, so the timed games
+                // stay paused meanwhile.
+                if !too_small {
+                    self.pause();
+                }
+                too_small = true;
+                clock = None;
+                display_too_small(terminal, min_size, size, &self.glyphs())?;
+                if should_force_quit(&events.read()?) {
+                    self.force_quit();
+                    self.update()?;
+                }
+                continue;
+            }
+            if too_small {
+                too_small = false;
+                frames.measure(|| self.render(terminal))?;
+            }
+            let ticked = match self.tick_rate() {
+                // The game only changes on input, so just wait for it.
+                None => {
+                    clock = None;
+                    self.process_events(events)?;
+                    self.update()?;
+                    true
+                }
+                // Input is handled as soon as it arrives and the game advances at a fixed rate,
+                // however much input there is. A poll that times out is exactly one tick, which
+                // keeps recordings and replays in step with the original session.
+                Some(rate) => {
+                    let clock = clock.get_or_insert_with(|| TickClock::new(rate));
+                    if events.poll(clock.until_next_tick(rate))? {
+                        self.process_events(events)?;
+                        self.update()?;
+                        false
+                    } else {
+                        clock.tick(rate);
+                        self.tick()?;
+                        true
+                    }
+                }
+            };
+            if ticked || last_frame.is_none_or(|frame| frame.elapsed() >= MIN_FRAME_INTERVAL) {
+                frames.measure(|| self.render(terminal))?;
+                last_frame = Some(Instant::now());
+            }
         }
         self.reset();
         Ok(self.kill_execution())
     }
-    fn process_events(&mut self) -> Result<()>;
+    fn process_events(&mut self, events: &mut dyn EventSource) -> Result<()>;
+    // Applies the input read by `process_events`.
     fn update(&mut self) -> Result<()>;
-    fn render(&mut self, terminal: &mut Terminal<CrosstermBackend<Stdout>>) -> Result<()>;
+    // Advances the game one step, called at `tick_rate` while it returns something. Ticks are
+    // dropped when the game falls too far behind, so anything going by the time, like a clock,
+    // has to measure it instead of counting ticks.
+    fn tick(&mut self) -> Result<()> {
+        Ok(())
+    }
+    // Time between two ticks, None while the game only changes on input.
+    fn tick_rate(&self) -> Option<Duration> {
+        None
+    }
+    // Stops anything measuring the time while the game is hidden, it goes on with the next tick.
+    fn pause(&mut self) {}
+    fn render(&mut self, terminal: &mut Terminal<B>) -> Result<()>;
     fn reset(&mut self);
     fn ended(&self) -> bool;
     fn kill_execution(&self) -> bool;
-    // In case is needed to limit game fps.
-    fn limit_fps(&self) {}
+    // Ends the execution as CTRL-c does.
+    fn force_quit(&mut self);
+    // Width and height of the smallest terminal the game fits in.
+    fn min_size(&self) -> (u16, u16);
+    // Symbols to draw what the game shows outside of its own screens with.
+    fn glyphs(&self) -> Glyphs {
+        Glyphs::default()
+    }
+}
+
+// When the next tick is due. Ticks are scheduled from when the previous one was due instead of
+// when it ran, so the time spent updating and drawing doesn't slow the game down.
+struct TickClock {
+    next_tick: Instant,
+}
+
+impl TickClock {
+    fn new(rate: Duration) -> Self {
+        Self {
+            next_tick: Instant::now() + rate,
+        }
+    }
+
+    fn until_next_tick(&self, rate: Duration) -> Duration {
+        // The rate can go down between two ticks, like when the player pauses and lowers the fps.
+        self.next_tick
+            .saturating_duration_since(Instant::now())
+            .min(rate)
+    }
+
+    fn tick(&mut self, rate: Duration) {
+        let now = Instant::now();
+        self.next_tick = if now.saturating_duration_since(self.next_tick) > rate * MAX_TICK_LAG {
+            now + rate
+        } else {
+            self.next_tick + rate
+        };
+    }
+}
+
+// How long the games took to draw their frames, printed with `--frame-stats`.
+#[derive(Default)]
+pub struct FrameStats {
+    frames: u32,
+    drawing: Duration,
+    slowest: Duration,
+    first: Option<Instant>,
+    last: Option<Instant>,
+}
+
+impl FrameStats {
+    fn measure(&mut self, render: impl FnOnce() -> Result<()>) -> Result<()> {
+        let start = Instant::now();
+        render()?;
+        let now = Instant::now();
+        let took = now - start;
+        self.frames += 1;
+        self.drawing += took;
+        self.slowest = self.slowest.max(took);
+        self.first.get_or_insert(start);
+        self.last = Some(now);
+        Ok(())
+    }
+
+    pub fn frames(&self) -> u32 {
+        self.frames
+    }
+
+    pub fn report(&self) -> String {
+        let (Some(first), Some(last)) = (self.first, self.last) else {
+            return String::from("No game frames were drawn");
+        };
+        let elapsed = last - first;
+        let fps = if elapsed.is_zero() {
+            0.0
+        } else {
+            f64::from(self.frames) / elapsed.as_secs_f64()
+        };
+        format!(
+            "{} frames in {:.1}s ({fps:.1} fps), drawing took {:.2}ms on average and {:.2}ms at most",
+            self.frames,
+            elapsed.as_secs_f64(),
+            (self.drawing / self.frames).as_secs_f64() * 1000.0,
+            self.slowest.as_secs_f64() * 1000.0
+        )
+    }
 }
 
 // Useful things
+pub type GameRng = ChaCha12Rng;
+
+pub fn new_rng(seed: u64) -> GameRng {
+    GameRng::seed_from_u64(seed)
+}
+
+// Seed for a new game session: the fixed one if it was given, a random one otherwise.
+pub fn session_seed(seed: Option<u64>) -> u64 {
+    seed.unwrap_or_else(rand::random)
+}
+
+// TOML has no unsigned 64 bit integers, so seeds are saved as strings.
+pub mod serde_seed {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(
+        seed: &u64,
+        serializer: S,
+    ) -> std::result::Result<S::Ok, S::Error> {
+        serializer.collect_str(seed)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> std::result::Result<u64, D::Error> {
+        String::deserialize(deserializer)?
+            .parse()
+            .map_err(D::Error::custom)
+    }
+}
+
+// Saves the exact state of the generator, so a resumed game deals the same as an uninterrupted
+// one. Written as `seed:stream:word position`, the seed in hexadecimal.
+pub mod serde_rng {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(
+        rng: &GameRng,
+        serializer: S,
+    ) -> std::result::Result<S::Ok, S::Error> {
+        let seed: String = rng
+            .get_seed()
+            .iter()
+            .map(|byte| format!("{byte:02x}"))
+            .collect();
+        serializer.collect_str(&format_args!(
+            "{seed}:{}:{}",
+            rng.get_stream(),
+            rng.get_word_pos()
+        ))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> std::result::Result<GameRng, D::Error> {
+        let state = String::deserialize(deserializer)?;
+        let invalid = || D::Error::custom(format!("invalid rng state \"{state}\""));
+        let mut parts = state.split(':');
+        let (Some(seed), Some(stream), Some(word_pos), None) =
+            (parts.next(), parts.next(), parts.next(), parts.next())
+        else {
+            return Err(invalid());
+        };
+        let mut seed_bytes = [0u8; 32];
+        if seed.len() != seed_bytes.len() * 2 {
+            return Err(invalid());
+        }
+        for (index, byte) in seed_bytes.iter_mut().enumerate() {
+            *byte =
+                u8::from_str_radix(&seed[index * 2..index * 2 + 2], 16).map_err(|_| invalid())?;
+        }
+        let mut rng = GameRng::from_seed(seed_bytes);
+        rng.set_stream(stream.parse().map_err(|_| invalid())?);
+        rng.set_word_pos(word_pos.parse().map_err(|_| invalid())?);
+        Ok(rng)
+    }
+}
+
+#[derive(Clone, Default)]
+pub struct GameSettings {
+    // Every session uses this seed, so runs can be reproduced and shared.
+    pub seed: Option<u64>,
+    pub keybindings: Keybindings,
+    // Colors in use, shared so changing it from the main menu reaches every game.
+    pub theme: SharedTheme,
+    pub themes: Themes,
+    // Symbols the boards are drawn with.
+    pub glyphs: Glyphs,
+    pub options: GameOptions,
+}
+
+// Options of a single game, the games that don't have them ignore them. Recordings keep them, so
+// the replay plays the same game.
+#[derive(Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct GameOptions {
+    pub difficult: Option<Difficult>,
+    // Height and width of the board.
+    pub board_size: Option<(usize, usize)>,
+    // Open a random empty area of Minesweeper boards right away, like it always used to.
+    pub auto_open: bool,
+    // Only deal Minesweeper boards that never need a guess.
+    pub no_guess: bool,
+    // Minesweeper board to play instead of a random one, as the text of a board file.
+    pub board: Option<String>,
+    pub fps: Option<u64>,
+}
+
+pub fn draw<B: GameBackend>(
+    terminal: &mut Terminal<B>,
+    render_callback: impl FnOnce(&mut Frame),
+) -> Result<()> {
+    terminal.draw(render_callback).map_err(Error::other)?;
+    Ok(())
+}
+
+// Size of the terminal needed to fit `lines` inside a bordered block that takes the given
+// percentages of the width and height of the screen.
+pub fn min_size_for(lines: &[Line], width_percentage: u16, height_percentage: u16) -> (u16, u16) {
+    let width = lines.iter().map(Line::width).max().unwrap_or(0) as u16 + 2;
+    let height = lines.len() as u16 + 2;
+    (
+        (width * 100).div_ceil(width_percentage),
+        (height * 100).div_ceil(height_percentage),
+    )
+}
+
+// A replay goes by the size of the terminal it was recorded on.
+fn screen_size<B: GameBackend>(
+    terminal: &Terminal<B>,
+    events: &dyn EventSource,
+) -> Result<(u16, u16)> {
+    match events.screen_size() {
+        Some(size) => Ok(size),
+        None => {
+            let size = terminal.size().map_err(Error::other)?;
+            Ok((size.width, size.height))
+        }
+    }
+}
+
+fn display_too_small<B: GameBackend>(
+    terminal: &mut Terminal<B>,
+    (width, height): (u16, u16),
+    (columns, rows): (u16, u16),
+    glyphs: &Glyphs,
+) -> Result<()> {
+    draw(terminal, |frame| {
+        let area = frame.area();
+        let lines = vec![
+            Line::from("Terminal too small").style(Style::default().fg(Color::Yellow)),
+            Line::from(format!("need {width}x{height}, have {columns}x{rows}")),
+            Line::from(""),
+            Line::from("Enlarge the window or make the font smaller.")
+                .style(Style::default().fg(Color::DarkGray)),
+            Line::from("CTRL-c - Quit").style(Style::default().fg(Color::DarkGray)),
+        ];
+        let [area] = Layout::vertical([Constraint::Length(lines.len() as u16 + 2)])
+            .flex(Flex::Center)
+            .areas(area);
+        frame.render_widget(
+            Paragraph::new(lines)
+                .alignment(Alignment::Center)
+                .block(Block::new().borders(Borders::ALL).border_set(glyphs.border)),
+            area,
+        );
+    })
+}
+
 pub fn confirmation_guide() -> String {
     String::from("N or n - go back to playing\nAny key - confirm")
 }
 
-#[derive(Clone)]
+#[derive(Clone, Serialize, Deserialize)]
 pub struct Coord {
     pub x: usize,
     pub y: usize,
 }
 
-#[derive(Clone)]
+#[derive(Clone, Serialize, Deserialize)]
 pub enum Directions {
     Up,
     Down,
@@ -42,9 +390,41 @@ pub enum Directions {
     Right,
 }
 
-#[derive(Clone)]
+#[derive(Clone, Display, EnumIter, Serialize, Deserialize)]
 pub enum Difficult {
     Easy,
     Medium,
     Hard,
+    // Board of any size chosen by the player, with a leaderboard for each of them.
+    #[strum(to_string = "Custom-{height}x{width}-{mines}")]
+    Custom {
+        height: usize,
+        width: usize,
+        mines: usize,
+    },
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::Rng;
+
+    #[derive(Serialize, Deserialize)]
+    struct Saved {
+        #[serde(with = "serde_rng")]
+        rng: GameRng,
+    }
+
+    #[test]
+    fn rng_resumes_where_it_was_saved() {
+        let mut rng = new_rng(42);
+        for _ in 0..37 {
+            rng.random::<u32>();
+        }
+        let content = toml::to_string(&Saved { rng: rng.clone() }).unwrap();
+        let mut restored = toml::from_str::<Saved>(&content).unwrap().rng;
+        let expected: Vec<u64> = (0..16).map(|_| rng.random()).collect();
+        let next: Vec<u64> = (0..16).map(|_| restored.random()).collect();
+        assert_eq!(next, expected);
+    }
 }
@@ -0,0 +1,182 @@
+use ratatui::symbols::{border, line};
+use serde::Deserialize;
+use std::{
+    env,
+    io::{Error, ErrorKind, Result},
+};
+use strum::IntoEnumIterator;
+use strum_macros::{Display, EnumIter};
+
+#[derive(Clone, Copy, PartialEq, Eq, Deserialize, Display, EnumIter)]
+#[serde(rename_all = "kebab-case")]
+#[strum(serialize_all = "kebab-case")]
+pub enum GlyphSet {
+    // Icons from a patched font, like https://www.nerdfonts.com.
+    NerdFont,
+    // Symbols any font with good Unicode coverage has.
+    Unicode,
+    // Only plain ASCII, for terminals that can't show anything else.
+    Ascii,
+}
+
+impl GlyphSet {
+    pub fn from_name(name: &str) -> Result<Self> {
+        Self::iter()
+            .find(|set| set.to_string() == name)
+            .ok_or_else(|| {
+                let names: Vec<String> = Self::iter().map(|set| set.to_string()).collect();
+                Error::new(
+                    ErrorKind::InvalidInput,
+                    format!(
+                        "unknown glyph set \"{name}\", expected one of: {}",
+                        names.join(", ")
+                    ),
+                )
+            })
+    }
+
+    // There is no way to ask the terminal which font it uses, so the Nerd Font symbols are kept
+    // unless the locale or the terminal says Unicode isn't supported at all.
+    pub fn detect() -> Self {
+        let term = env::var("TERM").unwrap_or_default();
+        let locale = ["LC_ALL", "LC_CTYPE", "LANG"]
+            .iter()
+            .find_map(|variable| env::var(variable).ok().filter(|value| !value.is_empty()))
+            .map(|locale| locale.to_uppercase().replace('-', ""));
+        let unicode_locale = locale.is_none_or(|locale| locale.contains("UTF8"));
+        if term == "dumb" || term == "linux" || !unicode_locale {
+            GlyphSet::Ascii
+        } else {
+            GlyphSet::NerdFont
+        }
+    }
+
+    pub fn glyphs(&self) -> Glyphs {
+        match self {
+            GlyphSet::NerdFont => NERD_FONT,
+            GlyphSet::Unicode => UNICODE,
+            GlyphSet::Ascii => ASCII,
+        }
+    }
+}
+
+// Every symbol the boards are drawn with. Squares of Snake, Tetris and Minesweeper are two columns
+// wide, the ones of Flappy bird only one.
+#[derive(Clone, Copy)]
+pub struct Glyphs {
+    pub set: GlyphSet,
+    // Lines around and across the boards.
+    pub line: line::Set<'static>,
+    // Borders of the panels.
+    pub border: border::Set<'static>,
+    pub block: &'static str,
+    pub square: &'static str,
+    // Up, down, left and right arrow keys in the key guides.
+    pub arrows: [&'static str; 4],
+    // Fill of each kind of Tetris brick when the theme asks for patterns, in the order of the
+    // theme colors.
+    pub bricks: [&'static str; 7],
+    // Snake head when the theme asks for patterns.
+    pub snake_head: &'static str,
+    pub snake_dead: &'static str,
+    pub food: &'static str,
+    pub bird: &'static str,
+    pub dead_bird: &'static str,
+    pub closed: &'static str,
+    pub flag: &'static str,
+    pub wrong_flag: &'static str,
+    pub uncertain: &'static str,
+    pub mine: &'static str,
+    // Mine that went off when the theme asks for patterns.
+    pub exploded: &'static str,
+    // Shown next to the name of the project.
+    pub logo: &'static str,
+}
+
+impl Default for Glyphs {
+    fn default() -> Self {
+        NERD_FONT
+    }
+}
+
+const UNICODE_BRICKS: [&str; 7] = ["██", "▓▓", "▒▒", "░░", "[]", "<>", "##"];
+
+pub const NERD_FONT: Glyphs = Glyphs {
+    set: GlyphSet::NerdFont,
+    arrows: ["\u{f062}", "\u{f063}", "\u{f060}", "\u{f061}"],
+    snake_dead: "󰯈 ",
+    food: "󰉛 ",
+    bird: "󱗆",
+    dead_bird: "󰯈",
+    closed: " ",
+    flag: "󰈿 ",
+    wrong_flag: "󰛅 ",
+    mine: "󰚑 ",
+    logo: "󰊴",
+    ..UNICODE
+};
+
+pub const UNICODE: Glyphs = Glyphs {
+    set: GlyphSet::Unicode,
+    line: line::ROUNDED,
+    border: border::PLAIN,
+    block: "█",
+    square: "██",
+    arrows: ["↑", "↓", "←", "→"],
+    bricks: UNICODE_BRICKS,
+    snake_head: "▓▓",
+    snake_dead: "✖ ",
+    food: "● ",
+    bird: "◆",
+    dead_bird: "✖",
+    closed: "■ ",
+    flag: "⚑ ",
+    wrong_flag: "✗ ",
+    uncertain: "? ",
+    mine: "✱ ",
+    exploded: "✹ ",
+    logo: "",
+};
+
+pub const ASCII: Glyphs = Glyphs {
+    set: GlyphSet::Ascii,
+    line: line::Set {
+        vertical: "|",
+        horizontal: "-",
+        top_right: "+",
+        top_left: "+",
+        bottom_right: "+",
+        bottom_left: "+",
+        vertical_left: "+",
+        vertical_right: "+",
+        horizontal_down: "+",
+        horizontal_up: "+",
+        cross: "+",
+    },
+    border: border::Set {
+        top_left: "+",
+        top_right: "+",
+        bottom_left: "+",
+        bottom_right: "+",
+        vertical_left: "|",
+        vertical_right: "|",
+        horizontal_top: "-",
+        horizontal_bottom: "-",
+    },
+    block: "#",
+    square: "[]",
+    arrows: ["UP", "DOWN", "LEFT", "RIGHT"],
+    bricks: ["[]", "##", "()", "{}", "<>", "@@", "%%"],
+    snake_head: "@@",
+    snake_dead: "XX",
+    food: "()",
+    bird: ">",
+    dead_bird: "X",
+    closed: ". ",
+    flag: "F ",
+    wrong_flag: "x ",
+    uncertain: "? ",
+    mine: "* ",
+    exploded: "X ",
+    logo: "",
+};
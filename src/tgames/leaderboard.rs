@@ -0,0 +1,339 @@
+use super::{
+    game_manager::{draw, Difficult, GameBackend},
+    glyphs::Glyphs,
+    records::{LeaderboardEntry, RecordsStore, DEFAULT_MODE, LEADERBOARD_SIZE},
+    theme::Theme,
+    Games,
+};
+use crossterm::event::{Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+use ratatui::{
+    layout::{Alignment, Constraint, Direction, Layout},
+    style::{Style, Stylize},
+    text::{Line, Span},
+    widgets::{Block, Borders, Paragraph},
+    Terminal,
+};
+use std::{fmt::Display, io::Result};
+use strum::IntoEnumIterator;
+
+const MAX_NAME_LENGTH: usize = 12;
+const DEFAULT_NAME: &str = "Player";
+
+#[derive(PartialEq, Eq)]
+pub enum NamePromptStatus {
+    Typing,
+    Confirmed,
+    Skipped,
+}
+
+pub struct NamePrompt {
+    name: String,
+    status: NamePromptStatus,
+}
+
+impl Default for NamePrompt {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl NamePrompt {
+    pub fn new() -> Self {
+        Self {
+            name: String::new(),
+            status: NamePromptStatus::Typing,
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.status = NamePromptStatus::Typing;
+    }
+
+    pub fn status(&self) -> &NamePromptStatus {
+        &self.status
+    }
+
+    // The last name typed is kept, so whoever is playing only has to confirm it next time.
+    pub fn name(&self) -> String {
+        if self.name.is_empty() {
+            DEFAULT_NAME.to_string()
+        } else {
+            self.name.clone()
+        }
+    }
+
+    pub fn handle_event(&mut self, event: &Event) {
+        if let Event::Key(KeyEvent {
+            code,
+            modifiers,
+            kind: KeyEventKind::Press,
+            ..
+        }) = event
+        {
+            match code {
+                KeyCode::Enter => self.status = NamePromptStatus::Confirmed,
+                KeyCode::Esc => self.status = NamePromptStatus::Skipped,
+                KeyCode::Backspace => {
+                    self.name.pop();
+                }
+                KeyCode::Char(character)
+                    if !modifiers.contains(KeyModifiers::CONTROL)
+                        && !character.is_control()
+                        && self.name.chars().count() < MAX_NAME_LENGTH =>
+                {
+                    self.name.push(*character)
+                }
+                _ => (),
+            }
+        }
+    }
+
+    pub fn display<B: GameBackend>(
+        &self,
+        terminal: &mut Terminal<B>,
+        score: impl Display,
+        theme: &Theme,
+        glyphs: &Glyphs,
+    ) -> Result<()> {
+        let lines = vec![
+            Line::from(Span::styled(
+                format!("New high score: {score}"),
+                Style::default().fg(theme.success),
+            )),
+            Line::from(""),
+            Line::from(vec![
+                Span::raw("Name: "),
+                Span::styled(self.name.clone() + "_", Style::default().fg(theme.warning)),
+            ]),
+            Line::from(""),
+            Line::from(Span::styled(
+                "ENTER - Save    ESC - Skip",
+                Style::default().fg(theme.dim),
+            )),
+        ];
+        draw(terminal, |frame| {
+            let vertical = Layout::default()
+                .direction(Direction::Vertical)
+                .constraints([
+                    Constraint::Fill(1),
+                    Constraint::Length(lines.len() as u16 + 2),
+                    Constraint::Fill(1),
+                ])
+                .split(frame.area());
+            let horizontal = Layout::default()
+                .direction(Direction::Horizontal)
+                .constraints([
+                    Constraint::Fill(1),
+                    Constraint::Length(40),
+                    Constraint::Fill(1),
+                ])
+                .split(vertical[1]);
+            frame.render_widget(
+                Paragraph::new(lines).alignment(Alignment::Center).block(
+                    Block::new()
+                        .borders(Borders::ALL)
+                        .border_set(glyphs.border)
+                        .title("Leaderboard")
+                        .title_alignment(Alignment::Center),
+                ),
+                horizontal[1],
+            );
+        })?;
+        Ok(())
+    }
+}
+
+// Modes with a leaderboard of their own. Each custom Minesweeper board has one too, see
+// `RecordsStore::modes`.
+pub fn modes(game: &Games) -> Vec<String> {
+    match game {
+        Games::Minesweeper => Difficult::iter()
+            .filter(|difficult| !matches!(difficult, Difficult::Custom { .. }))
+            .map(|difficult| difficult.to_string())
+            .collect(),
+        _ => vec![DEFAULT_MODE.to_string()],
+    }
+}
+
+// Auxiliary stat shown next to the score.
+pub fn stat_title(game: &Games) -> Option<&'static str> {
+    match game {
+        Games::Tetris => Some("Lines"),
+        Games::G2048 => Some("Moves"),
+        Games::Minesweeper => Some("Time"),
+        _ => None,
+    }
+}
+
+fn score_title(game: &Games) -> &'static str {
+    match game {
+        Games::Minesweeper => "3BV/s",
+        _ => "Score",
+    }
+}
+
+// Minesweeper ranks by hundredths of 3BV per second, the other games by plain points.
+pub fn format_score(game: &Games, score: u32) -> String {
+    match game {
+        Games::Minesweeper => format!("{}.{:02}", score / 100, score % 100),
+        _ => score.to_string(),
+    }
+}
+
+// Minesweeper times are kept in tenths of a second.
+fn format_stat(game: &Games, stat: u32) -> String {
+    match game {
+        Games::Minesweeper => format!("{}.{}s", stat / 10, stat % 10),
+        _ => stat.to_string(),
+    }
+}
+
+// The leaderboard of a mode as plain text, for printing outside of the TUI.
+pub fn leaderboard_text(records: &RecordsStore, game: &Games, mode: &str) -> String {
+    let mut text = String::new();
+    for row in leaderboard_rows(records.leaderboard(game, mode), game, stat_title(game)) {
+        text += row.trim_end();
+        text += "\n";
+    }
+    text
+}
+
+// Draws one table per mode, side by side.
+pub fn display_leaderboard<B: GameBackend>(
+    terminal: &mut Terminal<B>,
+    records: &RecordsStore,
+    game: &Games,
+    modes: &[String],
+    stat_title: Option<&str>,
+    theme: &Theme,
+    glyphs: &Glyphs,
+) -> Result<()> {
+    draw(terminal, |frame| {
+        let layout = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Fill(1), Constraint::Length(1)])
+            .split(frame.area());
+        let tables = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints(vec![Constraint::Fill(1); modes.len()])
+            .split(layout[0]);
+
+        for (mode, area) in modes.iter().zip(tables.iter()) {
+            frame.render_widget(
+                Paragraph::new(leaderboard_lines(
+                    records.leaderboard(game, mode),
+                    game,
+                    stat_title,
+                    theme,
+                ))
+                .block(
+                    Block::new()
+                        .borders(Borders::ALL)
+                        .border_set(glyphs.border)
+                        .title(format!("{game} leaderboard - {mode}"))
+                        .title_alignment(Alignment::Center),
+                ),
+                *area,
+            );
+        }
+
+        frame.render_widget(
+            Paragraph::new("Press any key to go back.").fg(theme.dim),
+            layout[1],
+        );
+    })?;
+    Ok(())
+}
+
+fn leaderboard_lines<'a>(
+    entries: &[LeaderboardEntry],
+    game: &Games,
+    stat_title: Option<&str>,
+    theme: &Theme,
+) -> Vec<Line<'a>> {
+    leaderboard_rows(entries, game, stat_title)
+        .into_iter()
+        .enumerate()
+        .map(|(index, row)| {
+            // The header comes first, then the entries and the empty positions.
+            let color = match index {
+                _ if index == 0 || index > entries.len() => theme.dim,
+                1..=3 => theme.podium[index - 1],
+                _ => theme.text,
+            };
+            Line::from(Span::styled(row, Style::default().fg(color)))
+        })
+        .collect()
+}
+
+fn leaderboard_rows(
+    entries: &[LeaderboardEntry],
+    game: &Games,
+    stat_title: Option<&str>,
+) -> Vec<String> {
+    let mut header = format!(
+        "{:>2}  {:<MAX_NAME_LENGTH$}  {:>8}",
+        "#",
+        "Name",
+        score_title(game)
+    );
+    if let Some(stat_title) = stat_title {
+        header += &format!("  {stat_title:>6}");
+    }
+    header += &format!("  {:<10}", "Date");
+    let mut rows = vec![header];
+    for (position, entry) in entries.iter().enumerate() {
+        let mut row = format!(
+            "{:>2}  {:<MAX_NAME_LENGTH$}  {:>8}",
+            position + 1,
+            entry.name,
+            format_score(game, entry.score)
+        );
+        if stat_title.is_some() {
+            row += &format!("  {:>6}", format_stat(game, entry.stat.unwrap_or(0)));
+        }
+        row += &format!("  {:<10}", format_date(entry.date));
+        if entry.hinted {
+            row += "  hints";
+        }
+        rows.push(row);
+    }
+    for position in entries.len()..LEADERBOARD_SIZE {
+        rows.push(format!("{:>2}  {:<MAX_NAME_LENGTH$}", position + 1, "---"));
+    }
+    rows
+}
+
+// Formats seconds since the unix epoch as YYYY-MM-DD (UTC), using Howard Hinnant's
+// days-to-civil algorithm.
+fn format_date(seconds: u64) -> String {
+    let days = (seconds / 86400) as i64 + 719468;
+    let era = days.div_euclid(146097);
+    let day_of_era = days.rem_euclid(146097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 {
+        month_index + 3
+    } else {
+        month_index - 9
+    };
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+    format!("{year:04}-{month:02}-{day:02}")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn dates_from_known_epochs() {
+        assert_eq!(format_date(0), "1970-01-01");
+        assert_eq!(format_date(951782400), "2000-02-29");
+        assert_eq!(format_date(1709251199), "2024-02-29");
+        assert_eq!(format_date(1709251200), "2024-03-01");
+        assert_eq!(format_date(1735689599), "2024-12-31");
+    }
+}
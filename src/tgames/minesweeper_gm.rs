@@ -1,38 +1,107 @@
-mod board;
+pub mod board;
+mod board_file;
+mod custom_board;
+mod solver;
 
 use super::{
     super::input::{
-        read_confirmation, read_key, should_force_quit, should_help, should_move_down,
-        should_move_left, should_move_right, should_move_up, should_play, should_quit,
+        event_source::EventSource,
+        is_resize,
+        keybindings::{Action, ActionGuide, Keymap},
+        mouse, read_confirmation, read_key, should_force_quit,
     },
-    game_manager::{self, Difficult, Directions},
+    game_manager::{self, draw, session_seed, Difficult, Directions, GameBackend, GameSettings},
+    glyphs::Glyphs,
+    leaderboard::{
+        display_leaderboard, format_score, modes, stat_title, NamePrompt, NamePromptStatus,
+    },
+    records::{LeaderboardEntry, SharedRecords},
+    theme::Theme,
+    Games,
 };
-use board::Board;
-use crossterm::event::{read, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+use board::{Board, Hint, Square};
+use board_file::BoardLayout;
+use crossterm::event::{Event, MouseButton};
+use custom_board::{CustomBoardPrompt, CustomBoardStatus};
 use ratatui::{
-    backend::CrosstermBackend,
-    layout::{Alignment, Constraint, Direction, Layout},
+    layout::{Alignment, Constraint, Direction, Layout, Position, Rect},
     style::{Color, Stylize},
     widgets::{Block, Borders, Paragraph},
     Terminal,
 };
-use std::io::{Result, Stdout};
+use serde::{Deserialize, Serialize};
+use std::{
+    io::{Error, ErrorKind, Result},
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+// Smallest and largest sides of a custom board, the terminal may still be too small for it.
+pub const MIN_SIDE: usize = 5;
+pub const MAX_SIDE: usize = 99;
+// Share of the squares of a custom board that can have mines at most, in percent.
+const MAX_DENSITY: usize = 80;
+// How often the clock shown is drawn again, the time itself is measured.
+const TIMER_TICK: Duration = Duration::from_millis(100);
+
+pub const MENU_ACTIONS: ActionGuide = &[
+    (Action::Play, "Play last difficult played"),
+    (Action::Continue, "Continue saved game"),
+    (Action::PlayEasy, "Play easy game"),
+    (Action::PlayMedium, "Play medium game"),
+    (Action::PlayHard, "Play hard game"),
+    (Action::PlayCustom, "Play custom game"),
+    (Action::ToggleNoGuess, "Toggle no-guess boards"),
+    (Action::Help, "Display game rules"),
+    (Action::Leaderboard, "Display leaderboard"),
+    (Action::Quit, "Go to main menu"),
+];
+
+pub const PLAY_ACTIONS: ActionGuide = &[
+    (Action::Reveal, "Reveal square"),
+    (Action::Mark, "Mark square"),
+    (Action::Chord, "Reveal around number"),
+    (Action::Hint, "Give a hint"),
+    (Action::Export, "Export board"),
+    (Action::MoveUp, "Move up"),
+    (Action::MoveDown, "Move down"),
+    (Action::MoveRight, "Move right"),
+    (Action::MoveLeft, "Move left"),
+    (Action::Quit, "Go to menu"),
+];
+
+pub const REVIEW_ACTIONS: ActionGuide = &[
+    (Action::MoveUp, "Move up"),
+    (Action::MoveDown, "Move down"),
+    (Action::MoveRight, "Move right"),
+    (Action::MoveLeft, "Move left"),
+    (Action::Quit, "Go to menu"),
+];
 
 enum MenuOpt {
     Play(Difficult),
+    Custom,
+    Continue,
     Quit,
     Help,
+    Leaderboard,
+    ToggleNoGuess,
     None,
 }
 
-#[derive(PartialEq, Eq)]
+#[derive(PartialEq, Eq, Clone, Copy)]
 enum GameState {
     Starting,
     Menu,
     Playing,
     Helping,
+    ChoosingBoard,
     Won,
+    // Looking around the board of a lost game, before the menu.
+    Reviewing,
     Lost,
+    EnteringName,
+    Leaderboard,
     AskingToQuit,
     Quitting,
 }
@@ -40,11 +109,23 @@ enum GameState {
 enum PlayOpt {
     Reveal,
     Mark,
+    Chord,
+    Hint,
+    Export,
+    // Button pressed on the square at a line and a column.
+    Click(MouseButton, usize, usize),
     Direction(Directions),
     Quit,
     None,
 }
 
+// Everything needed to resume a game left in the middle.
+#[derive(Serialize, Deserialize)]
+struct SavedGame {
+    board: Board,
+    difficult: Difficult,
+}
+
 pub struct MinesweeperGameManager {
     game_state: GameState,
     menu_opt: MenuOpt,
@@ -52,61 +133,194 @@ pub struct MinesweeperGameManager {
     confirmed: bool,
     difficult: Difficult,
     board: Board,
-    record: u32,
+    // Board read from a file, played again instead of a random one until another is chosen.
+    puzzle: Option<BoardLayout>,
+    // Where the last export was written, or why it failed, shown until the next move.
+    exported: Option<Result<PathBuf>>,
+    records: SharedRecords,
+    keymap: Keymap,
+    settings: GameSettings,
+    name_prompt: NamePrompt,
+    custom_prompt: CustomBoardPrompt,
+    end_state: GameState,
     kill_execution: bool,
+    resized: bool,
+    // Where the key guide was last drawn, so its entries can be clicked.
+    help_area: Rect,
+    // Where the board was last drawn, so its squares can be clicked.
+    board_area: Rect,
 }
 
-impl game_manager::GameManager for MinesweeperGameManager {
-    fn process_events(&mut self) -> Result<()> {
+impl<B: GameBackend> game_manager::GameManager<B> for MinesweeperGameManager {
+    fn process_events(&mut self, events: &mut dyn EventSource) -> Result<()> {
         match self.game_state {
             GameState::Starting => (),
-            GameState::Menu | GameState::Won | GameState::Lost => self.read_menu_input()?,
-            GameState::Playing => self.read_play_input()?,
-            GameState::Helping => read_key()?,
+            GameState::Menu | GameState::Won | GameState::Lost => self.read_menu_input(events)?,
+            GameState::Playing => self.read_play_input(events)?,
+            GameState::Reviewing => self.read_review_input(events)?,
+            GameState::Helping | GameState::Leaderboard => {
+                self.resized = is_resize(&read_key(events)?)
+            }
+            GameState::EnteringName => {
+                let event = events.read()?;
+                self.kill_execution = should_force_quit(&event);
+                self.name_prompt.handle_event(&event);
+            }
+            GameState::ChoosingBoard => {
+                let event = events.read()?;
+                self.kill_execution = should_force_quit(&event);
+                self.custom_prompt.handle_event(&event);
+            }
             GameState::AskingToQuit => {
-                let event = read()?;
+                let event = events.read()?;
                 self.kill_execution = should_force_quit(&event);
                 self.confirmed = read_confirmation(&event);
+                self.resized = is_resize(&event);
             }
             GameState::Quitting => (),
         }
         Ok(())
     }
     fn update(&mut self) -> Result<()> {
+        // A resize only needs the screen drawn again.
+        if self.resized {
+            self.resized = false;
+            return Ok(());
+        }
         if self.kill_execution {
+            if matches!(
+                self.game_state,
+                GameState::Playing | GameState::AskingToQuit
+            ) {
+                self.suspend_game()?;
+            }
             self.game_state = GameState::Quitting;
         }
         match self.game_state {
-            GameState::Starting => self.game_state = GameState::Playing,
-            GameState::Helping => self.game_state = GameState::Menu,
+            GameState::Starting => {
+                // A board given to play skips the choice between the suspended game and a new one.
+                if self.puzzle.is_none()
+                    && self.records.borrow().has_saved_game(&Games::Minesweeper)
+                {
+                    self.game_state = GameState::Menu;
+                } else {
+                    self.game_state = GameState::Playing;
+                }
+            }
+            GameState::Helping | GameState::Leaderboard => self.game_state = GameState::Menu,
             GameState::Menu | GameState::Won | GameState::Lost => match &self.menu_opt {
                 MenuOpt::Play(difficult) => {
                     self.game_state = GameState::Playing;
-                    self.board.reset(difficult);
+                    match &self.puzzle {
+                        Some(puzzle) => {
+                            self.board =
+                                Board::from_layout(puzzle, session_seed(self.settings.seed))
+                        }
+                        None => self.board.reset(
+                            difficult,
+                            session_seed(self.settings.seed),
+                            self.settings.options.auto_open,
+                            self.settings.options.no_guess,
+                        ),
+                    }
+                }
+                MenuOpt::Custom => {
+                    self.custom_prompt.clear();
+                    self.game_state = GameState::ChoosingBoard;
                 }
+                MenuOpt::Continue => self.resume_game(),
                 MenuOpt::Help => self.game_state = GameState::Helping,
+                MenuOpt::Leaderboard => self.game_state = GameState::Leaderboard,
+                MenuOpt::ToggleNoGuess => {
+                    self.settings.options.no_guess = !self.settings.options.no_guess;
+                }
                 MenuOpt::Quit => self.game_state = GameState::Quitting,
                 MenuOpt::None => (),
             },
-            GameState::Playing => match &self.play_opt {
-                PlayOpt::Reveal => {
-                    self.board.reveal();
-                    if self.board.won() {
-                        self.game_state = GameState::Won;
-                    } else if self.board.lost() {
-                        self.game_state = GameState::Lost;
+            GameState::Playing => {
+                self.exported = None;
+                match self.play_opt {
+                    PlayOpt::Reveal => {
+                        self.board.reveal();
+                        self.check_game_end();
+                    }
+                    // Marking the last mine can win the game too.
+                    PlayOpt::Mark => {
+                        self.board.mark();
+                        self.check_game_end();
+                    }
+                    PlayOpt::Chord => {
+                        self.board.chord();
+                        self.check_game_end();
                     }
-                    if self.board.score() > self.record {
-                        self.record = self.board.score();
+                    PlayOpt::Hint => self.board.hint(),
+                    PlayOpt::Export => self.exported = Some(self.export_board()),
+                    PlayOpt::Click(button, line, column) => {
+                        self.board.move_cursor_to(line, column);
+                        match button {
+                            MouseButton::Left => self.board.reveal(),
+                            MouseButton::Right => self.board.mark(),
+                            MouseButton::Middle => self.board.chord(),
+                        }
+                        self.check_game_end();
                     }
+                    PlayOpt::Direction(ref direction) => self.board.move_cursor(direction),
+                    PlayOpt::Quit => self.game_state = GameState::AskingToQuit,
+                    PlayOpt::None => (),
                 }
-                PlayOpt::Mark => self.board.mark(),
-                PlayOpt::Direction(direction) => self.board.move_cursor(direction),
-                PlayOpt::Quit => self.game_state = GameState::AskingToQuit,
-                PlayOpt::None => (),
+            }
+            GameState::Reviewing => match self.play_opt {
+                PlayOpt::Direction(ref direction) => self.board.move_cursor(direction),
+                PlayOpt::Click(_, line, column) => self.board.move_cursor_to(line, column),
+                PlayOpt::Quit => self.game_state = GameState::Lost,
+                _ => (),
+            },
+            GameState::EnteringName => match self.name_prompt.status() {
+                NamePromptStatus::Typing => (),
+                NamePromptStatus::Confirmed => {
+                    self.records.borrow_mut().add_leaderboard_entry(
+                        &Games::Minesweeper,
+                        &self.difficult.to_string(),
+                        LeaderboardEntry::new(
+                            self.name_prompt.name(),
+                            self.score(),
+                            Some(self.board.time().as_millis() as u32 / 100),
+                        )
+                        .with_hints(self.board.hints() > 0),
+                    );
+                    self.game_state = self.end_state;
+                }
+                NamePromptStatus::Skipped => self.game_state = self.end_state,
+            },
+            GameState::ChoosingBoard => match self.custom_prompt.status() {
+                CustomBoardStatus::Typing => (),
+                CustomBoardStatus::Confirmed => {
+                    let (height, width, mines) = self.custom_prompt.values();
+                    let screen = self.custom_prompt.screen();
+                    match check_custom_board(height, width, mines, screen, &self.settings.glyphs) {
+                        Ok(()) => {
+                            self.puzzle = None;
+                            self.difficult = Difficult::Custom {
+                                height,
+                                width,
+                                mines,
+                            };
+                            self.board.reset(
+                                &self.difficult,
+                                session_seed(self.settings.seed),
+                                self.settings.options.auto_open,
+                                self.settings.options.no_guess,
+                            );
+                            self.game_state = GameState::Playing;
+                        }
+                        Err(error) => self.custom_prompt.refuse(error.to_string()),
+                    }
+                }
+                CustomBoardStatus::Cancelled => self.game_state = GameState::Menu,
             },
             GameState::AskingToQuit => match self.confirmed {
                 true => {
+                    self.suspend_game()?;
                     self.board.clear();
                     self.game_state = GameState::Menu;
                 }
@@ -116,56 +330,110 @@ impl game_manager::GameManager for MinesweeperGameManager {
             },
             GameState::Quitting => (),
         }
+        self.sync_clock();
         Ok(())
     }
-    fn render(&mut self, terminal: &mut Terminal<CrosstermBackend<Stdout>>) -> Result<()> {
+    fn tick(&mut self) -> Result<()> {
+        self.sync_clock();
+        Ok(())
+    }
+
+    fn pause(&mut self) {
+        self.board.run_clock(false);
+    }
+
+    fn tick_rate(&self) -> Option<Duration> {
+        (self.game_state == GameState::Playing && self.board.timing()).then_some(TIMER_TICK)
+    }
+
+    fn render(&mut self, terminal: &mut Terminal<B>) -> Result<()> {
+        let theme = self.settings.theme.clone();
+        let theme = theme.borrow();
         match self.game_state {
             GameState::Starting => (),
             GameState::Menu => self.display_screen(
                 terminal,
-                self.record,
-                Self::menu_guide(),
+                format_score(&Games::Minesweeper, self.record()),
+                self.menu_guide(),
                 "Menu",
                 "Record",
                 "",
-                Color::default(),
-            )?,
-            GameState::Playing => self.display_screen(
-                terminal,
-                self.board.score(),
-                Self::play_guide(),
-                "Board",
-                "Score",
-                "",
-                Color::default(),
+                theme.text,
             )?,
+            GameState::Playing => {
+                let (message, color) = self.play_message(&theme);
+                self.display_screen(
+                    terminal,
+                    self.statistics(),
+                    self.play_guide(),
+                    "Board",
+                    "Statistics",
+                    &message,
+                    color,
+                )?
+            }
             GameState::Helping => self.display_game_rules(terminal)?,
+            GameState::ChoosingBoard => {
+                self.custom_prompt
+                    .display(terminal, &theme, &self.settings.glyphs)?
+            }
             GameState::Won => self.display_screen(
                 terminal,
-                self.record,
-                Self::menu_guide(),
+                self.statistics(),
+                self.menu_guide(),
                 "Menu",
-                "Record",
-                "You won, congratulations!",
-                Color::Green,
+                "Statistics",
+                &match self.board.hints() {
+                    0 => String::from("You won, congratulations!"),
+                    1 => String::from("You won, with a hint!"),
+                    hints => format!("You won, with {hints} hints!"),
+                },
+                theme.success,
             )?,
+            GameState::Reviewing => {
+                let (message, color) = self.review_message(&theme);
+                self.display_screen(
+                    terminal,
+                    self.statistics(),
+                    self.review_guide(),
+                    "Review",
+                    "Statistics",
+                    &message,
+                    color,
+                )?
+            }
             GameState::Lost => self.display_screen(
                 terminal,
-                self.record,
-                Self::menu_guide(),
+                self.statistics(),
+                self.menu_guide(),
                 "Menu",
-                "Record",
+                "Statistics",
                 "You lost, try again!",
-                Color::Red,
+                theme.failure,
+            )?,
+            GameState::EnteringName => self.name_prompt.display(
+                terminal,
+                format_score(&Games::Minesweeper, self.score()),
+                &theme,
+                &self.settings.glyphs,
+            )?,
+            GameState::Leaderboard => display_leaderboard(
+                terminal,
+                &self.records.borrow(),
+                &Games::Minesweeper,
+                &self.leaderboard_modes(),
+                stat_title(&Games::Minesweeper),
+                &theme,
+                &self.settings.glyphs,
             )?,
             GameState::AskingToQuit => self.display_screen(
                 terminal,
-                self.board.score(),
+                self.statistics(),
                 game_manager::confirmation_guide(),
                 "Quitting",
-                "Score",
+                "Statistics",
                 "Are you sure you want to quit?",
-                Color::Yellow,
+                theme.warning,
             )?,
             GameState::Quitting => (),
         }
@@ -174,7 +442,17 @@ impl game_manager::GameManager for MinesweeperGameManager {
 
     fn reset(&mut self) {
         self.game_state = GameState::Starting;
-        self.board.reset(&self.difficult);
+        match &self.puzzle {
+            Some(puzzle) => {
+                self.board = Board::from_layout(puzzle, session_seed(self.settings.seed))
+            }
+            None => self.board.reset(
+                &self.difficult,
+                session_seed(self.settings.seed),
+                self.settings.options.auto_open,
+                self.settings.options.no_guess,
+            ),
+        }
     }
 
     fn ended(&self) -> bool {
@@ -184,45 +462,277 @@ impl game_manager::GameManager for MinesweeperGameManager {
     fn kill_execution(&self) -> bool {
         self.kill_execution
     }
+
+    fn force_quit(&mut self) {
+        self.kill_execution = true;
+    }
+
+    fn min_size(&self) -> (u16, u16) {
+        board_min_size(
+            &self.board,
+            &self.settings.theme.borrow(),
+            &self.settings.glyphs,
+        )
+    }
+
+    fn glyphs(&self) -> Glyphs {
+        self.settings.glyphs
+    }
 }
 
 impl MinesweeperGameManager {
-    pub fn new() -> Self {
+    pub fn new(records: SharedRecords, settings: GameSettings) -> Self {
+        // The board was checked before the game started.
+        let puzzle = settings
+            .options
+            .board
+            .as_deref()
+            .and_then(|text| BoardLayout::from_text(text).ok());
+        let difficult = match &puzzle {
+            Some(puzzle) => Difficult::Custom {
+                height: puzzle.height,
+                width: puzzle.width,
+                mines: puzzle.mines(),
+            },
+            None => settings
+                .options
+                .difficult
+                .clone()
+                .unwrap_or(Difficult::Medium),
+        };
+        let custom_prompt = match difficult {
+            Difficult::Custom {
+                height,
+                width,
+                mines,
+            } => CustomBoardPrompt::new(height, width, mines),
+            _ => CustomBoardPrompt::new(16, 16, 40),
+        };
         Self {
             game_state: GameState::Starting,
             menu_opt: MenuOpt::None,
             play_opt: PlayOpt::None,
             confirmed: false,
-            difficult: Difficult::Medium,
-            board: Board::new(),
-            record: 0,
+            difficult: difficult.clone(),
+            board: match &puzzle {
+                Some(puzzle) => Board::from_layout(puzzle, session_seed(settings.seed)),
+                None => Board::new(
+                    &difficult,
+                    session_seed(settings.seed),
+                    settings.options.auto_open,
+                    settings.options.no_guess,
+                ),
+            },
+            puzzle,
+            exported: None,
+            records,
+            keymap: settings.keybindings.keymap(Games::Minesweeper.id()),
+            settings,
+            name_prompt: NamePrompt::new(),
+            custom_prompt,
+            end_state: GameState::Lost,
             kill_execution: false,
+            resized: false,
+            help_area: Rect::default(),
+            board_area: Rect::default(),
         }
     }
 
-    fn menu_guide() -> String {
-        String::from(
-            "ENTER    - Play last difficult played\ne        - Play easy game\nm        - Play medium game\nh        - Play hard game\n?        - Display game rules\nESC or q - Go to main menu\n",
+    fn suspend_game(&mut self) -> Result<()> {
+        self.board.run_clock(false);
+        self.records.borrow_mut().store_saved_game(
+            &Games::Minesweeper,
+            SavedGame {
+                board: self.board.clone(),
+                difficult: self.difficult.clone(),
+            },
         )
     }
 
-    fn play_guide() -> String {
-        String::from(
-            "ENTER or r    - Reveal square\n! or m        - Mark square\nw or k or    - Move up\ns or j or    - Move down\nd or l or    - Move right\na or h or    - Move left\nESC or q      - Go to menu",
+    fn resume_game(&mut self) {
+        let saved = self
+            .records
+            .borrow_mut()
+            .take_saved_game::<SavedGame>(&Games::Minesweeper);
+        if let Some(saved) = saved {
+            self.board = saved.board;
+            self.difficult = saved.difficult;
+            self.game_state = GameState::Playing;
+        }
+    }
+
+    // The clock runs while a board is being played, from its first square revealed on.
+    fn sync_clock(&mut self) {
+        self.board
+            .run_clock(self.game_state == GameState::Playing && self.board.timing());
+    }
+
+    // Only won games are ranked, by their 3BV/s, and boards read from a file never are.
+    fn check_game_end(&mut self) {
+        // Stopped right away, so the time ranked is the one shown.
+        self.sync_clock();
+        if self.board.won() && self.board.loaded() {
+            self.game_state = GameState::Won;
+        } else if self.board.won() {
+            self.finish_game(GameState::Won);
+            if self.score() > self.record() {
+                let (mode, record) = (self.difficult.to_string(), self.score());
+                self.records.borrow_mut().update(|records| {
+                    records.minesweeper.insert(mode, record);
+                });
+            }
+        } else if self.board.lost() {
+            self.game_state = GameState::Reviewing;
+        }
+    }
+
+    fn finish_game(&mut self, end_state: GameState) {
+        if self.records.borrow().qualifies_for_leaderboard(
+            &Games::Minesweeper,
+            &self.difficult.to_string(),
+            self.score(),
+        ) {
+            self.name_prompt.clear();
+            self.end_state = end_state;
+            self.game_state = GameState::EnteringName;
+        } else {
+            self.game_state = end_state;
+        }
+    }
+
+    // Best score on the board size being played, each one is kept apart like its leaderboard.
+    fn record(&self) -> u32 {
+        let mode = self.difficult.to_string();
+        self.records
+            .borrow()
+            .records()
+            .minesweeper
+            .get(&mode)
+            .copied()
+            .unwrap_or(0)
+    }
+
+    fn score(&self) -> u32 {
+        self.board.ranking_score()
+    }
+
+    fn statistics(&self) -> String {
+        let bbbv = match self.board.bbbv() {
+            Some(bbbv) => format!("{}/{bbbv}", self.board.solved_bbbv()),
+            None => String::from("-"),
+        };
+        format!(
+            "Time        {:.1}s\n3BV         {bbbv}\n3BV/s       {:.2}\nClicks      {}\nEfficiency  {:.0}%\nHints       {}",
+            self.board.time().as_secs_f64(),
+            self.board.bbbv_per_second(),
+            self.board.clicks(),
+            self.board.efficiency(),
+            self.board.hints(),
         )
     }
 
-    fn display_screen(
+    fn export_board(&self) -> Result<PathBuf> {
+        match self.board.layout() {
+            Some(layout) => layout.export(),
+            None => Err(Error::new(
+                ErrorKind::InvalidInput,
+                "there are no mines until the first square is revealed",
+            )),
+        }
+    }
+
+    // What the last export did, or else what the hint asked for says.
+    fn play_message(&self, theme: &Theme) -> (String, Color) {
+        match &self.exported {
+            Some(Ok(path)) => {
+                return (
+                    format!("Board exported to {} and .mbf", path.display()),
+                    theme.success,
+                )
+            }
+            Some(Err(error)) => {
+                return (format!("Couldn't export the board: {error}"), theme.failure)
+            }
+            None => (),
+        }
+        match self.board.current_hint() {
+            Some(Hint::Safe) => (String::from("Hint: this square is safe"), theme.success),
+            Some(Hint::Mine) => (String::from("Hint: this square has a mine"), theme.failure),
+            Some(Hint::Guess(chances)) => {
+                let (line, column) = self.board.cursor();
+                let chance = chances[line * self.board.width() + column].unwrap_or(0);
+                (
+                    format!("Hint: nothing is sure, this square has the lowest chance of a mine ({chance}%)"),
+                    theme.warning,
+                )
+            }
+            // No board that needs no guessing was found in time, so this one may need a guess.
+            None if self.settings.options.no_guess
+                && !self.board.no_guess()
+                && !self.board.loaded() =>
+            {
+                (
+                    String::from("This board may need a guess, none without one was found"),
+                    theme.warning,
+                )
+            }
+            None => (String::new(), theme.text),
+        }
+    }
+
+    // What went wrong on the square under the cursor, if anything did.
+    fn review_message(&self, theme: &Theme) -> (String, Color) {
+        let (line, column) = self.board.cursor();
+        let message = if self.board.exploded() == Some((line, column)) {
+            "This mine went off"
+        } else {
+            match self.board.consult_position(line, column) {
+                Square::Marked(false) => "This flag was wrong, there is no mine here",
+                Square::Uncertain(false) => "This question mark was wrong, there is no mine here",
+                Square::Bomb | Square::Uncertain(true) => "This mine wasn't flagged",
+                _ => "You lost, take a look at the board",
+            }
+        };
+        (String::from(message), theme.failure)
+    }
+
+    // The leaderboards of the usual difficulties, plus the one of the custom board being played.
+    fn leaderboard_modes(&self) -> Vec<String> {
+        let mut modes = modes(&Games::Minesweeper);
+        if let Difficult::Custom { .. } = self.difficult {
+            modes.push(self.difficult.to_string());
+        }
+        modes
+    }
+
+    fn menu_guide(&self) -> String {
+        self.keymap.guide(MENU_ACTIONS, &self.settings.glyphs)
+    }
+
+    fn play_guide(&self) -> String {
+        self.keymap.guide(PLAY_ACTIONS, &self.settings.glyphs)
+    }
+
+    fn review_guide(&self) -> String {
+        self.keymap.guide(REVIEW_ACTIONS, &self.settings.glyphs)
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn display_screen<B: GameBackend>(
         &mut self,
-        terminal: &mut Terminal<CrosstermBackend<Stdout>>,
-        score: u32,
+        terminal: &mut Terminal<B>,
+        score: String,
         help_message: String,
         title: &str,
         score_title: &str,
         message: &str,
         color: Color,
     ) -> Result<()> {
-        terminal.draw(|frame| {
+        let theme = self.settings.theme.clone();
+        let theme = theme.borrow();
+        let glyphs = self.settings.glyphs;
+        let (mut help_area, mut board_area) = (Rect::default(), Rect::default());
+        draw(terminal, |frame| {
             let layout = Layout::default()
                 .direction(Direction::Horizontal)
                 .constraints([Constraint::Percentage(70), Constraint::Percentage(30)])
@@ -230,26 +740,36 @@ impl MinesweeperGameManager {
             let sub_layout = Layout::default()
                 .direction(Direction::Vertical)
                 .constraints([
-                    Constraint::Percentage(15),
-                    Constraint::Percentage(15),
-                    Constraint::Percentage(70),
+                    Constraint::Length(8),
+                    Constraint::Length(3),
+                    Constraint::Fill(1),
                 ])
                 .split(layout[1]);
 
             frame.render_widget(
-                Paragraph::new(self.board.display_board(message.to_string(), color)).block(
+                Paragraph::new(self.board.display_board(
+                    message.to_string(),
+                    color,
+                    &theme,
+                    &glyphs,
+                ))
+                .block(
                     Block::new()
                         .borders(Borders::ALL)
+                        .border_set(glyphs.border)
                         .title(title)
+                        .title_bottom(format!("Seed {}", self.board.seed()))
                         .title_alignment(Alignment::Center),
                 ),
                 layout[0],
             );
+            board_area = mouse::inner_area(layout[0]);
 
             frame.render_widget(
-                Paragraph::new(score.to_string()).block(
+                Paragraph::new(score).block(
                     Block::new()
                         .borders(Borders::ALL)
+                        .border_set(glyphs.border)
                         .title(score_title)
                         .title_alignment(Alignment::Center),
                 ),
@@ -260,6 +780,7 @@ impl MinesweeperGameManager {
                 Paragraph::new(self.board.bombs().to_string()).block(
                     Block::new()
                         .borders(Borders::ALL)
+                        .border_set(glyphs.border)
                         .title("Bombs remaining")
                         .title_alignment(Alignment::Center),
                 ),
@@ -270,19 +791,24 @@ impl MinesweeperGameManager {
                 Paragraph::new(help_message).block(
                     Block::new()
                         .borders(Borders::ALL)
+                        .border_set(glyphs.border)
                         .title("Help")
+                        .title_bottom(format!(
+                            "No-guess boards {}",
+                            on_off(self.settings.options.no_guess)
+                        ))
                         .title_alignment(Alignment::Center),
                 ),
                 sub_layout[2],
             );
+            help_area = mouse::inner_area(sub_layout[2]);
         })?;
+        self.help_area = help_area;
+        self.board_area = board_area;
         Ok(())
     }
 
-    fn display_game_rules(
-        &mut self,
-        terminal: &mut Terminal<CrosstermBackend<Stdout>>,
-    ) -> Result<()> {
+    fn display_game_rules<B: GameBackend>(&mut self, terminal: &mut Terminal<B>) -> Result<()> {
         let message = String::from(
 "Picture a grid, like a little field, filled with hidden mines and numbers. Your job is to
 uncover all the squares on the grid without detonating any mines.
@@ -301,130 +827,214 @@ with flags.
 You can mark a square as a potential mine by pressing 'm'. This helps you keep track of where
 you think the mines might be.
 
+Once a number has as many marks around it as it says, revealing it again (or pressing 'c')
+reveals every other square around it at once. If one of those marks is wrong, boom!
+
+Stuck? Press '?' for a hint: the cursor moves to a square that is surely safe or surely a mine.
+When nothing is sure, every hidden square shows its chance of a mine, in tens of percent.
+
 The game ends when you've uncovered all the squares that don't contain mines. If you manage to 
 clear the entire grid without detonating any mines, you win!
 
 So, in summary: click to uncover squares, use the numbers to avoid the mines, and mark potential
 mines with flags. It's a classic game of strategy and deduction that's perfect for relaxing and 
 exercising your brain!");
-        terminal.draw(|frame| {
+        draw(terminal, |frame| {
             let area = frame.area();
-            frame.render_widget(Paragraph::new(message).white(), area)
+            frame.render_widget(
+                Paragraph::new(message).fg(self.settings.theme.borrow().text),
+                area,
+            )
         })?;
         Ok(())
     }
 
-    fn read_menu_input(&mut self) -> Result<()> {
+    // Button pressed and the square it was pressed on, if the event is a click on the board.
+    fn clicked_square(&self, event: &Event) -> Option<(MouseButton, usize, usize)> {
+        [MouseButton::Left, MouseButton::Right, MouseButton::Middle]
+            .into_iter()
+            .find_map(|button| {
+                let (column, row) = mouse::clicked(event, button)?;
+                let area = self.board_area;
+                if !area.contains(Position::new(column, row)) {
+                    return None;
+                }
+                let (line, column) = self.board.square_at(column - area.x, row - area.y)?;
+                Some((button, line, column))
+            })
+    }
+
+    fn read_menu_input(&mut self, events: &mut dyn EventSource) -> Result<()> {
         loop {
-            let event = read()?;
-            if should_quit(&event) {
+            let event =
+                mouse::click_to_key(events.read()?, self.help_area, MENU_ACTIONS, &self.keymap);
+            if is_resize(&event) {
+                self.resized = true;
+                break;
+            } else if self.keymap.matches(Action::Quit, &event) {
                 self.menu_opt = MenuOpt::Quit;
                 break;
             } else if should_force_quit(&event) {
                 self.kill_execution = true;
                 break;
-            } else if should_help(&event) {
+            } else if self.keymap.matches(Action::Help, &event) {
                 self.menu_opt = MenuOpt::Help;
                 break;
-            } else if should_play(&event) {
+            } else if self.keymap.matches(Action::Leaderboard, &event) {
+                self.menu_opt = MenuOpt::Leaderboard;
+                break;
+            } else if self.keymap.matches(Action::Play, &event) {
                 self.menu_opt = MenuOpt::Play(self.difficult.clone());
                 break;
-            } else if matches!(
-                event,
-                Event::Key(KeyEvent {
-                    code: KeyCode::Char('e'),
-                    modifiers: KeyModifiers::NONE,
-                    kind: KeyEventKind::Press,
-                    ..
-                })
-            ) {
+            } else if self.keymap.matches(Action::Continue, &event) {
+                self.menu_opt = MenuOpt::Continue;
+                break;
+            } else if self.keymap.matches(Action::ToggleNoGuess, &event) {
+                self.menu_opt = MenuOpt::ToggleNoGuess;
+                break;
+            } else if self.keymap.matches(Action::PlayEasy, &event) {
+                self.puzzle = None;
                 self.difficult = Difficult::Easy;
                 self.menu_opt = MenuOpt::Play(self.difficult.clone());
                 break;
-            } else if matches!(
-                event,
-                Event::Key(KeyEvent {
-                    code: KeyCode::Char('m'),
-                    modifiers: KeyModifiers::NONE,
-                    kind: KeyEventKind::Press,
-                    ..
-                })
-            ) {
+            } else if self.keymap.matches(Action::PlayMedium, &event) {
+                self.puzzle = None;
                 self.difficult = Difficult::Medium;
                 self.menu_opt = MenuOpt::Play(self.difficult.clone());
                 break;
-            } else if matches!(
-                event,
-                Event::Key(KeyEvent {
-                    code: KeyCode::Char('h'),
-                    modifiers: KeyModifiers::NONE,
-                    kind: KeyEventKind::Press,
-                    ..
-                })
-            ) {
+            } else if self.keymap.matches(Action::PlayHard, &event) {
+                self.puzzle = None;
                 self.difficult = Difficult::Hard;
                 self.menu_opt = MenuOpt::Play(self.difficult.clone());
                 break;
+            } else if self.keymap.matches(Action::PlayCustom, &event) {
+                self.menu_opt = MenuOpt::Custom;
+                break;
             }
         }
         Ok(())
     }
 
-    fn read_play_input(&mut self) -> Result<()> {
-        loop {
-            let event = read()?;
-            if should_move_up(&event) {
-                self.play_opt = PlayOpt::Direction(Directions::Up);
-                break;
-            } else if should_move_down(&event) {
-                self.play_opt = PlayOpt::Direction(Directions::Down);
-                break;
-            } else if should_move_left(&event) {
-                self.play_opt = PlayOpt::Direction(Directions::Left);
-                break;
-            } else if should_move_right(&event) {
-                self.play_opt = PlayOpt::Direction(Directions::Right);
-                break;
-            } else if should_quit(&event) {
-                self.play_opt = PlayOpt::Quit;
-                break;
-            } else if should_force_quit(&event) {
-                self.kill_execution = true;
-                break;
-            } else if matches!(
-                event,
-                Event::Key(KeyEvent {
-                    code: KeyCode::Char('m'),
-                    modifiers: KeyModifiers::NONE,
-                    kind: KeyEventKind::Press,
-                    ..
-                }) | Event::Key(KeyEvent {
-                    code: KeyCode::Char('!'),
-                    modifiers: KeyModifiers::NONE,
-                    kind: KeyEventKind::Press,
-                    ..
-                })
-            ) {
-                self.play_opt = PlayOpt::Mark;
-                break;
-            } else if matches!(
-                event,
-                Event::Key(KeyEvent {
-                    code: KeyCode::Enter,
-                    modifiers: KeyModifiers::NONE,
-                    kind: KeyEventKind::Press,
-                    ..
-                }) | Event::Key(KeyEvent {
-                    code: KeyCode::Char('r'),
-                    modifiers: KeyModifiers::NONE,
-                    kind: KeyEventKind::Press,
-                    ..
-                })
-            ) {
-                self.play_opt = PlayOpt::Reveal;
-                break;
-            }
+    // Only moving around the board and leaving it are read, the game is over.
+    fn read_review_input(&mut self, events: &mut dyn EventSource) -> Result<()> {
+        self.play_opt = PlayOpt::None;
+        let event =
+            mouse::click_to_key(events.read()?, self.help_area, REVIEW_ACTIONS, &self.keymap);
+        if let Some((button, line, column)) = self.clicked_square(&event) {
+            self.play_opt = PlayOpt::Click(button, line, column);
+        } else if is_resize(&event) {
+            self.resized = true;
+        } else if self.keymap.matches(Action::MoveUp, &event) {
+            self.play_opt = PlayOpt::Direction(Directions::Up);
+        } else if self.keymap.matches(Action::MoveDown, &event) {
+            self.play_opt = PlayOpt::Direction(Directions::Down);
+        } else if self.keymap.matches(Action::MoveLeft, &event) {
+            self.play_opt = PlayOpt::Direction(Directions::Left);
+        } else if self.keymap.matches(Action::MoveRight, &event) {
+            self.play_opt = PlayOpt::Direction(Directions::Right);
+        } else if self.keymap.matches(Action::Quit, &event) {
+            self.play_opt = PlayOpt::Quit;
+        } else if should_force_quit(&event) {
+            self.kill_execution = true;
+        }
+        Ok(())
+    }
+
+    fn read_play_input(&mut self, events: &mut dyn EventSource) -> Result<()> {
+        self.play_opt = PlayOpt::None;
+        let event = events.read()?;
+        if let Some((button, line, column)) = self.clicked_square(&event) {
+            self.play_opt = PlayOpt::Click(button, line, column);
+        } else if is_resize(&event) {
+            self.resized = true;
+        } else if self.keymap.matches(Action::MoveUp, &event) {
+            self.play_opt = PlayOpt::Direction(Directions::Up);
+        } else if self.keymap.matches(Action::MoveDown, &event) {
+            self.play_opt = PlayOpt::Direction(Directions::Down);
+        } else if self.keymap.matches(Action::MoveLeft, &event) {
+            self.play_opt = PlayOpt::Direction(Directions::Left);
+        } else if self.keymap.matches(Action::MoveRight, &event) {
+            self.play_opt = PlayOpt::Direction(Directions::Right);
+        } else if self.keymap.matches(Action::Quit, &event) {
+            self.play_opt = PlayOpt::Quit;
+        } else if should_force_quit(&event) {
+            self.kill_execution = true;
+        } else if self.keymap.matches(Action::Mark, &event) {
+            self.play_opt = PlayOpt::Mark;
+        } else if self.keymap.matches(Action::Reveal, &event) {
+            self.play_opt = PlayOpt::Reveal;
+        } else if self.keymap.matches(Action::Chord, &event) {
+            self.play_opt = PlayOpt::Chord;
+        } else if self.keymap.matches(Action::Hint, &event) {
+            self.play_opt = PlayOpt::Hint;
+        } else if self.keymap.matches(Action::Export, &event) {
+            self.play_opt = PlayOpt::Export;
         }
         Ok(())
     }
 }
+
+// Terminal size needed to play on `board`.
+fn board_min_size(board: &Board, theme: &Theme, glyphs: &Glyphs) -> (u16, u16) {
+    game_manager::min_size_for(
+        &board.display_board(String::new(), Color::Reset, theme, glyphs),
+        70,
+        100,
+    )
+}
+
+// Refuses custom boards out of bounds, too crowded or too big for a `screen` of known size.
+pub fn check_custom_board(
+    height: usize,
+    width: usize,
+    mines: usize,
+    screen: Option<(u16, u16)>,
+    glyphs: &Glyphs,
+) -> Result<()> {
+    let invalid = |message: String| Err(Error::new(ErrorKind::InvalidInput, message));
+    if !(MIN_SIDE..=MAX_SIDE).contains(&height) || !(MIN_SIDE..=MAX_SIDE).contains(&width) {
+        return invalid(format!(
+            "height and width must be between {MIN_SIDE} and {MAX_SIDE}"
+        ));
+    }
+    let max_mines = height * width * MAX_DENSITY / 100;
+    if mines == 0 || mines > max_mines {
+        return invalid(format!(
+            "a {height}x{width} board takes between 1 and {max_mines} mines"
+        ));
+    }
+    let board = Board::new(
+        &Difficult::Custom {
+            height,
+            width,
+            mines,
+        },
+        0,
+        false,
+        false,
+    );
+    let (needed_width, needed_height) = board_min_size(&board, &Theme::default(), glyphs);
+    if let Some((columns, rows)) = screen {
+        if needed_width > columns || needed_height > rows {
+            return invalid(format!(
+                "a {height}x{width} board needs a {needed_width}x{needed_height} terminal, this one is {columns}x{rows}"
+            ));
+        }
+    }
+    Ok(())
+}
+
+// The board of a text or .mbf file as text, refusing those that can't be played as a custom one.
+pub fn read_board_file(path: &Path, screen: Option<(u16, u16)>, glyphs: &Glyphs) -> Result<String> {
+    let layout = BoardLayout::read(path)?;
+    check_custom_board(layout.height, layout.width, layout.mines(), screen, glyphs)?;
+    Ok(layout.to_text())
+}
+
+fn on_off(enabled: bool) -> &'static str {
+    if enabled {
+        "on"
+    } else {
+        "off"
+    }
+}
@@ -1,9 +1,17 @@
 use ratatui::{
-    style::{Color, Style, Stylize},
+    style::{Color, Modifier, Style},
     text::{Line, Span},
 };
 
-use crate::tgames::game_manager::{Difficult, Directions};
+use super::{
+    board_file::BoardLayout,
+    solver::{Deduction, Solver},
+};
+use crate::tgames::game_manager::{new_rng, serde_rng, serde_seed, Difficult, Directions, GameRng};
+use crate::tgames::{glyphs::Glyphs, theme::Theme};
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use std::time::{Duration, Instant};
 
 const EASY_BOARD_INFO: BoardInfo = BoardInfo {
     height: 9,
@@ -20,15 +28,18 @@ const HARD_BOARD_INFO: BoardInfo = BoardInfo {
     width: 30,
     number_of_bombs: 100,
 };
+// Boards that need guessing are dealt again this many times at most, then the last one is kept.
+// Bounded by attempts rather than time, so a seed deals the same board on any machine.
+const MAX_NO_GUESS_ATTEMPTS: u32 = 200;
 
-#[derive(Clone)]
+#[derive(Clone, Serialize, Deserialize)]
 struct BoardInfo {
     pub height: usize,
     pub width: usize,
     pub number_of_bombs: i32,
 }
 
-#[derive(PartialEq, Eq)]
+#[derive(PartialEq, Eq, Clone, Serialize, Deserialize)]
 struct SquarePosition {
     line: i32,
     column: i32,
@@ -38,8 +49,8 @@ const NOT_REVEALED: SquarePosition = SquarePosition {
     column: -1,
 };
 
-#[derive(PartialEq, Clone)]
-enum Square {
+#[derive(PartialEq, Clone, Serialize, Deserialize)]
+pub enum Square {
     Close(usize),
     Opened(usize),
     Marked(bool),
@@ -47,6 +58,16 @@ enum Square {
     Bomb,
 }
 
+// What a hint told about the square under the cursor, shown until the next move.
+#[derive(Clone)]
+pub enum Hint {
+    Safe,
+    Mine,
+    // Nothing is certain, these are the chances of a mine on each square, in percent.
+    Guess(Vec<Option<u8>>),
+}
+
+#[derive(Clone, Serialize, Deserialize)]
 pub struct Board {
     board: Vec<Square>,
     board_info: BoardInfo,
@@ -54,11 +75,44 @@ pub struct Board {
     curr_line: usize,
     curr_column: usize,
     revealed_bomb: SquarePosition,
-    score: u32,
+    // Time played until `started`, when the clock last started running.
+    #[serde(default)]
+    time: Duration,
+    #[serde(skip)]
+    started: Option<Instant>,
+    // Squares revealed, marked or chorded by the player, whether it changed anything or not.
+    #[serde(default)]
+    clicks: u32,
+    // Least clicks needed to clear the board, known once the mines are placed.
+    #[serde(default)]
+    bbbv: u32,
+    #[serde(default)]
+    hints: u32,
+    #[serde(skip)]
+    hint: Option<Hint>,
+    // The mines are only placed once the first square is revealed, away from it.
+    #[serde(default)]
+    pending_generation: bool,
+    // Only boards that can be cleared with logic alone from the first square revealed.
+    #[serde(default)]
+    no_guess: bool,
+    // Read from a file, where the mines are was known beforehand so it is never ranked.
+    #[serde(default)]
+    loaded: bool,
+    #[serde(with = "serde_seed")]
+    seed: u64,
+    #[serde(with = "serde_rng")]
+    rng: GameRng,
+}
+
+impl Default for Board {
+    fn default() -> Self {
+        Self::new(&Difficult::Medium, rand::random(), false, false)
+    }
 }
 
 impl Board {
-    pub fn new() -> Self {
+    pub fn new(difficult: &Difficult, seed: u64, auto_open: bool, no_guess: bool) -> Self {
         let mut instance = Self {
             board: Vec::new(),
             board_info: BoardInfo {
@@ -73,12 +127,64 @@ impl Board {
                 line: -1,
                 column: -1,
             },
-            score: 0,
+            time: Duration::ZERO,
+            started: None,
+            clicks: 0,
+            bbbv: 0,
+            hints: 0,
+            hint: None,
+            pending_generation: false,
+            no_guess,
+            loaded: false,
+            seed,
+            rng: new_rng(seed),
         };
-        instance.reset(&Difficult::Medium);
+        instance.reset(difficult, seed, auto_open, no_guess);
+        instance
+    }
+
+    // Board with the squares of `layout`, its mines are placed so the clock runs from the start.
+    pub fn from_layout(layout: &BoardLayout, seed: u64) -> Self {
+        let mines = layout.mines();
+        let difficult = Difficult::Custom {
+            height: layout.height,
+            width: layout.width,
+            mines,
+        };
+        let mut instance = Self::new(&difficult, seed, false, false);
+        instance.board = layout.squares.clone();
+        instance.pending_generation = false;
+        instance.loaded = true;
+        let marks = instance
+            .board
+            .iter()
+            .filter(|square| matches!(square, Square::Marked(_)))
+            .count();
+        instance.board_info.number_of_bombs = mines as i32 - marks as i32;
+        instance.count_bombs_around();
+        for index in 0..instance.board.len() {
+            if let Square::Opened(_) = instance.board[index] {
+                let (line, column) = (index / layout.width, index % layout.width);
+                instance.board[index] = Square::Opened(instance.mines_around(line, column));
+            }
+        }
+        instance.bbbv = instance.count_bbbv(false);
         instance
     }
 
+    // Squares of the board as they are now, None until the mines are placed.
+    pub fn layout(&self) -> Option<BoardLayout> {
+        (!self.pending_generation).then(|| BoardLayout {
+            height: self.board_info.height,
+            width: self.board_info.width,
+            squares: self.board.clone(),
+        })
+    }
+
+    pub fn loaded(&self) -> bool {
+        self.loaded
+    }
+
     pub fn won(&self) -> bool {
         for line in 0..self.board_info.height {
             for column in 0..self.board_info.width {
@@ -98,6 +204,14 @@ impl Board {
         self.revealed_bomb != NOT_REVEALED
     }
 
+    // Line and column of the mine that went off.
+    pub fn exploded(&self) -> Option<(usize, usize)> {
+        self.lost().then_some((
+            self.revealed_bomb.line as usize,
+            self.revealed_bomb.column as usize,
+        ))
+    }
+
     pub fn bombs(&self) -> i32 {
         self.board_info.number_of_bombs
     }
@@ -107,43 +221,241 @@ impl Board {
         self.hide_cursor = true;
     }
 
-    pub fn reset(&mut self, difficult: &Difficult) {
-        self.score = 0;
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    pub fn no_guess(&self) -> bool {
+        self.no_guess
+    }
+
+    // Mines wait for the first reveal to be placed away from it, unless `auto_open` opens an area.
+    pub fn reset(&mut self, difficult: &Difficult, seed: u64, auto_open: bool, no_guess: bool) {
+        self.seed = seed;
+        self.no_guess = no_guess;
+        self.loaded = false;
+        self.rng = new_rng(seed);
+        self.time = Duration::ZERO;
+        self.started = None;
+        self.clicks = 0;
+        self.bbbv = 0;
+        self.hints = 0;
+        self.hint = None;
         self.hide_cursor = false;
         self.revealed_bomb = NOT_REVEALED;
         match difficult {
             Difficult::Easy => self.board_info = EASY_BOARD_INFO,
             Difficult::Medium => self.board_info = MEDIUM_BOARD_INFO,
             Difficult::Hard => self.board_info = HARD_BOARD_INFO,
+            Difficult::Custom {
+                height,
+                width,
+                mines,
+            } => {
+                self.board_info = BoardInfo {
+                    height: *height,
+                    width: *width,
+                    number_of_bombs: *mines as i32,
+                }
+            }
         }
         self.board = vec![Square::Close(0); self.board_info.width * self.board_info.height];
-        for _ in 0..self.board_info.number_of_bombs {
-            let mut index = rand::random_range(0..self.board.len());
-            while self.board[index] == Square::Bomb {
+        self.pending_generation = !auto_open;
+        if !auto_open {
+            self.curr_line = self.board_info.height / 2;
+            self.curr_column = self.board_info.width / 2;
+            return;
+        }
+        (self.curr_line, self.curr_column) = self.place_bombs(None);
+        self.reveal_block(self.curr_line, self.curr_column);
+    }
+
+    // Places the mines away from `safe` and returns the square the game opens on.
+    fn place_bombs(&mut self, safe: Option<(usize, usize)>) -> (usize, usize) {
+        let marked = self
+            .board
+            .iter()
+            .filter(|square| matches!(square, Square::Marked(_)))
+            .count();
+        let bombs = self.board_info.number_of_bombs as usize + marked;
+        let mut excluded = vec![false; self.board.len()];
+        if let Some((line, column)) = safe {
+            let mut around = self.neighbours(line, column);
+            around.push((line, column));
+            if self.board.len() - around.len() < bombs {
+                around = vec![(line, column)];
+            }
+            for (line, column) in around {
+                excluded[line * self.board_info.width + column] = true;
+            }
+        }
+        let mut attempts = 0;
+        loop {
+            self.scatter_bombs(bombs, &excluded);
+            let opening = safe.unwrap_or_else(|| self.random_opening());
+            attempts += 1;
+            if self.no_guess && !self.solvable(opening) {
+                if attempts < MAX_NO_GUESS_ATTEMPTS {
+                    self.remove_bombs();
+                    continue;
+                }
+                // The last board is played anyway, just not as one that needs no guessing.
+                self.no_guess = false;
+            }
+            self.bbbv = self.count_bbbv(false);
+            return opening;
+        }
+    }
+
+    // 3BV: one per opening plus one per number off their edges, only opened ones with `solved`.
+    fn count_bbbv(&self, solved: bool) -> u32 {
+        let width = self.board_info.width;
+        let empty = |index: usize| {
+            !Self::has_bomb(&self.board[index])
+                && self.mines_around(index / width, index % width) == 0
+        };
+        let opened = |index: usize| matches!(self.board[index], Square::Opened(_));
+        let mut counted = vec![false; self.board.len()];
+        let mut bbbv = 0;
+        for index in 0..self.board.len() {
+            if counted[index] || !empty(index) {
+                continue;
+            }
+            let mut opening_opened = false;
+            let mut pending = vec![index];
+            counted[index] = true;
+            while let Some(current) = pending.pop() {
+                opening_opened |= opened(current);
+                for (line, column) in self.neighbours(current / width, current % width) {
+                    let neighbour = line * width + column;
+                    if !counted[neighbour] {
+                        counted[neighbour] = true;
+                        if empty(neighbour) {
+                            pending.push(neighbour);
+                        }
+                    }
+                }
+            }
+            if !solved || opening_opened {
+                bbbv += 1;
+            }
+        }
+        for (index, counted) in counted.into_iter().enumerate() {
+            if !counted && !Self::has_bomb(&self.board[index]) && (!solved || opened(index)) {
+                bbbv += 1;
+            }
+        }
+        bbbv
+    }
+
+    fn mines_around(&self, line: usize, column: usize) -> usize {
+        self.neighbours(line, column)
+            .into_iter()
+            .filter(|(line, column)| Self::has_bomb(self.consult_position(*line, *column)))
+            .count()
+    }
+
+    fn scatter_bombs(&mut self, bombs: usize, excluded: &[bool]) {
+        for _ in 0..bombs {
+            let mut index = self.rng.random_range(0..self.board.len());
+            while excluded[index] || Self::has_bomb(&self.board[index]) {
                 index += 1;
                 index %= self.board.len();
             }
-            self.board[index] = Square::Bomb;
+            self.board[index] = match self.board[index] {
+                Square::Marked(_) => Square::Marked(true),
+                Square::Uncertain(_) => Square::Uncertain(true),
+                _ => Square::Bomb,
+            };
         }
+        self.count_bombs_around();
+    }
+
+    fn remove_bombs(&mut self) {
+        for square in self.board.iter_mut() {
+            *square = match square {
+                Square::Marked(_) => Square::Marked(false),
+                Square::Uncertain(_) => Square::Uncertain(false),
+                _ => Square::Close(0),
+            };
+        }
+    }
+
+    fn count_bombs_around(&mut self) {
         for line in 0..self.board_info.height {
             for column in 0..self.board_info.width {
                 self.update_close_square_counter(line, column);
             }
         }
-        self.curr_line = rand::random_range(0..self.board_info.height);
-        self.curr_column = rand::random_range(0..self.board_info.width);
+    }
+
+    // A random empty square to open on, or any without a mine on crowded boards that have none.
+    fn random_opening(&mut self) -> (usize, usize) {
+        let mut line = self.rng.random_range(0..self.board_info.height);
+        let mut column = self.rng.random_range(0..self.board_info.width);
+        let has_empty = self.board.contains(&Square::Close(0));
         let mut counter = 0;
-        while *self.consult_board() != Square::Close(0) {
-            self.curr_column += 1;
-            self.curr_column %= self.board_info.width;
+        while *self.consult_position(line, column) != Square::Close(0)
+            && (has_empty || !matches!(self.consult_position(line, column), Square::Close(_)))
+        {
+            column += 1;
+            column %= self.board_info.width;
             counter += 1;
             if counter == self.board_info.width {
                 counter = 0;
-                self.curr_line += 1;
-                self.curr_line %= self.board_info.height;
+                line += 1;
+                line %= self.board_info.height;
             }
         }
-        self.reveal_block(self.curr_line, self.curr_column);
+        (line, column)
+    }
+
+    // Whether logic alone clears the board from `opening`, only proven safe squares are opened.
+    fn solvable(&self, opening: (usize, usize)) -> bool {
+        let mut board = self.clone();
+        // Marks don't tell anything about the mines, they were put before there were any.
+        for square in board.board.iter_mut() {
+            *square = if Self::has_bomb(square) {
+                Square::Bomb
+            } else {
+                Square::Close(0)
+            };
+        }
+        board.count_bombs_around();
+        board.reveal_block(opening.0, opening.1);
+        let mines = board
+            .board
+            .iter()
+            .filter(|square| Self::has_bomb(square))
+            .count();
+        let width = board.board_info.width;
+        let mut solver = Solver::new(&board.board, width, board.board_info.height, mines);
+        loop {
+            let safe: Vec<usize> = solver
+                .solve()
+                .into_iter()
+                .filter(|(_, deduction)| *deduction == Deduction::Safe)
+                .map(|(index, _)| index)
+                .collect();
+            if safe.is_empty() {
+                break;
+            }
+            for index in safe {
+                board.reveal_block(index / width, index % width);
+            }
+            solver.update(&board.board);
+        }
+        !board
+            .board
+            .iter()
+            .any(|square| matches!(square, Square::Close(_)))
+    }
+
+    pub fn has_bomb(square: &Square) -> bool {
+        matches!(
+            square,
+            Square::Bomb | Square::Marked(true) | Square::Uncertain(true)
+        )
     }
 
     fn update_close_square_counter(&mut self, line: usize, column: usize) {
@@ -154,8 +466,9 @@ impl Board {
                     let consult_line = line as i32 + line_offset;
                     let consult_column = column as i32 + column_offset;
                     if self.in_bounds(consult_line, consult_column)
-                        && *self.consult_position(consult_line as usize, consult_column as usize)
-                            == Square::Bomb
+                        && Self::has_bomb(
+                            self.consult_position(consult_line as usize, consult_column as usize),
+                        )
                     {
                         counter += 1;
                     }
@@ -166,6 +479,8 @@ impl Board {
     }
 
     pub fn mark(&mut self) {
+        self.clicks += 1;
+        self.hint = None;
         match *self.consult_board() {
             Square::Close(_) => {
                 self.board_info.number_of_bombs -= 1;
@@ -191,7 +506,6 @@ impl Board {
     }
 
     pub fn reveal_block(&mut self, line: usize, column: usize) {
-        self.score += 1;
         let amount = if let Square::Close(amount) = *self.consult_position(line, column) {
             amount
         } else {
@@ -221,17 +535,161 @@ impl Board {
     }
 
     pub fn reveal(&mut self) {
+        self.clicks += 1;
+        self.hint = None;
+        if self.pending_generation && matches!(self.consult_board(), Square::Close(_)) {
+            self.pending_generation = false;
+            self.place_bombs(Some((self.curr_line, self.curr_column)));
+        }
         match *self.get_position() {
             Square::Close(_) => self.reveal_block(self.curr_line, self.curr_column),
             Square::Bomb => {
                 self.revealed_bomb.line = self.curr_line as i32;
                 self.revealed_bomb.column = self.curr_column as i32;
             }
-            Square::Opened(_) | Square::Marked(_) | Square::Uncertain(_) => (),
+            Square::Opened(_) => self.reveal_around(),
+            Square::Marked(_) | Square::Uncertain(_) => (),
         };
     }
 
+    pub fn chord(&mut self) {
+        self.clicks += 1;
+        self.hint = None;
+        self.reveal_around();
+    }
+
+    // Opens around a number with as many marks as its mines, a wrong mark sets a mine off.
+    fn reveal_around(&mut self) {
+        let amount = match *self.consult_board() {
+            Square::Opened(amount) if amount > 0 => amount,
+            _ => return,
+        };
+        let neighbours = self.neighbours(self.curr_line, self.curr_column);
+        let marks = neighbours
+            .iter()
+            .filter(|(line, column)| {
+                matches!(self.consult_position(*line, *column), Square::Marked(_))
+            })
+            .count();
+        if marks != amount {
+            return;
+        }
+        for (line, column) in neighbours {
+            match *self.consult_position(line, column) {
+                Square::Close(_) => self.reveal_block(line, column),
+                Square::Bomb if !self.lost() => {
+                    self.revealed_bomb.line = line as i32;
+                    self.revealed_bomb.column = column as i32;
+                }
+                _ => (),
+            }
+        }
+    }
+
+    // Points the cursor at a proven safe square or unmarked mine, or else shows the mine chances.
+    pub fn hint(&mut self) {
+        self.hints += 1;
+        if self.pending_generation {
+            // Nothing is around the first square revealed.
+            self.hint = Some(Hint::Safe);
+            return;
+        }
+        let width = self.board_info.width;
+        let mines = self
+            .board
+            .iter()
+            .filter(|square| Self::has_bomb(square))
+            .count();
+        let mut solver = Solver::new(&self.board, width, self.board_info.height, mines);
+        let found = solver.solve();
+        let unmarked = |index: usize| !matches!(self.board[index], Square::Marked(_));
+        let useful = found
+            .iter()
+            .any(|(index, deduction)| *deduction == Deduction::Safe || unmarked(*index));
+        let probabilities = if useful {
+            Vec::new()
+        } else {
+            solver.probabilities()
+        };
+        // Layouts counted one by one can prove what the rules didn't.
+        let certain = probabilities
+            .iter()
+            .enumerate()
+            .filter_map(|(index, chance)| match chance {
+                Some(chance) if *chance < 1e-9 => Some((index, Deduction::Safe)),
+                Some(chance) if *chance > 1.0 - 1e-9 => Some((index, Deduction::Mine)),
+                _ => None,
+            });
+        let deductions: Vec<(usize, Deduction)> = found.into_iter().chain(certain).collect();
+        let distance = |index: usize| {
+            (index / width)
+                .abs_diff(self.curr_line)
+                .max((index % width).abs_diff(self.curr_column))
+        };
+        let closest = |deduction: Deduction| {
+            deductions
+                .iter()
+                .filter(|(index, found)| {
+                    *found == deduction && (deduction == Deduction::Safe || unmarked(*index))
+                })
+                .map(|(index, _)| *index)
+                .min_by_key(|index| distance(*index))
+        };
+        let (index, hint) = if let Some(index) = closest(Deduction::Safe) {
+            (index, Hint::Safe)
+        } else if let Some(index) = closest(Deduction::Mine) {
+            (index, Hint::Mine)
+        } else {
+            let safest = probabilities
+                .iter()
+                .enumerate()
+                .filter(|(index, _)| unmarked(*index))
+                .filter_map(|(index, chance)| chance.map(|chance| (index, chance)))
+                .min_by(|(_, first), (_, second)| first.total_cmp(second))
+                .map_or(self.curr_line * width + self.curr_column, |(index, _)| {
+                    index
+                });
+            let percents = probabilities
+                .iter()
+                .map(|chance| chance.map(|chance| (chance * 100.0).round().clamp(1.0, 99.0) as u8))
+                .collect();
+            (safest, Hint::Guess(percents))
+        };
+        self.curr_line = index / width;
+        self.curr_column = index % width;
+        self.hint = Some(hint);
+    }
+
+    pub fn current_hint(&self) -> Option<&Hint> {
+        self.hint.as_ref()
+    }
+
+    pub fn cursor(&self) -> (usize, usize) {
+        (self.curr_line, self.curr_column)
+    }
+
+    pub fn hints(&self) -> u32 {
+        self.hints
+    }
+
+    pub fn move_cursor_to(&mut self, line: usize, column: usize) {
+        self.hint = None;
+        if line < self.board_info.height && column < self.board_info.width {
+            self.curr_line = line;
+            self.curr_column = column;
+        }
+    }
+
+    // Line and column of the square drawn at `column` and `row` of what `display_board` returns.
+    pub fn square_at(&self, column: u16, row: u16) -> Option<(usize, usize)> {
+        // The top border comes first, and every line starts with the side border and a space.
+        let line = (row as usize).checked_sub(1)?;
+        let column = (column as usize).checked_sub(2)? / 2;
+        (line < self.board_info.height && column < self.board_info.width).then_some((line, column))
+    }
+
     pub fn move_cursor(&mut self, direction: &Directions) {
+        self.hint = None;
         match direction {
             Directions::Up => {
                 if self.curr_line == 0 {
@@ -262,38 +720,81 @@ impl Board {
         }
     }
 
-    pub fn display_board(&self, message: String, color: Color) -> Vec<Line<'_>> {
+    pub fn display_board(
+        &self,
+        message: String,
+        color: Color,
+        theme: &Theme,
+        glyphs: &Glyphs,
+    ) -> Vec<Line<'_>> {
         let mut lines = Vec::new();
-        self.push_horizontal_board(&mut lines, true);
+        self.push_horizontal_board(&mut lines, true, theme, glyphs);
         for line in 0..self.board_info.height {
             let mut spans = Vec::new();
-            spans.push(Span::styled("│ ", Style::default().fg(Color::DarkGray)));
+            spans.push(Span::styled(
+                glyphs.line.vertical.to_string() + " ",
+                Style::default().fg(theme.dim),
+            ));
             for column in 0..self.board_info.width {
+                let cursor_style =
+                    if line == self.curr_line && column == self.curr_column && !self.hide_cursor {
+                        if let Some(Hint::Safe) = self.hint {
+                            Style::default().fg(Color::Black).bg(theme.success)
+                        } else if let Some(Hint::Mine) = self.hint {
+                            Style::default().fg(Color::Black).bg(theme.failure)
+                        } else if theme.patterns {
+                            Style::default().add_modifier(Modifier::REVERSED)
+                        } else {
+                            Style::default().bg(theme.cursor)
+                        }
+                    } else {
+                        Style::default()
+                    };
                 if line as i32 == self.revealed_bomb.line
                     && column as i32 == self.revealed_bomb.column
                 {
-                    spans.push(Span::styled(
-                        "󰚑 ",
-                        Style::default().fg(Color::Black).bg(Color::LightRed),
-                    ));
-                    continue;
-                }
-                let background_color =
-                    if line == self.curr_line && column == self.curr_column && !self.hide_cursor {
-                        Color::Gray
+                    // With patterns, the exploded mine has a glyph of its own.
+                    let exploded = if theme.patterns {
+                        glyphs.exploded
                     } else {
-                        Color::Reset
+                        glyphs.mine
                     };
+                    // The cursor can be moved over it while the lost game is reviewed.
+                    spans.push(
+                        Span::styled(
+                            exploded,
+                            Style::default().fg(Color::Black).bg(theme.exploded),
+                        )
+                        .patch_style(cursor_style),
+                    );
+                    continue;
+                }
+                let chance = match &self.hint {
+                    Some(Hint::Guess(chances)) => chances[line * self.board_info.width + column],
+                    _ => None,
+                };
+                if let Some(chance) = chance.filter(|_| {
+                    !matches!(
+                        self.consult_position(line, column),
+                        Square::Opened(_) | Square::Marked(_)
+                    )
+                }) {
+                    // Squares are too narrow for the percent, tens of it have to do.
+                    let tens = (chance + 5) / 10;
+                    spans.push(
+                        Span::styled(
+                            format!("{} ", tens.clamp(1, 9)),
+                            Style::default().fg(theme.warning),
+                        )
+                        .patch_style(cursor_style),
+                    );
+                    continue;
+                }
                 match *self.consult_position(line, column) {
                     Square::Opened(amount) => {
                         let color = match amount {
-                            1 => Color::LightBlue,
-                            2 => Color::LightGreen,
-                            3 => Color::LightRed,
-                            4 => Color::Blue,
-                            5 => Color::Red,
-                            6 => Color::Yellow,
-                            _ => Color::Magenta,
+                            0 => theme.text,
+                            _ => theme.numbers[(amount - 1).min(7)],
                         };
                         let amount = if amount == 0 {
                             "  ".to_string()
@@ -302,84 +803,183 @@ impl Board {
                         };
                         spans.push(Span::styled(
                             amount,
-                            Style::default().fg(color).bg(background_color),
+                            Style::default().fg(color).patch(cursor_style),
                         ));
                     }
-                    Square::Marked(correct) => {
-                        if self.revealed_bomb == NOT_REVEALED || correct {
-                            spans.push(
-                                Span::styled("󰈿 ", Style::default().fg(Color::Red))
-                                    .bg(background_color),
-                            );
-                        } else {
-                            spans.push(
-                                Span::styled("󰛅 ", Style::default().fg(Color::DarkGray))
-                                    .bg(background_color),
-                            );
-                        }
+                    // Marks put where there was no mine stand out like the mine that went off.
+                    Square::Marked(false) | Square::Uncertain(false) if self.lost() => {
+                        spans.push(
+                            Span::styled(
+                                glyphs.wrong_flag,
+                                Style::default().fg(theme.text).bg(theme.wrong_flag),
+                            )
+                            .patch_style(cursor_style),
+                        );
+                    }
+                    Square::Marked(_) => {
+                        spans.push(
+                            Span::styled(glyphs.flag, Style::default().fg(theme.flag))
+                                .patch_style(cursor_style),
+                        );
                     }
                     Square::Uncertain(correct) => {
-                        if self.revealed_bomb != NOT_REVEALED && !correct {
+                        if self.lost() && correct {
                             spans.push(
-                                Span::styled("󰛅 ", Style::default().fg(Color::DarkGray))
-                                    .bg(background_color),
+                                Span::styled(glyphs.mine, Style::default().fg(theme.mine))
+                                    .patch_style(cursor_style),
                             );
                         } else {
                             spans.push(
-                                Span::styled("? ", Style::default().fg(Color::Magenta))
-                                    .bg(background_color),
+                                Span::styled(
+                                    glyphs.uncertain,
+                                    Style::default().fg(theme.uncertain),
+                                )
+                                .patch_style(cursor_style),
                             );
                         }
                     }
                     Square::Close(_) => {
                         spans.push(
-                            Span::styled(" ", Style::default().fg(Color::DarkGray))
-                                .bg(background_color),
+                            Span::styled(glyphs.closed, Style::default().fg(theme.dim))
+                                .patch_style(cursor_style),
                         );
                     }
                     Square::Bomb => {
                         if self.revealed_bomb != NOT_REVEALED {
-                            spans.push(Span::styled("󰚑 ", Style::default().fg(Color::LightRed)));
+                            spans.push(
+                                Span::styled(glyphs.mine, Style::default().fg(theme.mine))
+                                    .patch_style(cursor_style),
+                            );
                         } else {
                             spans.push(
-                                Span::styled(" ", Style::default().fg(Color::DarkGray))
-                                    .bg(background_color),
+                                Span::styled(glyphs.closed, Style::default().fg(theme.dim))
+                                    .patch_style(cursor_style),
                             );
                         }
                     }
                 }
             }
-            spans.push(Span::styled(" │", Style::default().fg(Color::DarkGray)));
+            spans.push(Span::styled(
+                " ".to_string() + glyphs.line.vertical,
+                Style::default().fg(theme.dim),
+            ));
             lines.push(Line::from(spans));
         }
-        self.push_horizontal_board(&mut lines, false);
+        self.push_horizontal_board(&mut lines, false, theme, glyphs);
         lines.push(Line::from(Span::styled(message, color)));
         lines
     }
 
-    fn push_horizontal_board(&self, lines: &mut Vec<Line>, up: bool) {
+    fn push_horizontal_board(
+        &self,
+        lines: &mut Vec<Line>,
+        up: bool,
+        theme: &Theme,
+        glyphs: &Glyphs,
+    ) {
         let mut spans: String = String::new();
         if up {
-            spans += "╭";
+            spans += glyphs.line.top_left;
         } else {
-            spans += "╰";
+            spans += glyphs.line.bottom_left;
         }
         for _ in 0..self.board_info.width * 2 + 2 {
-            spans += "─";
+            spans += glyphs.line.horizontal;
         }
         if up {
-            spans += "╮";
+            spans += glyphs.line.top_right;
         } else {
-            spans += "╯";
+            spans += glyphs.line.bottom_right;
         }
         lines.push(Line::from(Span::styled(
             spans,
-            Style::default().fg(Color::DarkGray),
+            Style::default().fg(theme.dim),
         )));
     }
 
-    pub fn score(&self) -> u32 {
-        self.score
+    // The clock runs from the first square revealed until the game ends.
+    pub fn timing(&self) -> bool {
+        !self.pending_generation && !self.lost() && !self.won()
+    }
+
+    // Starts or stops the clock, keeping the time counted so far.
+    pub fn run_clock(&mut self, running: bool) {
+        match (running, self.started) {
+            (true, None) => self.started = Some(Instant::now()),
+            (false, Some(started)) => {
+                self.time += started.elapsed();
+                self.started = None;
+            }
+            _ => (),
+        }
+    }
+
+    pub fn time(&self) -> Duration {
+        self.time
+            + self
+                .started
+                .map_or(Duration::ZERO, |started| started.elapsed())
+    }
+
+    pub fn clicks(&self) -> u32 {
+        self.clicks
+    }
+
+    // None until the mines are placed.
+    pub fn bbbv(&self) -> Option<u32> {
+        (!self.pending_generation).then_some(self.bbbv)
+    }
+
+    pub fn solved_bbbv(&self) -> u32 {
+        self.count_bbbv(true)
+    }
+
+    // 3BV solved per second.
+    pub fn bbbv_per_second(&self) -> f64 {
+        let time = self.time();
+        if time.is_zero() {
+            0.0
+        } else {
+            self.solved_bbbv() as f64 / time.as_secs_f64()
+        }
+    }
+
+    // Hundredths of the 3BV solved per second, what won games are ranked by.
+    pub fn ranking_score(&self) -> u32 {
+        (self.bbbv_per_second() * 100.0).round() as u32
+    }
+
+    // 3BV solved per click, in percent.
+    pub fn efficiency(&self) -> f64 {
+        if self.clicks == 0 {
+            0.0
+        } else {
+            self.solved_bbbv() as f64 * 100.0 / self.clicks as f64
+        }
+    }
+
+    pub fn height(&self) -> usize {
+        self.board_info.height
+    }
+
+    pub fn width(&self) -> usize {
+        self.board_info.width
+    }
+
+    fn neighbours(&self, line: usize, column: usize) -> Vec<(usize, usize)> {
+        let mut neighbours = Vec::new();
+        for line_offset in -1..=1 {
+            for column_offset in -1..=1 {
+                let neighbour_line = line as i32 + line_offset;
+                let neighbour_column = column as i32 + column_offset;
+                if (line_offset, column_offset) != (0, 0)
+                    && self.in_bounds(neighbour_line, neighbour_column)
+                {
+                    neighbours.push((neighbour_line as usize, neighbour_column as usize));
+                }
+            }
+        }
+        neighbours
     }
 
     fn in_bounds(&self, line: i32, column: i32) -> bool {
@@ -389,7 +989,7 @@ impl Board {
             && column < self.board_info.width as i32
     }
 
-    fn consult_position(&self, line: usize, column: usize) -> &Square {
+    pub fn consult_position(&self, line: usize, column: usize) -> &Square {
         &self.board[line * self.board_info.width + column]
     }
 
@@ -401,3 +1001,262 @@ impl Board {
         &mut self.board[self.curr_line * self.board_info.width + self.curr_column]
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Board with a mine on each `*` of the rows, every other square hidden.
+    fn board(rows: &[&str]) -> Board {
+        let layout = BoardLayout {
+            height: rows.len(),
+            width: rows[0].len(),
+            squares: rows
+                .iter()
+                .flat_map(|row| row.chars())
+                .map(|symbol| match symbol {
+                    '*' => Square::Bomb,
+                    _ => Square::Close(0),
+                })
+                .collect(),
+        };
+        Board::from_layout(&layout, 0)
+    }
+
+    // Board from the text: `*` a mine, `F`/`f` a right/wrong mark, digits opened numbers.
+    fn board_from_text(text: &str) -> Board {
+        let rows: Vec<&str> = text.lines().collect();
+        let mut board = Board::new(&Difficult::Easy, 0, false, false);
+        board.board = rows
+            .iter()
+            .flat_map(|row| row.chars())
+            .map(|symbol| match symbol {
+                '*' => Square::Bomb,
+                'F' => Square::Marked(true),
+                'f' => Square::Marked(false),
+                '0'..='8' => Square::Opened(symbol as usize - '0' as usize),
+                _ => Square::Close(0),
+            })
+            .collect();
+        board.board_info = BoardInfo {
+            height: rows.len(),
+            width: rows[0].len(),
+            number_of_bombs: board.board.iter().filter(|s| Board::has_bomb(s)).count() as i32,
+        };
+        board.pending_generation = false;
+        board.count_bombs_around();
+        board
+    }
+
+    fn opened(board: &Board, line: usize, column: usize) -> bool {
+        matches!(board.consult_position(line, column), Square::Opened(_))
+    }
+
+    #[test]
+    fn solvable_when_the_opening_clears_it() {
+        assert!(board(&["*..", "...", "..."]).solvable((2, 2)));
+    }
+
+    #[test]
+    fn solvable_by_deductions() {
+        // The opening leaves the 1-2-1 on top, which tells where both mines are.
+        assert!(board(&["*.*", "...", "...", "..."]).solvable((3, 1)));
+    }
+
+    #[test]
+    fn not_solvable_with_a_fifty_fifty() {
+        assert!(!board(&["*.", "..", ".."]).solvable((2, 0)));
+    }
+
+    #[test]
+    fn no_guess_falls_back_when_every_board_needs_guessing() {
+        // Whatever the first square revealed, the two mines hide among three squares around it.
+        let difficult = Difficult::Custom {
+            height: 2,
+            width: 2,
+            mines: 2,
+        };
+        let mut board = Board::new(&difficult, 0, false, true);
+        board.reveal();
+        assert!(!board.no_guess());
+
+        let mut board = Board::new(&Difficult::Easy, 0, false, true);
+        board.reveal();
+        assert!(board.no_guess());
+        assert!(board.solvable(board.cursor()));
+    }
+
+    fn mines(board: &Board) -> usize {
+        board
+            .board
+            .iter()
+            .filter(|square| Board::has_bomb(square))
+            .count()
+    }
+
+    #[test]
+    fn first_reveal_is_clear_around() {
+        for seed in 0..50 {
+            for (line, column) in [(0, 0), (4, 4)] {
+                let mut board = Board::new(&Difficult::Easy, seed, false, false);
+                board.move_cursor_to(line, column);
+                board.reveal();
+                assert!(!board.lost());
+                assert_eq!(mines(&board), 10);
+                assert!(*board.consult_position(line, column) == Square::Opened(0));
+                for (line, column) in board.neighbours(line, column) {
+                    assert!(!Board::has_bomb(board.consult_position(line, column)));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn dense_boards_only_keep_the_first_square_clear() {
+        // Eight mines on nine squares leave no room to keep the neighbours clear.
+        let difficult = Difficult::Custom {
+            height: 3,
+            width: 3,
+            mines: 8,
+        };
+        for seed in 0..10 {
+            let mut board = Board::new(&difficult, seed, false, false);
+            board.move_cursor_to(1, 1);
+            board.reveal();
+            assert!(!board.lost());
+            assert!(*board.consult_position(1, 1) == Square::Opened(8));
+        }
+    }
+
+    #[test]
+    fn auto_open_opens_an_area_right_away() {
+        for seed in 0..50 {
+            let board = Board::new(&Difficult::Easy, seed, true, false);
+            assert!(!board.pending_generation && !board.lost());
+            assert_eq!(mines(&board), 10);
+            let (line, column) = board.cursor();
+            assert!(*board.consult_position(line, column) == Square::Opened(0));
+        }
+    }
+
+    #[test]
+    fn statistics_of_a_known_board() {
+        // One opening over the top two lines, plus two numbers off its edge.
+        let mut board = board(&["....", "....", "....", "*..*"]);
+        assert_eq!(board.bbbv(), Some(3));
+        assert_eq!(board.solved_bbbv(), 0);
+        board.move_cursor_to(0, 0);
+        board.reveal();
+        assert_eq!(board.solved_bbbv(), 1);
+        // Revealing an opened square is still a click.
+        board.reveal();
+        board.move_cursor_to(3, 1);
+        board.reveal();
+        board.move_cursor_to(3, 2);
+        board.reveal();
+        assert_eq!(board.solved_bbbv(), 3);
+        assert_eq!(board.clicks(), 4);
+        assert_eq!(board.efficiency(), 75.0);
+        // 1.875 3BV/s, rounded up to the nearest hundredth.
+        board.time = Duration::from_millis(1600);
+        assert_eq!(board.ranking_score(), 188);
+    }
+
+    #[test]
+    fn hints_point_at_a_proven_safe_square() {
+        // The 1 in the corner only touches the mine, so the 1 below it is already satisfied.
+        let mut board = board_from_text("1*.\n11.\n");
+        board.hint();
+        assert!(matches!(board.current_hint(), Some(Hint::Safe)));
+        assert!([(0, 2), (1, 2)].contains(&board.cursor()));
+        assert_eq!(board.hints(), 1);
+    }
+
+    #[test]
+    fn hints_fall_back_to_the_lowest_chance() {
+        // One mine among the three squares around the 1, the other among the five left.
+        let mut board = board_from_text("1*.\n...\n..*\n");
+        board.hint();
+        let Some(Hint::Guess(chances)) = board.current_hint() else {
+            panic!("a guess is needed");
+        };
+        assert_eq!(chances[1], Some(33));
+        assert_eq!(chances[2], Some(20));
+        let (line, column) = board.cursor();
+        assert!(line == 2 || column == 2);
+    }
+
+    #[test]
+    fn squares_are_found_under_the_cells_drawn() {
+        let board = board(&["....", "....", "...."]);
+        assert_eq!(board.square_at(2, 1), Some((0, 0)));
+        assert_eq!(board.square_at(3, 1), Some((0, 0)));
+        assert_eq!(board.square_at(4, 1), Some((0, 1)));
+        assert_eq!(board.square_at(9, 3), Some((2, 3)));
+        // Borders and the space after the left one.
+        assert_eq!(board.square_at(0, 1), None);
+        assert_eq!(board.square_at(1, 1), None);
+        assert_eq!(board.square_at(2, 0), None);
+        // Past the last column and line.
+        assert_eq!(board.square_at(10, 1), None);
+        assert_eq!(board.square_at(2, 4), None);
+    }
+
+    #[test]
+    fn reveal_floods_up_to_the_numbers() {
+        let mut board = board_from_text("..*..\n..*..\n..*..\n");
+        board.move_cursor_to(0, 0);
+        board.reveal();
+        for line in 0..3 {
+            assert!(opened(&board, line, 0) && opened(&board, line, 1));
+            assert!(!opened(&board, line, 3) && !opened(&board, line, 4));
+        }
+        assert!(*board.consult_position(1, 1) == Square::Opened(3));
+        assert!(!board.lost());
+    }
+
+    #[test]
+    fn chord_opens_around_a_flagged_number() {
+        let mut board = board_from_text("F1.\n11.\n...\n");
+        board.move_cursor_to(0, 1);
+        board.chord();
+        assert!(opened(&board, 0, 2) && opened(&board, 2, 2));
+        assert!(!board.lost());
+        assert!(board.won());
+    }
+
+    #[test]
+    fn chord_with_a_wrong_flag_sets_a_mine_off() {
+        let mut board = board_from_text("f1*\n011\n");
+        board.move_cursor_to(0, 1);
+        board.chord();
+        assert_eq!(board.exploded(), Some((0, 2)));
+    }
+
+    #[test]
+    fn wrong_marks_stand_out_after_a_loss() {
+        let layout = BoardLayout {
+            height: 1,
+            width: 3,
+            squares: vec![
+                Square::Bomb,
+                Square::Marked(false),
+                Square::Uncertain(false),
+            ],
+        };
+        let mut board = Board::from_layout(&layout, 0);
+        board.move_cursor_to(0, 0);
+        board.reveal();
+        let theme = Theme::default();
+        let glyphs = crate::tgames::glyphs::ASCII;
+        let lines = board.display_board(String::new(), theme.text, &theme, &glyphs);
+        let wrong = lines
+            .iter()
+            .flat_map(|line| &line.spans)
+            .filter(|span| {
+                span.content == glyphs.wrong_flag && span.style.bg == Some(theme.wrong_flag)
+            })
+            .count();
+        assert_eq!(wrong, 2);
+    }
+}
@@ -0,0 +1,267 @@
+use super::board::{Board, Square};
+use std::{
+    fs,
+    io::{Error, ErrorKind, Result},
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+const BOARDS_DIRECTORY: &str = "boards";
+// Header of the .mbf format: width, height and the amount of mines, then two bytes per mine.
+const MBF_HEADER_SIZE: usize = 4;
+
+// A board as kept in a file, the numbers are counted again when it is played.
+#[derive(Clone)]
+pub struct BoardLayout {
+    pub height: usize,
+    pub width: usize,
+    pub squares: Vec<Square>,
+}
+
+impl BoardLayout {
+    pub fn mines(&self) -> usize {
+        self.squares
+            .iter()
+            .filter(|square| Board::has_bomb(square))
+            .count()
+    }
+
+    // Files ending in .mbf use the binary format other Minesweeper programs share, others text.
+    pub fn read(path: &Path) -> Result<Self> {
+        if is_mbf(path) {
+            Self::from_mbf(&fs::read(path)?)
+        } else {
+            Self::from_text(&fs::read_to_string(path)?)
+        }
+    }
+
+    fn write(&self, path: &Path) -> Result<()> {
+        if is_mbf(path) {
+            fs::write(path, self.to_mbf()?)
+        } else {
+            fs::write(path, self.to_text())
+        }
+    }
+
+    // Writes the board to the data directory as text and .mbf, returns the path of the text one.
+    pub fn export(&self) -> Result<PathBuf> {
+        let directory = dirs::data_dir()
+            .map(|directory| directory.join("tgames").join(BOARDS_DIRECTORY))
+            .ok_or_else(|| Error::new(ErrorKind::NotFound, "no data directory to export to"))?;
+        fs::create_dir_all(&directory)?;
+        let seconds = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |elapsed| elapsed.as_secs());
+        let path = directory.join(format!("minesweeper-{seconds}.txt"));
+        self.write(&path)?;
+        self.write(&path.with_extension("mbf"))?;
+        Ok(path)
+    }
+
+    // . hidden, * mine, 0 to 8 opened, F flag, Q question mark, lowercase without a mine.
+    pub fn from_text(text: &str) -> Result<Self> {
+        let mut squares = Vec::new();
+        let mut numbers = Vec::new();
+        let (mut height, mut width) = (0, 0);
+        for line in text.lines().map(str::trim_end) {
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let line_width = line.chars().count();
+            if height == 0 {
+                width = line_width;
+            } else if line_width != width {
+                return Err(invalid(format!(
+                    "line {} has {line_width} squares, the first one has {width}",
+                    height + 1
+                )));
+            }
+            for (column, symbol) in line.chars().enumerate() {
+                let square = match symbol {
+                    '.' => Square::Close(0),
+                    '*' => Square::Bomb,
+                    'F' => Square::Marked(true),
+                    'f' => Square::Marked(false),
+                    'Q' => Square::Uncertain(true),
+                    'q' => Square::Uncertain(false),
+                    '0'..='8' => {
+                        let number = symbol as usize - '0' as usize;
+                        numbers.push((squares.len(), number));
+                        Square::Opened(number)
+                    }
+                    _ => {
+                        return Err(invalid(format!(
+                            "unknown square '{symbol}' on line {}, column {}",
+                            height + 1,
+                            column + 1
+                        )))
+                    }
+                };
+                squares.push(square);
+            }
+            height += 1;
+        }
+        if height == 0 {
+            return Err(invalid(String::from("the file has no board")));
+        }
+        let layout = Self {
+            height,
+            width,
+            squares,
+        };
+        // Numbers that don't agree with the mines are most likely a mistake in the file.
+        for (index, number) in numbers {
+            let mines = layout.mines_around(index);
+            if mines != number {
+                return Err(invalid(format!(
+                    "the {number} on line {}, column {} should be {mines}",
+                    index / width + 1,
+                    index % width + 1
+                )));
+            }
+        }
+        Ok(layout)
+    }
+
+    pub fn to_text(&self) -> String {
+        let mut text = format!(
+            "# Minesweeper board, {}x{} with {} mines\n",
+            self.height,
+            self.width,
+            self.mines()
+        );
+        for line in self.squares.chunks(self.width) {
+            for square in line {
+                match square {
+                    Square::Close(_) => text.push('.'),
+                    Square::Bomb => text.push('*'),
+                    Square::Marked(true) => text.push('F'),
+                    Square::Marked(false) => text.push('f'),
+                    Square::Uncertain(true) => text.push('Q'),
+                    Square::Uncertain(false) => text.push('q'),
+                    Square::Opened(amount) => text += &amount.to_string(),
+                }
+            }
+            text.push('\n');
+        }
+        text
+    }
+
+    // Only where the mines are is kept in .mbf files, every square starts hidden.
+    fn from_mbf(bytes: &[u8]) -> Result<Self> {
+        if bytes.len() < MBF_HEADER_SIZE {
+            return Err(invalid(String::from(
+                "the file is too short for a .mbf board",
+            )));
+        }
+        let (width, height) = (bytes[0] as usize, bytes[1] as usize);
+        let mines = u16::from_be_bytes([bytes[2], bytes[3]]) as usize;
+        let positions = &bytes[MBF_HEADER_SIZE..];
+        if positions.len() != mines * 2 {
+            return Err(invalid(format!(
+                "the file says there are {mines} mines but has {} bytes of them",
+                positions.len()
+            )));
+        }
+        let mut squares = vec![Square::Close(0); height * width];
+        for position in positions.chunks(2) {
+            let (column, line) = (position[0] as usize, position[1] as usize);
+            if line >= height || column >= width {
+                return Err(invalid(format!(
+                    "mine at line {}, column {} is off the {height}x{width} board",
+                    line + 1,
+                    column + 1
+                )));
+            }
+            if squares[line * width + column] == Square::Bomb {
+                return Err(invalid(format!(
+                    "two mines at line {}, column {}",
+                    line + 1,
+                    column + 1
+                )));
+            }
+            squares[line * width + column] = Square::Bomb;
+        }
+        Ok(Self {
+            height,
+            width,
+            squares,
+        })
+    }
+
+    fn to_mbf(&self) -> Result<Vec<u8>> {
+        let (Ok(width), Ok(height), Ok(mines)) = (
+            u8::try_from(self.width),
+            u8::try_from(self.height),
+            u16::try_from(self.mines()),
+        ) else {
+            return Err(invalid(String::from(
+                "the board is too big for a .mbf file",
+            )));
+        };
+        let mut bytes = vec![width, height];
+        bytes.extend(mines.to_be_bytes());
+        for (index, square) in self.squares.iter().enumerate() {
+            if Board::has_bomb(square) {
+                bytes.push((index % self.width) as u8);
+                bytes.push((index / self.width) as u8);
+            }
+        }
+        Ok(bytes)
+    }
+
+    fn mines_around(&self, index: usize) -> usize {
+        let (line, column) = (index / self.width, index % self.width);
+        let mut mines = 0;
+        for neighbour_line in line.saturating_sub(1)..=(line + 1).min(self.height - 1) {
+            for neighbour_column in column.saturating_sub(1)..=(column + 1).min(self.width - 1) {
+                let neighbour = neighbour_line * self.width + neighbour_column;
+                if neighbour != index && Board::has_bomb(&self.squares[neighbour]) {
+                    mines += 1;
+                }
+            }
+        }
+        mines
+    }
+}
+
+fn is_mbf(path: &Path) -> bool {
+    path.extension()
+        .is_some_and(|extension| extension.eq_ignore_ascii_case("mbf"))
+}
+
+fn invalid(message: String) -> Error {
+    Error::new(ErrorKind::InvalidData, message)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn text_round_trip() {
+        let text = "# Minesweeper board, 3x4 with 4 mines\n*F.q\n3Q3.\n12*f\n";
+        let layout = BoardLayout::from_text(text).unwrap();
+        assert_eq!(layout.mines(), 4);
+        assert_eq!(layout.to_text(), text);
+    }
+
+    #[test]
+    fn mbf_round_trip() {
+        let layout = BoardLayout::from_text("*F.q\n3Q3.\n12*f\n").unwrap();
+        let bytes = layout.to_mbf().unwrap();
+        assert_eq!(bytes, [4, 3, 0, 4, 0, 0, 1, 0, 1, 1, 2, 2]);
+        let read = BoardLayout::from_mbf(&bytes).unwrap();
+        assert_eq!((read.height, read.width), (3, 4));
+        let mines = |layout: &BoardLayout| -> Vec<bool> {
+            layout.squares.iter().map(Board::has_bomb).collect()
+        };
+        assert_eq!(mines(&read), mines(&layout));
+    }
+
+    #[test]
+    fn numbers_must_agree_with_the_mines() {
+        let error = BoardLayout::from_text("*2\n..\n").err().unwrap();
+        assert_eq!(error.to_string(), "the 2 on line 1, column 2 should be 1");
+    }
+}
@@ -0,0 +1,163 @@
+use crate::tgames::{
+    game_manager::{draw, GameBackend},
+    glyphs::Glyphs,
+    theme::Theme,
+};
+use crossterm::event::{Event, KeyCode, KeyEvent, KeyEventKind};
+use ratatui::{
+    layout::{Alignment, Constraint, Direction, Layout},
+    style::Style,
+    text::{Line, Span},
+    widgets::{Block, Borders, Paragraph},
+    Terminal,
+};
+use std::io::Result;
+
+const FIELDS: [&str; 3] = ["Height", "Width", "Mines"];
+const MAX_DIGITS: usize = 4;
+
+#[derive(PartialEq, Eq)]
+pub enum CustomBoardStatus {
+    Typing,
+    Confirmed,
+    Cancelled,
+}
+
+// Form where the height, width and amount of mines of a custom board are typed.
+pub struct CustomBoardPrompt {
+    values: [String; 3],
+    selected: usize,
+    status: CustomBoardStatus,
+    error: Option<String>,
+    // Columns and rows of the terminal the last time the form was drawn.
+    screen: Option<(u16, u16)>,
+}
+
+impl CustomBoardPrompt {
+    pub fn new(height: usize, width: usize, mines: usize) -> Self {
+        Self {
+            values: [height.to_string(), width.to_string(), mines.to_string()],
+            selected: 0,
+            status: CustomBoardStatus::Typing,
+            error: None,
+            screen: None,
+        }
+    }
+
+    // Starts over with the values of the last board asked for.
+    pub fn clear(&mut self) {
+        self.selected = 0;
+        self.status = CustomBoardStatus::Typing;
+        self.error = None;
+    }
+
+    pub fn status(&self) -> &CustomBoardStatus {
+        &self.status
+    }
+
+    pub fn screen(&self) -> Option<(u16, u16)> {
+        self.screen
+    }
+
+    // Height, width and mines typed, an empty field counts as zero.
+    pub fn values(&self) -> (usize, usize, usize) {
+        let [height, width, mines] = self.values.clone().map(|value| value.parse().unwrap_or(0));
+        (height, width, mines)
+    }
+
+    // Lets the values be fixed after they were refused.
+    pub fn refuse(&mut self, error: String) {
+        self.error = Some(error);
+        self.status = CustomBoardStatus::Typing;
+    }
+
+    pub fn handle_event(&mut self, event: &Event) {
+        if let Event::Key(KeyEvent {
+            code,
+            kind: KeyEventKind::Press,
+            ..
+        }) = event
+        {
+            match code {
+                KeyCode::Enter => self.status = CustomBoardStatus::Confirmed,
+                KeyCode::Esc => self.status = CustomBoardStatus::Cancelled,
+                KeyCode::Tab | KeyCode::Down => self.selected = (self.selected + 1) % FIELDS.len(),
+                KeyCode::BackTab | KeyCode::Up => {
+                    self.selected = (self.selected + FIELDS.len() - 1) % FIELDS.len()
+                }
+                KeyCode::Backspace => {
+                    self.values[self.selected].pop();
+                }
+                KeyCode::Char(digit)
+                    if digit.is_ascii_digit() && self.values[self.selected].len() < MAX_DIGITS =>
+                {
+                    self.values[self.selected].push(*digit)
+                }
+                _ => (),
+            }
+        }
+    }
+
+    pub fn display<B: GameBackend>(
+        &mut self,
+        terminal: &mut Terminal<B>,
+        theme: &Theme,
+        glyphs: &Glyphs,
+    ) -> Result<()> {
+        let mut lines = Vec::new();
+        for (index, (field, value)) in FIELDS.iter().zip(self.values.iter()).enumerate() {
+            let (value, style) = if index == self.selected {
+                (value.clone() + "_", Style::default().fg(theme.warning))
+            } else {
+                (value.clone(), Style::default().fg(theme.text))
+            };
+            lines.push(Line::from(vec![
+                Span::raw(format!("{field:>6}: ")),
+                Span::styled(format!("{value:<5}"), style),
+            ]));
+        }
+        lines.push(Line::from(""));
+        lines.push(Line::from(Span::styled(
+            self.error.clone().unwrap_or_default(),
+            Style::default().fg(theme.failure),
+        )));
+        lines.push(Line::from(""));
+        lines.push(Line::from(Span::styled(
+            "TAB - Next    ENTER - Play    ESC - Back",
+            Style::default().fg(theme.dim),
+        )));
+        let mut screen = None;
+        draw(terminal, |frame| {
+            let area = frame.area();
+            screen = Some((area.width, area.height));
+            let vertical = Layout::default()
+                .direction(Direction::Vertical)
+                .constraints([
+                    Constraint::Fill(1),
+                    Constraint::Length(lines.len() as u16 + 2),
+                    Constraint::Fill(1),
+                ])
+                .split(area);
+            let horizontal = Layout::default()
+                .direction(Direction::Horizontal)
+                .constraints([
+                    Constraint::Fill(1),
+                    Constraint::Length(60),
+                    Constraint::Fill(1),
+                ])
+                .split(vertical[1]);
+            frame.render_widget(
+                Paragraph::new(lines).alignment(Alignment::Center).block(
+                    Block::new()
+                        .borders(Borders::ALL)
+                        .border_set(glyphs.border)
+                        .title("Custom board")
+                        .title_alignment(Alignment::Center),
+                ),
+                horizontal[1],
+            );
+        })?;
+        self.screen = screen;
+        Ok(())
+    }
+}
@@ -0,0 +1,462 @@
+use super::board::Square;
+
+// What the numbers opened so far prove about a closed square.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Deduction {
+    Safe,
+    Mine,
+}
+
+// Squares around an opened number that are still unknown, and how many of them are mines.
+struct Constraint {
+    number: usize,
+    squares: Vec<usize>,
+    mines: usize,
+}
+
+// Deduces squares from the numbers and mines left, marks are ignored since they may be wrong.
+pub struct Solver {
+    width: usize,
+    height: usize,
+    mines: usize,
+    numbers: Vec<Option<usize>>,
+    known: Vec<Option<Deduction>>,
+}
+
+impl Solver {
+    pub fn new(board: &[Square], width: usize, height: usize, mines: usize) -> Self {
+        let mut instance = Self {
+            width,
+            height,
+            mines,
+            numbers: Vec::new(),
+            known: vec![None; board.len()],
+        };
+        instance.update(board);
+        instance
+    }
+
+    // Takes in the squares opened since the last time.
+    pub fn update(&mut self, board: &[Square]) {
+        self.numbers = board
+            .iter()
+            .map(|square| match square {
+                Square::Opened(amount) => Some(*amount),
+                _ => None,
+            })
+            .collect();
+    }
+
+    // Applies the rules until nothing new is proven, returns the squares found by index.
+    pub fn solve(&mut self) -> Vec<(usize, Deduction)> {
+        let mut found = Vec::new();
+        loop {
+            let deductions = self.step();
+            if deductions.is_empty() {
+                break;
+            }
+            for (index, deduction) in deductions {
+                if self.known[index].is_none() {
+                    self.known[index] = Some(deduction);
+                    found.push((index, deduction));
+                }
+            }
+        }
+        found
+    }
+
+    // Chance of a mine on each unknown square, every layout agreeing with the numbers as likely.
+    pub fn probabilities(&mut self) -> Vec<Option<f64>> {
+        self.solve();
+        let mut probabilities: Vec<Option<f64>> = self
+            .known
+            .iter()
+            .map(|known| match known {
+                Some(Deduction::Safe) => Some(0.0),
+                Some(Deduction::Mine) => Some(1.0),
+                None => None,
+            })
+            .collect();
+        let constraints = self.constraints();
+        let unknown: Vec<usize> = (0..self.numbers.len())
+            .filter(|&index| self.numbers[index].is_none() && self.known[index].is_none())
+            .collect();
+        let found = self
+            .known
+            .iter()
+            .filter(|known| **known == Some(Deduction::Mine))
+            .count();
+        let mines_left = self.mines.saturating_sub(found);
+
+        // Groups sharing no number are counted apart, too big ones count as if they had no numbers.
+        let groups: Vec<Group> = groups(&constraints)
+            .into_iter()
+            .filter_map(|group| Group::count(&constraints, &group))
+            .collect();
+        let in_groups: Vec<usize> = groups
+            .iter()
+            .flat_map(|group| group.squares.clone())
+            .collect();
+        let others = unknown.len() - in_groups.len();
+
+        // Ways of placing the rest of the mines among the other squares, relative to each other.
+        let ln_factorials: Vec<f64> = (0..=unknown.len())
+            .scan(0.0, |sum, number: usize| {
+                *sum += (number.max(1) as f64).ln();
+                Some(*sum)
+            })
+            .collect();
+        let ways_outside = |mines: usize| -> f64 {
+            if mines > mines_left || mines_left - mines > others {
+                return f64::NEG_INFINITY;
+            }
+            let rest = mines_left - mines;
+            ln_factorials[others] - ln_factorials[rest] - ln_factorials[others - rest]
+        };
+        let scale = (0..=mines_left)
+            .map(ways_outside)
+            .fold(f64::NEG_INFINITY, f64::max);
+        let outside = |mines: usize| (ways_outside(mines) - scale).exp();
+
+        // Layouts of all the groups but one, by the mines they use.
+        let layouts_without = |skipped: Option<usize>| -> Vec<f64> {
+            let mut layouts = vec![1.0];
+            for (index, group) in groups.iter().enumerate() {
+                if Some(index) == skipped {
+                    continue;
+                }
+                let mut combined = vec![0.0; layouts.len() + group.layouts.len() - 1];
+                for (mines, ways) in layouts.iter().enumerate() {
+                    for (group_mines, group_ways) in group.layouts.iter().enumerate() {
+                        combined[mines + group_mines] += ways * group_ways;
+                    }
+                }
+                layouts = combined;
+            }
+            layouts
+        };
+
+        let all = layouts_without(None);
+        let total: f64 = all
+            .iter()
+            .enumerate()
+            .map(|(mines, ways)| ways * outside(mines))
+            .sum();
+        if total == 0.0 {
+            return probabilities;
+        }
+        for (index, group) in groups.iter().enumerate() {
+            let rest = layouts_without(Some(index));
+            for (square_index, square) in group.squares.iter().enumerate() {
+                let mut ways = 0.0;
+                for (group_mines, mine_layouts) in group.mine_layouts.iter().enumerate() {
+                    for (rest_mines, rest_ways) in rest.iter().enumerate() {
+                        ways += mine_layouts[square_index]
+                            * rest_ways
+                            * outside(group_mines + rest_mines);
+                    }
+                }
+                probabilities[*square] = Some(ways / total);
+            }
+        }
+        if others > 0 {
+            let mines_outside: f64 = all
+                .iter()
+                .enumerate()
+                .map(|(mines, ways)| {
+                    ways * outside(mines) * mines_left.saturating_sub(mines) as f64
+                })
+                .sum();
+            let chance = mines_outside / total / others as f64;
+            for square in unknown {
+                if probabilities[square].is_none() && !in_groups.contains(&square) {
+                    probabilities[square] = Some(chance);
+                }
+            }
+        }
+        probabilities
+    }
+
+    // The simplest rule that proves something, the others are only tried when it doesn't.
+    fn step(&self) -> Vec<(usize, Deduction)> {
+        let constraints = self.constraints();
+        let mut deductions = Vec::new();
+
+        // A number with all of its mines found, or with as many unknown squares as mines left.
+        for constraint in &constraints {
+            if constraint.mines == 0 {
+                deductions.extend(constraint.squares.iter().map(|&s| (s, Deduction::Safe)));
+            } else if constraint.mines == constraint.squares.len() {
+                deductions.extend(constraint.squares.iter().map(|&s| (s, Deduction::Mine)));
+            }
+        }
+        if !deductions.is_empty() {
+            return deductions;
+        }
+
+        // When one number's extra mines only fit its own squares, the other's own are all safe.
+        let mut by_number = vec![None; self.numbers.len()];
+        for (index, constraint) in constraints.iter().enumerate() {
+            by_number[constraint.number] = Some(index);
+        }
+        for first in &constraints {
+            let (line, column) = (first.number / self.width, first.number % self.width);
+            for other_line in line.saturating_sub(2)..(line + 3).min(self.height) {
+                for other_column in column.saturating_sub(2)..(column + 3).min(self.width) {
+                    let Some(second) = by_number[other_line * self.width + other_column] else {
+                        continue;
+                    };
+                    let second = &constraints[second];
+                    if first.number == second.number || first.mines < second.mines {
+                        continue;
+                    }
+                    let only_first = difference(&first.squares, &second.squares);
+                    if first.mines - second.mines == only_first.len() {
+                        let only_second = difference(&second.squares, &first.squares);
+                        deductions.extend(only_first.iter().map(|&s| (s, Deduction::Mine)));
+                        deductions.extend(only_second.iter().map(|&s| (s, Deduction::Safe)));
+                    }
+                }
+            }
+        }
+        if !deductions.is_empty() {
+            return deductions;
+        }
+
+        // The mines left on the whole board.
+        let unknown: Vec<usize> = (0..self.numbers.len())
+            .filter(|&index| self.numbers[index].is_none() && self.known[index].is_none())
+            .collect();
+        let found = self
+            .known
+            .iter()
+            .filter(|known| **known == Some(Deduction::Mine))
+            .count();
+        let mines_left = self.mines.saturating_sub(found);
+        if mines_left == 0 {
+            deductions.extend(unknown.iter().map(|&s| (s, Deduction::Safe)));
+        } else if mines_left == unknown.len() {
+            deductions.extend(unknown.iter().map(|&s| (s, Deduction::Mine)));
+        }
+        deductions
+    }
+
+    fn constraints(&self) -> Vec<Constraint> {
+        let mut constraints = Vec::new();
+        for (index, number) in self.numbers.iter().enumerate() {
+            let Some(number) = number else {
+                continue;
+            };
+            let mut squares = Vec::new();
+            let mut mines_found = 0;
+            for neighbour in self.neighbours(index) {
+                match self.known[neighbour] {
+                    Some(Deduction::Mine) => mines_found += 1,
+                    Some(Deduction::Safe) => (),
+                    None if self.numbers[neighbour].is_none() => squares.push(neighbour),
+                    None => (),
+                }
+            }
+            if !squares.is_empty() {
+                constraints.push(Constraint {
+                    number: index,
+                    squares,
+                    mines: number.saturating_sub(mines_found),
+                });
+            }
+        }
+        constraints
+    }
+
+    fn neighbours(&self, index: usize) -> Vec<usize> {
+        let (line, column) = (index / self.width, index % self.width);
+        let mut neighbours = Vec::new();
+        for neighbour_line in line.saturating_sub(1)..(line + 2).min(self.height) {
+            for neighbour_column in column.saturating_sub(1)..(column + 2).min(self.width) {
+                if (neighbour_line, neighbour_column) != (line, column) {
+                    neighbours.push(neighbour_line * self.width + neighbour_column);
+                }
+            }
+        }
+        neighbours
+    }
+}
+
+fn difference(first: &[usize], second: &[usize]) -> Vec<usize> {
+    first
+        .iter()
+        .filter(|square| !second.contains(square))
+        .copied()
+        .collect()
+}
+
+// Squares of the groups of numbers that share squares, by index of the constraints.
+fn groups(constraints: &[Constraint]) -> Vec<Vec<usize>> {
+    let mut group_of: Vec<Option<usize>> = vec![None; constraints.len()];
+    let mut groups = Vec::new();
+    for start in 0..constraints.len() {
+        if group_of[start].is_some() {
+            continue;
+        }
+        let mut group = vec![start];
+        group_of[start] = Some(groups.len());
+        let mut next = 0;
+        while next < group.len() {
+            let current = &constraints[group[next]];
+            for (index, other) in constraints.iter().enumerate() {
+                if group_of[index].is_none()
+                    && other
+                        .squares
+                        .iter()
+                        .any(|square| current.squares.contains(square))
+                {
+                    group_of[index] = Some(groups.len());
+                    group.push(index);
+                }
+            }
+            next += 1;
+        }
+        groups.push(group);
+    }
+    groups
+}
+
+// Every way of placing mines on the squares of a group, counted by the mines used.
+struct Group {
+    squares: Vec<usize>,
+    // Layouts using each amount of mines.
+    layouts: Vec<f64>,
+    // Layouts using each amount of mines with a mine on each square.
+    mine_layouts: Vec<Vec<f64>>,
+}
+
+// Layouts tried on a single group at most, so a hint never takes long.
+const MAX_STEPS: usize = 200_000;
+
+impl Group {
+    fn count(constraints: &[Constraint], group: &[usize]) -> Option<Self> {
+        let mut squares: Vec<usize> = group
+            .iter()
+            .flat_map(|&constraint| constraints[constraint].squares.clone())
+            .collect();
+        squares.sort_unstable();
+        squares.dedup();
+        // For each constraint of the group: its squares by their position in `squares`.
+        let local: Vec<(Vec<usize>, usize)> = group
+            .iter()
+            .map(|&constraint| {
+                let constraint = &constraints[constraint];
+                let positions = constraint
+                    .squares
+                    .iter()
+                    .map(|square| squares.binary_search(square).unwrap_or_default())
+                    .collect();
+                (positions, constraint.mines)
+            })
+            .collect();
+        let mut instance = Self {
+            layouts: vec![0.0; squares.len() + 1],
+            mine_layouts: vec![vec![0.0; squares.len()]; squares.len() + 1],
+            squares,
+        };
+        let mut mines = vec![false; instance.squares.len()];
+        let mut steps = 0;
+        instance
+            .place(&local, &mut mines, 0, &mut steps)
+            .then_some(instance)
+    }
+
+    // Tries both ways for the square at `position`, giving up past the layout limit.
+    fn place(
+        &mut self,
+        constraints: &[(Vec<usize>, usize)],
+        mines: &mut Vec<bool>,
+        position: usize,
+        steps: &mut usize,
+    ) -> bool {
+        *steps += 1;
+        if *steps > MAX_STEPS {
+            return false;
+        }
+        // A constraint fails when it already has too many mines, or can't get enough anymore.
+        let fails = constraints.iter().any(|(squares, wanted)| {
+            let placed = squares
+                .iter()
+                .filter(|&&square| square < position && mines[square])
+                .count();
+            let open = squares.iter().filter(|&&square| square >= position).count();
+            placed > *wanted || placed + open < *wanted
+        });
+        if fails {
+            return true;
+        }
+        if position == mines.len() {
+            let count = mines.iter().filter(|mine| **mine).count();
+            self.layouts[count] += 1.0;
+            for (square, mine) in mines.iter().enumerate() {
+                if *mine {
+                    self.mine_layouts[count][square] += 1.0;
+                }
+            }
+            return true;
+        }
+        for mine in [false, true] {
+            mines[position] = mine;
+            if !self.place(constraints, mines, position + 1, steps) {
+                return false;
+            }
+        }
+        mines[position] = false;
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Squares from rows of `.` for hidden, `*` for a mine and digits for opened numbers.
+    fn squares(rows: &[&str]) -> Vec<Square> {
+        rows.iter()
+            .flat_map(|row| row.chars())
+            .map(|symbol| match symbol {
+                '*' => Square::Bomb,
+                '.' => Square::Close(0),
+                digit => Square::Opened(digit.to_digit(10).unwrap() as usize),
+            })
+            .collect()
+    }
+
+    fn solve(rows: &[&str], mines: usize) -> Vec<(usize, Deduction)> {
+        let mut found = Solver::new(&squares(rows), rows[0].len(), rows.len(), mines).solve();
+        found.sort_by_key(|(index, _)| *index);
+        found
+    }
+
+    #[test]
+    fn one_two_one() {
+        assert_eq!(
+            solve(&["*.*", "121"], 2),
+            vec![
+                (0, Deduction::Mine),
+                (1, Deduction::Safe),
+                (2, Deduction::Mine)
+            ]
+        );
+    }
+
+    #[test]
+    fn single_square_around_a_one() {
+        assert_eq!(solve(&["*1", "11"], 1), vec![(0, Deduction::Mine)]);
+    }
+
+    #[test]
+    fn fifty_fifty_has_no_deduction() {
+        assert!(solve(&["*.", "11"], 1).is_empty());
+        let mut solver = Solver::new(&squares(&["*.", "11"]), 2, 2, 1);
+        let probabilities = solver.probabilities();
+        assert_eq!(probabilities[0], Some(0.5));
+        assert_eq!(probabilities[1], Some(0.5));
+        assert_eq!(probabilities[2], None);
+    }
+}
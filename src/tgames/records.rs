@@ -0,0 +1,421 @@
+use super::Games;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use std::{
+    cell::RefCell,
+    collections::BTreeMap,
+    fs,
+    io::{Error, ErrorKind, Result},
+    path::{Path, PathBuf},
+    rc::Rc,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+// Bump this whenever the layout of the records file changes. Older versions must keep loading,
+// new fields should default to empty.
+const RECORDS_VERSION: u32 = 4;
+const RECORDS_FILE_NAME: &str = "records.toml";
+// Same as above, for the suspended games. A save from another version is discarded.
+const SAVES_VERSION: u32 = 1;
+const SAVES_DIRECTORY: &str = "saves";
+pub const LEADERBOARD_SIZE: usize = 10;
+// Mode used by the games that don't have modes or difficulties.
+pub const DEFAULT_MODE: &str = "Classic";
+
+#[derive(Serialize, Deserialize, Default, Clone)]
+#[serde(default)]
+pub struct Records {
+    pub snake: u32,
+    pub tetris_score: u32,
+    pub tetris_lines: u32,
+    pub g2048: u32,
+    // By mode, like the leaderboards.
+    pub minesweeper: BTreeMap<String, u32>,
+    pub flappy_bird: u32,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct LeaderboardEntry {
+    pub name: String,
+    pub score: u32,
+    // Auxiliary stat shown next to the score, like Tetris lines or 2048 moves.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stat: Option<u32>,
+    // Seconds since the unix epoch.
+    pub date: u64,
+    // Whether the game was won with help, like Minesweeper hints.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub hinted: bool,
+}
+
+impl LeaderboardEntry {
+    pub fn new(name: String, score: u32, stat: Option<u32>) -> Self {
+        Self {
+            name,
+            score,
+            stat,
+            date: seconds_since_epoch(),
+            hinted: false,
+        }
+    }
+
+    pub fn with_hints(mut self, hinted: bool) -> Self {
+        self.hinted = hinted;
+        self
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct RecordsFile {
+    version: u32,
+    records: Records,
+    #[serde(default)]
+    leaderboards: BTreeMap<String, Vec<LeaderboardEntry>>,
+}
+
+impl Default for RecordsFile {
+    fn default() -> Self {
+        Self {
+            version: RECORDS_VERSION,
+            records: Records::default(),
+            leaderboards: BTreeMap::new(),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct SavedGameFile<T> {
+    version: u32,
+    game: T,
+}
+
+pub type SharedRecords = Rc<RefCell<RecordsStore>>;
+
+pub struct RecordsStore {
+    path: Option<PathBuf>,
+    records: Records,
+    leaderboards: BTreeMap<String, Vec<LeaderboardEntry>>,
+    // Suspended games, serialized. Kept on disk next to the records, one file per game.
+    saved_games: BTreeMap<String, String>,
+}
+
+impl RecordsStore {
+    // Fails when the records were written by a newer tgames, they would be lost on the next save.
+    pub fn load() -> Result<Self> {
+        let mut path = dirs::data_dir().map(|dir| dir.join("tgames").join(RECORDS_FILE_NAME));
+        let mut read_only = false;
+        let file = match &path {
+            Some(path) => match Self::read(path) {
+                Ok(file) => file,
+                Err(error) if error.kind() == ErrorKind::InvalidData => {
+                    // Keep the unreadable file around instead of overwriting it on the next save,
+                    // stamped so it doesn't replace an earlier one.
+                    let corrupt = format!("toml.{}.corrupt", seconds_since_epoch());
+                    let _ = fs::rename(path, path.with_extension(corrupt));
+                    RecordsFile::default()
+                }
+                Err(error) if error.kind() == ErrorKind::Unsupported => return Err(error),
+                // The file may be fine and only out of reach for now, so it is left alone.
+                Err(_) => {
+                    read_only = true;
+                    RecordsFile::default()
+                }
+            },
+            None => RecordsFile::default(),
+        };
+        let mut saved_games = BTreeMap::new();
+        if let Some(directory) = path.as_ref().and_then(|path| path.parent()) {
+            if let Ok(entries) = fs::read_dir(directory.join(SAVES_DIRECTORY)) {
+                for entry in entries.flatten() {
+                    let path = entry.path();
+                    if path
+                        .extension()
+                        .is_some_and(|extension| extension == "toml")
+                    {
+                        if let (Some(name), Ok(content)) =
+                            (path.file_stem(), fs::read_to_string(&path))
+                        {
+                            saved_games.insert(name.to_string_lossy().to_string(), content);
+                        }
+                    }
+                }
+            }
+        }
+        if read_only {
+            path = None;
+        }
+        Ok(Self {
+            path,
+            records: file.records,
+            leaderboards: file.leaderboards,
+            saved_games,
+        })
+    }
+
+    // Keeps everything in memory, nothing is read from or written to disk.
+    pub fn in_memory() -> Self {
+        Self {
+            path: None,
+            records: Records::default(),
+            leaderboards: BTreeMap::new(),
+            saved_games: BTreeMap::new(),
+        }
+    }
+
+    pub fn shared(self) -> SharedRecords {
+        Rc::new(RefCell::new(self))
+    }
+
+    pub fn records(&self) -> &Records {
+        &self.records
+    }
+
+    // Applies the change and writes the records to disk. Failing to save is not fatal, the
+    // records are still kept in memory for the current session.
+    pub fn update(&mut self, change: impl FnOnce(&mut Records)) {
+        change(&mut self.records);
+        let _ = self.save();
+    }
+
+    pub fn leaderboard(&self, game: &Games, mode: &str) -> &[LeaderboardEntry] {
+        self.leaderboards
+            .get(&Self::leaderboard_key(game, mode))
+            .map_or(&[], |entries| entries.as_slice())
+    }
+
+    // Every mode of `game` with a leaderboard saved.
+    pub fn modes(&self, game: &Games) -> Vec<String> {
+        let prefix = Self::leaderboard_key(game, "");
+        self.leaderboards
+            .keys()
+            .filter_map(|key| key.strip_prefix(&prefix))
+            .map(String::from)
+            .collect()
+    }
+
+    pub fn qualifies_for_leaderboard(&self, game: &Games, mode: &str, score: u32) -> bool {
+        let entries = self.leaderboard(game, mode);
+        score > 0
+            && (entries.len() < LEADERBOARD_SIZE
+                || entries.last().is_some_and(|last| score > last.score))
+    }
+
+    pub fn add_leaderboard_entry(&mut self, game: &Games, mode: &str, entry: LeaderboardEntry) {
+        let entries = self
+            .leaderboards
+            .entry(Self::leaderboard_key(game, mode))
+            .or_default();
+        // Ties keep the older entry on top.
+        let position = entries.partition_point(|current| current.score >= entry.score);
+        entries.insert(position, entry);
+        entries.truncate(LEADERBOARD_SIZE);
+        let _ = self.save();
+    }
+
+    pub fn has_saved_game(&self, game: &Games) -> bool {
+        self.saved_games.contains_key(&Self::saved_game_key(game))
+    }
+
+    // Takes the suspended game out of the store, so it can only be resumed once. A save that
+    // can't be read anymore is dropped.
+    pub fn take_saved_game<T: DeserializeOwned>(&mut self, game: &Games) -> Option<T> {
+        let key = Self::saved_game_key(game);
+        let content = self.saved_games.remove(&key)?;
+        if let Some(path) = self.saved_game_path(&key) {
+            let _ = fs::remove_file(path);
+        }
+        toml::from_str::<SavedGameFile<T>>(&content)
+            .ok()
+            .filter(|file| file.version == SAVES_VERSION)
+            .map(|file| file.game)
+    }
+
+    // Fails when the game can't be serialized. Failing to write it is not fatal, the game is still
+    // kept in memory for the current session.
+    pub fn store_saved_game<T: Serialize>(&mut self, game: &Games, state: T) -> Result<()> {
+        let key = Self::saved_game_key(game);
+        let content = toml::to_string(&SavedGameFile {
+            version: SAVES_VERSION,
+            game: state,
+        })
+        .map_err(|error| {
+            Error::new(
+                ErrorKind::InvalidData,
+                format!("can't save {game}: {error}"),
+            )
+        })?;
+        if let Some(path) = self.saved_game_path(&key) {
+            let _ = Self::write_atomically(&path, &content);
+        }
+        self.saved_games.insert(key, content);
+        Ok(())
+    }
+
+    fn saved_game_key(game: &Games) -> String {
+        game.id().to_string()
+    }
+
+    fn saved_game_path(&self, key: &str) -> Option<PathBuf> {
+        let directory = self.path.as_ref()?.parent()?.join(SAVES_DIRECTORY);
+        Some(directory.join(key).with_extension("toml"))
+    }
+
+    fn leaderboard_key(game: &Games, mode: &str) -> String {
+        format!("{game}/{mode}")
+    }
+
+    fn read(path: &PathBuf) -> Result<RecordsFile> {
+        let content = match fs::read_to_string(path) {
+            Ok(content) => content,
+            Err(error) if error.kind() == ErrorKind::NotFound => return Ok(RecordsFile::default()),
+            Err(error) => return Err(error),
+        };
+        let invalid = |error| Error::new(ErrorKind::InvalidData, error);
+        // The version is checked first, newer files may not have the layout this one knows.
+        let mut table: toml::Table = toml::from_str(&content).map_err(invalid)?;
+        let version = table.get("version").and_then(toml::Value::as_integer);
+        if version.is_some_and(|version| version > RECORDS_VERSION as i64) {
+            return Err(Error::new(
+                ErrorKind::Unsupported,
+                format!(
+                    "{} was written by a newer version of tgames, update it to keep your records",
+                    path.display()
+                ),
+            ));
+        }
+        // Before version 4 Minesweeper had a single record for every mode, each mode gets its own
+        // from the leaderboard below instead.
+        if version.is_none_or(|version| version < 4) {
+            if let Some(toml::Value::Table(records)) = table.get_mut("records") {
+                records.remove("minesweeper");
+            }
+        }
+        let mut file: RecordsFile = table.try_into().map_err(invalid)?;
+        // Minesweeper scores counted the squares revealed before version 3, they can't be ranked
+        // against 3BV/s.
+        if file.version < 3 {
+            let prefix = Self::leaderboard_key(&Games::Minesweeper, "");
+            file.leaderboards.retain(|key, _| !key.starts_with(&prefix));
+        }
+        if file.version < 4 {
+            let prefix = Self::leaderboard_key(&Games::Minesweeper, "");
+            for (key, entries) in &file.leaderboards {
+                let best = entries.iter().map(|entry| entry.score).max();
+                if let (Some(mode), Some(best)) = (key.strip_prefix(&prefix), best) {
+                    file.records.minesweeper.insert(mode.to_string(), best);
+                }
+            }
+        }
+        Ok(file)
+    }
+
+    fn save(&self) -> Result<()> {
+        let path = match &self.path {
+            Some(path) => path,
+            None => return Ok(()),
+        };
+        let content = toml::to_string(&RecordsFile {
+            version: RECORDS_VERSION,
+            records: self.records.clone(),
+            leaderboards: self.leaderboards.clone(),
+        })
+        .map_err(|error| Error::new(ErrorKind::InvalidData, error))?;
+        Self::write_atomically(path, &content)
+    }
+
+    // Write to a temporary file first so a crash mid-write never leaves a truncated file.
+    fn write_atomically(path: &Path, content: &str) -> Result<()> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let temporary = path.with_extension("toml.tmp");
+        fs::write(&temporary, content)?;
+        fs::rename(&temporary, path)
+    }
+}
+
+fn seconds_since_epoch() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn minesweeper_records_split_by_mode() {
+        let name = format!("tgames-records-version-3-{}.toml", std::process::id());
+        let path = std::env::temp_dir().join(name);
+        fs::write(
+            &path,
+            r#"
+version = 3
+
+[records]
+minesweeper = 250
+
+[[leaderboards."Minesweeper/Easy"]]
+name = "a"
+score = 250
+date = 0
+
+[[leaderboards."Minesweeper/Easy"]]
+name = "b"
+score = 120
+date = 0
+
+[[leaderboards."Minesweeper/Hard"]]
+name = "a"
+score = 80
+date = 0
+"#,
+        )
+        .unwrap();
+        let file = RecordsStore::read(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+        let expected = BTreeMap::from([(String::from("Easy"), 250), (String::from("Hard"), 80)]);
+        assert_eq!(file.records.minesweeper, expected);
+    }
+
+    fn entry(name: &str, score: u32) -> LeaderboardEntry {
+        LeaderboardEntry::new(name.to_string(), score, None)
+    }
+
+    fn names(store: &RecordsStore) -> Vec<&str> {
+        store
+            .leaderboard(&Games::Snake, DEFAULT_MODE)
+            .iter()
+            .map(|entry| entry.name.as_str())
+            .collect()
+    }
+
+    #[test]
+    fn ties_keep_the_older_entry_on_top() {
+        let mut store = RecordsStore::in_memory();
+        store.add_leaderboard_entry(&Games::Snake, DEFAULT_MODE, entry("a", 10));
+        store.add_leaderboard_entry(&Games::Snake, DEFAULT_MODE, entry("b", 20));
+        store.add_leaderboard_entry(&Games::Snake, DEFAULT_MODE, entry("c", 10));
+        assert_eq!(names(&store), ["b", "a", "c"]);
+    }
+
+    #[test]
+    fn a_full_leaderboard_drops_its_last_entry() {
+        let mut store = RecordsStore::in_memory();
+        for score in 1..=LEADERBOARD_SIZE as u32 {
+            let name = format!("{score}");
+            store.add_leaderboard_entry(&Games::Snake, DEFAULT_MODE, entry(&name, score * 10));
+        }
+        assert!(!store.qualifies_for_leaderboard(&Games::Snake, DEFAULT_MODE, 10));
+        assert!(store.qualifies_for_leaderboard(&Games::Snake, DEFAULT_MODE, 11));
+        assert!(!store.qualifies_for_leaderboard(&Games::Snake, "Other", 0));
+        assert!(store.qualifies_for_leaderboard(&Games::Snake, "Other", 1));
+
+        store.add_leaderboard_entry(&Games::Snake, DEFAULT_MODE, entry("new", 55));
+        let board = store.leaderboard(&Games::Snake, DEFAULT_MODE);
+        assert_eq!(board.len(), LEADERBOARD_SIZE);
+        assert_eq!(board[5].name, "new");
+        assert_eq!(board.last().unwrap().score, 20);
+    }
+}
@@ -1,39 +1,60 @@
-mod board;
+pub mod board;
 
 use super::{
     super::input::{
-        read_confirmation, read_key, should_decrease_fps, should_force_quit, should_help,
-        should_increase_fps, should_move_down, should_move_left, should_move_right, should_move_up,
-        should_play, should_quit,
+        event_source::EventSource,
+        is_resize,
+        keybindings::{Action, ActionGuide, Keymap},
+        mouse, read_confirmation, read_key, should_force_quit,
     },
-    game_manager::{self, Directions},
+    game_manager::{self, draw, session_seed, Directions, GameBackend, GameSettings},
+    glyphs::Glyphs,
+    leaderboard::{display_leaderboard, modes, stat_title, NamePrompt, NamePromptStatus},
+    records::{LeaderboardEntry, SharedRecords, DEFAULT_MODE},
+    Games,
 };
 use board::Board;
-use crossterm::event::{poll, read};
 use ratatui::{
-    backend::CrosstermBackend,
-    layout::{Alignment, Constraint, Direction, Layout},
+    layout::{Alignment, Constraint, Direction, Layout, Rect},
     style::{Color, Stylize},
     widgets::{Block, Borders, Paragraph},
     Terminal,
 };
-use std::{
-    io::{Result, Stdout},
-    time::Duration,
-};
+use serde::{Deserialize, Serialize};
+use std::{io::Result, time::Duration};
 
 const FPS_CHANGE: i64 = 15;
 
+pub const MENU_ACTIONS: ActionGuide = &[
+    (Action::Play, "Play"),
+    (Action::Continue, "Continue saved game"),
+    (Action::DecreaseFps, "Decrease fps"),
+    (Action::IncreaseFps, "Increase fps"),
+    (Action::Help, "Display game rules"),
+    (Action::Leaderboard, "Display leaderboard"),
+    (Action::Quit, "Go to main menu"),
+];
+
+pub const PLAY_ACTIONS: ActionGuide = &[
+    (Action::MoveUp, "Move up"),
+    (Action::MoveDown, "Move down"),
+    (Action::MoveRight, "Move right"),
+    (Action::MoveLeft, "Move left"),
+    (Action::Quit, "Go to menu"),
+];
+
 enum MenuOpt {
     Play,
+    Continue,
     Quit,
     Help,
+    Leaderboard,
     None,
     IncreaseFPS,
     DecreaseFPS,
 }
 
-#[derive(PartialEq, Eq)]
+#[derive(PartialEq, Eq, Clone, Copy)]
 enum GameState {
     Starting,
     Menu,
@@ -41,10 +62,20 @@ enum GameState {
     Helping,
     Won,
     Lost,
+    EnteringName,
+    Leaderboard,
     AskingToQuit,
     Quitting,
 }
 
+// Everything needed to resume a game left in the middle.
+#[derive(Serialize, Deserialize)]
+struct SavedGame {
+    board: Board,
+    direction: Directions,
+    fps: u64,
+}
+
 pub struct SnakeGameManager {
     game_state: GameState,
     menu_opt: MenuOpt,
@@ -52,20 +83,36 @@ pub struct SnakeGameManager {
     confirmed: bool,
     board: Board,
     record: u32,
+    records: SharedRecords,
+    keymap: Keymap,
+    settings: GameSettings,
+    name_prompt: NamePrompt,
+    end_state: GameState,
     fps: u64,
     kill_execution: bool,
+    resized: bool,
+    // Where the key guide was last drawn, so its entries can be clicked.
+    help_area: Rect,
 }
-impl game_manager::GameManager for SnakeGameManager {
-    fn process_events(&mut self) -> Result<()> {
+impl<B: GameBackend> game_manager::GameManager<B> for SnakeGameManager {
+    fn process_events(&mut self, events: &mut dyn EventSource) -> Result<()> {
         match self.game_state {
             GameState::Starting => (),
-            GameState::Menu | GameState::Won | GameState::Lost => self.read_menu_input()?,
-            GameState::Helping => read_key()?,
-            GameState::Playing => self.read_play_input()?,
+            GameState::Menu | GameState::Won | GameState::Lost => self.read_menu_input(events)?,
+            GameState::Helping | GameState::Leaderboard => {
+                self.resized = is_resize(&read_key(events)?)
+            }
+            GameState::Playing => self.read_play_input(events)?,
+            GameState::EnteringName => {
+                let event = events.read()?;
+                self.kill_execution = should_force_quit(&event);
+                self.name_prompt.handle_event(&event);
+            }
             GameState::AskingToQuit => {
-                let event = read()?;
+                let event = events.read()?;
                 self.kill_execution = should_force_quit(&event);
                 self.confirmed = read_confirmation(&event);
+                self.resized = is_resize(&event);
             }
             GameState::Quitting => (),
         }
@@ -73,42 +120,70 @@ impl game_manager::GameManager for SnakeGameManager {
     }
 
     fn update(&mut self) -> Result<()> {
+        // A resize only needs the screen drawn again.
+        if self.resized {
+            self.resized = false;
+            return Ok(());
+        }
         if self.kill_execution {
+            if matches!(
+                self.game_state,
+                GameState::Playing | GameState::AskingToQuit
+            ) {
+                self.suspend_game()?;
+            }
             self.game_state = GameState::Quitting;
         }
         match self.game_state {
-            GameState::Starting => self.game_state = GameState::Playing,
-            GameState::Helping => self.game_state = GameState::Menu,
+            GameState::Starting => {
+                // Let the player choose between the suspended game and a new one.
+                if self.records.borrow().has_saved_game(&Games::Snake) {
+                    self.game_state = GameState::Menu;
+                } else {
+                    self.game_state = GameState::Playing;
+                }
+            }
+            GameState::Helping | GameState::Leaderboard => self.game_state = GameState::Menu,
             GameState::Menu | GameState::Won | GameState::Lost => match self.menu_opt {
                 MenuOpt::Play => {
                     self.game_state = GameState::Playing;
                     self.direction = Directions::Right;
-                    self.board.reset_board();
+                    self.board.reset_board(session_seed(self.settings.seed));
                 }
+                MenuOpt::Continue => self.resume_game(),
                 MenuOpt::Quit => self.game_state = GameState::Quitting,
                 MenuOpt::Help => self.game_state = GameState::Helping,
+                MenuOpt::Leaderboard => self.game_state = GameState::Leaderboard,
                 MenuOpt::IncreaseFPS => self.change_fps(self.fps as i64 + FPS_CHANGE),
                 MenuOpt::DecreaseFPS => self.change_fps(self.fps as i64 - FPS_CHANGE),
                 MenuOpt::None => (),
             },
             GameState::Playing => {
-                self.board.move_snake(&self.direction);
-                if self.board.snake_died() {
-                    self.game_state = GameState::Lost;
-                } else if self.board.won() {
-                    self.game_state = GameState::Won;
-                }
-                if self.record < self.board.consult_score() {
-                    self.record = self.board.consult_score();
-                }
                 if matches!(self.menu_opt, MenuOpt::Quit) {
                     self.game_state = GameState::AskingToQuit;
                     self.menu_opt = MenuOpt::None;
                 }
             }
+            GameState::EnteringName => match self.name_prompt.status() {
+                NamePromptStatus::Typing => (),
+                NamePromptStatus::Confirmed => {
+                    self.records.borrow_mut().add_leaderboard_entry(
+                        &Games::Snake,
+                        DEFAULT_MODE,
+                        LeaderboardEntry::new(
+                            self.name_prompt.name(),
+                            self.board.consult_score(),
+                            None,
+                        ),
+                    );
+                    self.game_state = self.end_state;
+                }
+                NamePromptStatus::Skipped => self.game_state = self.end_state,
+            },
             GameState::AskingToQuit => match self.confirmed {
                 true => {
-                    self.board.reset_board();
+                    self.suspend_game()?;
+                    self.board.reset_board(session_seed(self.settings.seed));
                     self.game_state = GameState::Menu;
                 }
                 false => self.game_state = GameState::Playing,
@@ -118,45 +193,86 @@ impl game_manager::GameManager for SnakeGameManager {
         Ok(())
     }
 
-    fn render(&mut self, terminal: &mut Terminal<CrosstermBackend<Stdout>>) -> Result<()> {
+    fn tick(&mut self) -> Result<()> {
+        self.board.move_snake(&self.direction);
+        if self.board.snake_died() {
+            self.finish_game(GameState::Lost);
+        } else if self.board.won() {
+            self.finish_game(GameState::Won);
+        }
+        if self.record < self.board.consult_score() {
+            self.record = self.board.consult_score();
+            let record = self.record;
+            self.records
+                .borrow_mut()
+                .update(|records| records.snake = record);
+        }
+        Ok(())
+    }
+
+    fn tick_rate(&self) -> Option<Duration> {
+        match self.game_state {
+            GameState::Playing => Some(Duration::from_secs(1) / self.fps as u32),
+            _ => None,
+        }
+    }
+
+    fn render(&mut self, terminal: &mut Terminal<B>) -> Result<()> {
+        let theme = self.settings.theme.clone();
+        let theme = theme.borrow();
         match self.game_state {
             GameState::Starting => (),
             GameState::Helping => self.display_game_rules(terminal)?,
             GameState::Menu => self.display_screen(
                 terminal,
                 self.record,
-                Self::menu_guide(),
+                self.menu_guide(),
                 "Menu",
                 "Record",
                 "",
-                Color::Gray,
+                theme.text,
             )?,
             GameState::Playing => self.display_screen(
                 terminal,
                 self.board.consult_score(),
-                Self::play_guide(),
+                self.play_guide(),
                 "Game board",
                 "Score",
                 "",
-                Color::Gray,
+                theme.text,
             )?,
             GameState::Won => self.display_screen(
                 terminal,
                 self.record,
-                Self::menu_guide(),
+                self.menu_guide(),
                 "Menu",
                 "Record",
                 "You won, congratulations!!",
-                Color::Green,
+                theme.success,
             )?,
             GameState::Lost => self.display_screen(
                 terminal,
                 self.record,
-                Self::menu_guide(),
+                self.menu_guide(),
                 "Menu",
                 "Record",
                 "You lost.",
-                Color::Red,
+                theme.failure,
+            )?,
+            GameState::EnteringName => self.name_prompt.display(
+                terminal,
+                self.board.consult_score(),
+                &theme,
+                &self.settings.glyphs,
+            )?,
+            GameState::Leaderboard => display_leaderboard(
+                terminal,
+                &self.records.borrow(),
+                &Games::Snake,
+                &modes(&Games::Snake),
+                stat_title(&Games::Snake),
+                &theme,
+                &self.settings.glyphs,
             )?,
             GameState::AskingToQuit => self.display_screen(
                 terminal,
@@ -165,7 +281,7 @@ impl game_manager::GameManager for SnakeGameManager {
                 "Quitting",
                 "Score",
                 "Are you sure you want to quit?",
-                Color::Yellow,
+                theme.warning,
             )?,
             GameState::Quitting => (),
         }
@@ -176,7 +292,7 @@ impl game_manager::GameManager for SnakeGameManager {
         self.game_state = GameState::Starting;
         self.direction = Directions::Right;
         self.menu_opt = MenuOpt::None;
-        self.board.reset_board();
+        self.board.reset_board(session_seed(self.settings.seed));
     }
 
     fn ended(&self) -> bool {
@@ -187,28 +303,52 @@ impl game_manager::GameManager for SnakeGameManager {
         self.kill_execution
     }
 
-    fn limit_fps(&self) {
-        std::thread::sleep(Duration::from_millis(1000 / self.fps));
+    fn force_quit(&mut self) {
+        self.kill_execution = true;
+    }
+
+    fn min_size(&self) -> (u16, u16) {
+        game_manager::min_size_for(
+            &self.board.display_board(
+                String::new(),
+                Color::Reset,
+                &self.settings.theme.borrow(),
+                &self.settings.glyphs,
+            ),
+            50,
+            100,
+        )
+    }
+
+    fn glyphs(&self) -> Glyphs {
+        self.settings.glyphs
     }
 }
 impl SnakeGameManager {
-    pub fn new() -> Self {
+    pub fn new(records: SharedRecords, settings: GameSettings) -> Self {
+        let record = records.borrow().records().snake;
+        let (height, width) = settings.options.board_size.unwrap_or((12, 20));
+        let fps = settings.options.fps.unwrap_or(15);
         SnakeGameManager {
             game_state: GameState::Starting,
             menu_opt: MenuOpt::None,
             direction: Directions::Right,
             confirmed: false,
-            board: Board::new(12, 20),
-            record: 0,
-            fps: 15,
+            board: Board::new(height, width, session_seed(settings.seed)),
+            record,
+            records,
+            keymap: settings.keybindings.keymap(Games::Snake.id()),
+            settings,
+            name_prompt: NamePrompt::new(),
+            end_state: GameState::Lost,
+            fps,
             kill_execution: false,
+            resized: false,
+            help_area: Rect::default(),
         }
     }
 
-    fn display_game_rules(
-        &mut self,
-        terminal: &mut Terminal<CrosstermBackend<Stdout>>,
-    ) -> Result<()> {
+    fn display_game_rules<B: GameBackend>(&mut self, terminal: &mut Terminal<B>) -> Result<()> {
         let message = String::from(
             "Imagine you're a little snake, slithering around a tiny world, looking for food. In 
 this game, you control the snake's movements, guiding it across the screen.
@@ -227,16 +367,20 @@ So, to sum it up: guide your snake around, gobble up the food to grow longer, an
 crashing into anything. It's a simple concept, but you'll find yourself hooked in no time,
 trying to beat your high score with each game!",
         );
-        terminal.draw(|frame| {
+        draw(terminal, |frame| {
             let area = frame.area();
-            frame.render_widget(Paragraph::new(message).white(), area)
+            frame.render_widget(
+                Paragraph::new(message).fg(self.settings.theme.borrow().text),
+                area,
+            )
         })?;
         Ok(())
     }
 
-    fn display_screen(
+    #[allow(clippy::too_many_arguments)]
+    fn display_screen<B: GameBackend>(
         &mut self,
-        terminal: &mut Terminal<CrosstermBackend<Stdout>>,
+        terminal: &mut Terminal<B>,
         score: u32,
         help_message: String,
         title: &str,
@@ -244,7 +388,11 @@ trying to beat your high score with each game!",
         message: &str,
         color: Color,
     ) -> Result<()> {
-        terminal.draw(|frame| {
+        let theme = self.settings.theme.clone();
+        let theme = theme.borrow();
+        let glyphs = self.settings.glyphs;
+        let mut help_area = Rect::default();
+        draw(terminal, |frame| {
             let layout = Layout::default()
                 .direction(Direction::Horizontal)
                 .constraints([Constraint::Percentage(50), Constraint::Percentage(50)])
@@ -255,10 +403,18 @@ trying to beat your high score with each game!",
                 .split(layout[1]);
 
             frame.render_widget(
-                Paragraph::new(self.board.display_board(message.to_string(), color)).block(
+                Paragraph::new(self.board.display_board(
+                    message.to_string(),
+                    color,
+                    &theme,
+                    &glyphs,
+                ))
+                .block(
                     Block::new()
                         .borders(Borders::ALL)
+                        .border_set(glyphs.border)
                         .title(title)
+                        .title_bottom(format!("Seed {}", self.board.seed()))
                         .title_alignment(Alignment::Center),
                 ),
                 layout[0],
@@ -268,6 +424,7 @@ trying to beat your high score with each game!",
                 Paragraph::new(score.to_string()).block(
                     Block::new()
                         .borders(Borders::ALL)
+                        .border_set(glyphs.border)
                         .title(score_title)
                         .title_alignment(Alignment::Center),
                 ),
@@ -278,45 +435,55 @@ trying to beat your high score with each game!",
                 Paragraph::new(help_message).block(
                     Block::new()
                         .borders(Borders::ALL)
+                        .border_set(glyphs.border)
                         .title("Help")
                         .title_alignment(Alignment::Center),
                 ),
                 sub_layout[1],
             );
+            help_area = mouse::inner_area(sub_layout[1]);
         })?;
+        self.help_area = help_area;
         Ok(())
     }
 
-    fn play_guide() -> String {
-        String::from(
-            "w or k or    - Move up\ns or j or    - Move down\nd or l or    - Move right\na or h or    - Move left\nESC or q      - Go to menu",
-        )
+    fn play_guide(&self) -> String {
+        self.keymap.guide(PLAY_ACTIONS, &self.settings.glyphs)
     }
 
-    fn menu_guide() -> String {
-        String::from("ENTER or p - Play\nf          - Decrease fps\nF          - Increase fps\n?          - Display game rules\nESC or q   - Go to main menu\n",
-        )
+    fn menu_guide(&self) -> String {
+        self.keymap.guide(MENU_ACTIONS, &self.settings.glyphs)
     }
 
-    fn read_menu_input(&mut self) -> Result<()> {
+    fn read_menu_input(&mut self, events: &mut dyn EventSource) -> Result<()> {
         loop {
-            let event = read()?;
-            if should_quit(&event) {
+            let event =
+                mouse::click_to_key(events.read()?, self.help_area, MENU_ACTIONS, &self.keymap);
+            if is_resize(&event) {
+                self.resized = true;
+                break;
+            } else if self.keymap.matches(Action::Quit, &event) {
                 self.menu_opt = MenuOpt::Quit;
                 break;
             } else if should_force_quit(&event) {
                 self.kill_execution = true;
                 break;
-            } else if should_play(&event) {
+            } else if self.keymap.matches(Action::Play, &event) {
                 self.menu_opt = MenuOpt::Play;
                 break;
-            } else if should_help(&event) {
+            } else if self.keymap.matches(Action::Continue, &event) {
+                self.menu_opt = MenuOpt::Continue;
+                break;
+            } else if self.keymap.matches(Action::Help, &event) {
                 self.menu_opt = MenuOpt::Help;
                 break;
-            } else if should_increase_fps(&event) {
+            } else if self.keymap.matches(Action::Leaderboard, &event) {
+                self.menu_opt = MenuOpt::Leaderboard;
+                break;
+            } else if self.keymap.matches(Action::IncreaseFps, &event) {
                 self.menu_opt = MenuOpt::IncreaseFPS;
                 break;
-            } else if should_decrease_fps(&event) {
+            } else if self.keymap.matches(Action::DecreaseFps, &event) {
                 self.menu_opt = MenuOpt::DecreaseFPS;
                 break;
             }
@@ -324,26 +491,62 @@ trying to beat your high score with each game!",
         Ok(())
     }
 
-    fn read_play_input(&mut self) -> Result<()> {
-        if poll(Duration::from_millis(100))? {
-            let event = read()?;
-            if should_move_up(&event) {
-                self.direction = Directions::Up;
-            } else if should_move_down(&event) {
-                self.direction = Directions::Down;
-            } else if should_move_left(&event) {
-                self.direction = Directions::Left;
-            } else if should_move_right(&event) {
-                self.direction = Directions::Right;
-            } else if should_quit(&event) {
-                self.menu_opt = MenuOpt::Quit;
-            } else if should_force_quit(&event) {
-                self.kill_execution = true;
-            }
+    fn read_play_input(&mut self, events: &mut dyn EventSource) -> Result<()> {
+        let event = events.read()?;
+        if self.keymap.matches(Action::MoveUp, &event) {
+            self.direction = Directions::Up;
+        } else if self.keymap.matches(Action::MoveDown, &event) {
+            self.direction = Directions::Down;
+        } else if self.keymap.matches(Action::MoveLeft, &event) {
+            self.direction = Directions::Left;
+        } else if self.keymap.matches(Action::MoveRight, &event) {
+            self.direction = Directions::Right;
+        } else if self.keymap.matches(Action::Quit, &event) {
+            self.menu_opt = MenuOpt::Quit;
+        } else if should_force_quit(&event) {
+            self.kill_execution = true;
         }
         Ok(())
     }
 
+    fn finish_game(&mut self, end_state: GameState) {
+        if self.records.borrow().qualifies_for_leaderboard(
+            &Games::Snake,
+            DEFAULT_MODE,
+            self.board.consult_score(),
+        ) {
+            self.name_prompt.clear();
+            self.end_state = end_state;
+            self.game_state = GameState::EnteringName;
+        } else {
+            self.game_state = end_state;
+        }
+    }
+
+    fn suspend_game(&mut self) -> Result<()> {
+        self.records.borrow_mut().store_saved_game(
+            &Games::Snake,
+            SavedGame {
+                board: self.board.clone(),
+                direction: self.direction.clone(),
+                fps: self.fps,
+            },
+        )
+    }
+
+    fn resume_game(&mut self) {
+        let saved = self
+            .records
+            .borrow_mut()
+            .take_saved_game::<SavedGame>(&Games::Snake);
+        if let Some(saved) = saved {
+            self.board = saved.board;
+            self.direction = saved.direction;
+            self.fps = saved.fps;
+            self.game_state = GameState::Playing;
+        }
+    }
+
     fn change_fps(&mut self, fps: i64) {
         if fps > 0 {
             self.fps = fps as u64;
@@ -1,11 +1,14 @@
-use super::game_manager::{Coord, Directions};
+use super::super::{glyphs::Glyphs, theme::Theme};
+use super::game_manager::{new_rng, serde_rng, serde_seed, Coord, Directions, GameRng};
+use rand::Rng;
 use ratatui::style::{Color, Style, Stylize};
 use ratatui::text::{Line, Span};
+use serde::{Deserialize, Serialize};
 use std::collections::VecDeque;
 use std::vec::Vec;
 
-#[derive(Clone)]
-enum BoardPossibilities {
+#[derive(Clone, Serialize, Deserialize)]
+pub enum BoardPossibilities {
     SnakeBody,
     SnakeHead,
     SnakeDead,
@@ -13,26 +16,33 @@ enum BoardPossibilities {
     Empty,
 }
 
+#[derive(Clone, Serialize, Deserialize)]
 pub struct Board {
     board: Vec<BoardPossibilities>,
     height: usize,
     width: usize,
     score: u32,
     snake: VecDeque<Coord>,
+    #[serde(with = "serde_seed")]
+    seed: u64,
+    #[serde(with = "serde_rng")]
+    rng: GameRng,
 }
 impl Board {
-    pub fn new(board_height: usize, board_width: usize) -> Self {
+    pub fn new(board_height: usize, board_width: usize, seed: u64) -> Self {
         let mut board = Self {
             board: Vec::with_capacity(board_height * board_width),
             height: board_height,
             width: board_width,
             score: 1,
             snake: VecDeque::new(),
+            seed,
+            rng: new_rng(seed),
         };
         board
             .board
             .resize(board.height * board.width, BoardPossibilities::Empty);
-        board.reset_board();
+        board.reset_board(seed);
         board
     }
 
@@ -54,7 +64,7 @@ impl Board {
         if let Some(value) = self.snake.front() {
             head = value.clone();
         } else {
-            panic!();
+            unreachable!("the snake always has at least its head");
         }
         let mut new_head_pos = head.clone();
         // making indexing from one to avoid overflow, since its unsigned.
@@ -96,49 +106,81 @@ impl Board {
         self.score
     }
 
-    pub fn display_board(&self, message: String, color: Color) -> Vec<Line<'_>> {
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn display_board(
+        &self,
+        message: String,
+        color: Color,
+        theme: &Theme,
+        glyphs: &Glyphs,
+    ) -> Vec<Line<'_>> {
         let mut lines: Vec<Line> = Vec::new();
         let mut spans: String = String::new();
-        spans += "╭";
+        spans += glyphs.line.top_left;
         for _counter in 1..self.width + 1 {
-            spans += "──";
+            spans += &glyphs.line.horizontal.repeat(2);
         }
-        spans += "╮";
+        spans += glyphs.line.top_right;
         lines.push(Line::from(Span::styled(
             spans,
-            Style::default().fg(Color::DarkGray),
+            Style::default().fg(theme.dim),
         )));
         for i in 0..self.height {
             let mut spans: Vec<Span> = Vec::new();
-            spans.push(Span::styled("│", Style::default().fg(Color::DarkGray)));
+            spans.push(Span::styled(
+                glyphs.line.vertical,
+                Style::default().fg(theme.dim),
+            ));
             for j in 0..self.width {
                 match self.consult_board(i, j) {
                     BoardPossibilities::Empty => spans.push(Span::styled("  ", Style::default())),
                     BoardPossibilities::SnakeBody => {
-                        spans.push(Span::styled("██", Style::default().fg(Color::Blue)));
+                        spans.push(Span::styled(
+                            glyphs.square,
+                            Style::default().fg(theme.snake_body),
+                        ));
                     }
                     BoardPossibilities::SnakeHead => {
-                        spans.push(Span::styled("██", Style::default().fg(Color::Cyan)));
+                        // Patterns tell the head from the body without colors.
+                        let head = if theme.patterns {
+                            glyphs.snake_head
+                        } else {
+                            glyphs.square
+                        };
+                        spans.push(Span::styled(head, Style::default().fg(theme.snake_head)));
                     }
                     BoardPossibilities::SnakeDead => {
-                        spans.push(Span::styled("󰯈 ", Style::default().fg(Color::Red)));
+                        spans.push(Span::styled(
+                            glyphs.snake_dead,
+                            Style::default().fg(theme.snake_dead),
+                        ));
                     }
                     BoardPossibilities::Food => {
-                        spans.push(Span::styled("󰉛 ", Style::default().fg(Color::LightRed)));
+                        spans.push(Span::styled(glyphs.food, Style::default().fg(theme.food)));
                     }
                 }
             }
-            spans.push(Span::styled("│", Style::default().fg(Color::DarkGray)));
+            spans.push(Span::styled(
+                glyphs.line.vertical,
+                Style::default().fg(theme.dim),
+            ));
             lines.push(Line::from(spans));
         }
         let mut spans: String = String::new();
-        spans += "╰";
+        spans += glyphs.line.bottom_left;
         for _counter in 1..self.width + 1 {
-            spans += "──";
+            spans += &glyphs.line.horizontal.repeat(2);
         }
-        spans += "╯";
+        spans += glyphs.line.bottom_right;
         lines.push(Line::from(
-            Span::styled(spans, Style::default()).fg(Color::DarkGray),
+            Span::styled(spans, Style::default()).fg(theme.dim),
         ));
         if !message.is_empty() {
             lines.push(Line::from(Span::styled(message, color)));
@@ -150,7 +192,13 @@ impl Board {
         lines
     }
 
-    pub fn reset_board(&mut self) {
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    pub fn reset_board(&mut self, seed: u64) {
+        self.seed = seed;
+        self.rng = new_rng(seed);
         self.score = 1;
         self.board.fill(BoardPossibilities::Empty);
         self.snake.clear();
@@ -164,8 +212,8 @@ impl Board {
     }
 
     fn generate_food(&mut self) {
-        let mut line = rand::random_range(0..self.height);
-        let mut column = rand::random_range(0..self.width);
+        let mut line = self.rng.random_range(0..self.height);
+        let mut column = self.rng.random_range(0..self.width);
         while !matches!(self.consult_board(line, column), BoardPossibilities::Empty) {
             column += 1;
             if column == self.width {
@@ -179,7 +227,7 @@ impl Board {
         *self.get_board_position(&line, &column) = BoardPossibilities::Food;
     }
 
-    fn consult_board(&self, line: usize, column: usize) -> &BoardPossibilities {
+    pub fn consult_board(&self, line: usize, column: usize) -> &BoardPossibilities {
         &self.board[line * self.width + column]
     }
 
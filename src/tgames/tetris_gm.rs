@@ -1,32 +1,57 @@
-mod board;
+pub mod board;
 mod bricks;
 
 use super::{
     super::input::{
-        read_confirmation, read_key, should_force_quit, should_help, should_move_down,
-        should_move_left, should_move_right, should_move_up, should_pause, should_play,
-        should_quit,
+        event_source::EventSource,
+        is_resize,
+        keybindings::{Action, ActionGuide, Keymap},
+        mouse, read_confirmation, read_key, should_force_quit,
     },
-    game_manager::{self, GameManager},
+    game_manager::{self, draw, session_seed, GameBackend, GameManager, GameSettings},
+    glyphs::Glyphs,
+    leaderboard::{display_leaderboard, modes, stat_title, NamePrompt, NamePromptStatus},
+    records::{LeaderboardEntry, SharedRecords, DEFAULT_MODE},
+    Games,
 };
 use board::Board;
-use crossterm::event::{poll, read, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
 use ratatui::{
-    backend::CrosstermBackend,
-    layout::{Alignment, Constraint, Direction, Layout},
+    layout::{Alignment, Constraint, Direction, Layout, Rect},
     style::Stylize,
     widgets::{Block, Borders, Paragraph},
     Terminal,
 };
-use std::{
-    io::{Result, Stdout},
-    time::Duration,
-};
+use serde::{Deserialize, Serialize};
+use std::{io::Result, time::Duration};
+
+// The brick falls one line every few ticks, the moves in between are applied right away.
+const TICK_RATE: Duration = Duration::from_millis(50);
+const TICKS_PER_DROP: u32 = 4;
+
+pub const MENU_ACTIONS: ActionGuide = &[
+    (Action::Play, "Play"),
+    (Action::Continue, "Continue saved game"),
+    (Action::Help, "Display game rules"),
+    (Action::Leaderboard, "Display leaderboard"),
+    (Action::Quit, "Go to main menu"),
+];
+
+pub const PLAY_ACTIONS: ActionGuide = &[
+    (Action::MoveRight, "Move piece to the right"),
+    (Action::MoveLeft, "Move piece to the left"),
+    (Action::Rotate, "Rotate piece"),
+    (Action::SoftDrop, "Soft drop"),
+    (Action::HardDrop, "Hard drop"),
+    (Action::Pause, "Pause game"),
+    (Action::Quit, "Go back to menu"),
+];
 
 enum MenuOpt {
     Play,
+    Continue,
     Quit,
     Help,
+    Leaderboard,
     None,
 }
 
@@ -49,10 +74,19 @@ enum GameState {
     Playing,
     Pause,
     Lost,
+    EnteringName,
+    Leaderboard,
     AskingToQuit,
     Quitting,
 }
 
+// Everything needed to resume a game left in the middle.
+#[derive(Serialize, Deserialize)]
+struct SavedGame {
+    board: Board,
+    counter: u32,
+}
+
 pub struct TetrisGameManager {
     game_state: GameState,
     menu_opt: MenuOpt,
@@ -62,20 +96,39 @@ pub struct TetrisGameManager {
     counter: u32,
     score_record: u32,
     line_record: u32,
+    records: SharedRecords,
+    keymap: Keymap,
+    settings: GameSettings,
+    name_prompt: NamePrompt,
     kill_execution: bool,
+    resized: bool,
+    // Where the key guide was last drawn, so its entries can be clicked.
+    help_area: Rect,
 }
-impl GameManager for TetrisGameManager {
-    fn process_events(&mut self) -> Result<()> {
+impl<B: GameBackend> GameManager<B> for TetrisGameManager {
+    fn process_events(&mut self, events: &mut dyn EventSource) -> Result<()> {
         match self.game_state {
             GameState::Starting => (),
-            GameState::Helping => read_key()?,
-            GameState::Menu | GameState::Lost => self.read_menu_input()?,
-            GameState::Playing => self.read_play_input()?,
-            GameState::Pause => read_key()?,
+            GameState::Helping | GameState::Leaderboard => {
+                self.resized = is_resize(&read_key(events)?)
+            }
+            GameState::Menu | GameState::Lost => self.read_menu_input(events)?,
+            GameState::Playing => self.read_play_input(events)?,
+            GameState::EnteringName => {
+                let event = events.read()?;
+                self.kill_execution = should_force_quit(&event);
+                self.name_prompt.handle_event(&event);
+            }
+            GameState::Pause => {
+                let event = events.read()?;
+                self.kill_execution = should_force_quit(&event);
+                self.resized = is_resize(&event);
+            }
             GameState::AskingToQuit => {
-                let event = read()?;
+                let event = events.read()?;
                 self.kill_execution = should_force_quit(&event);
                 self.confirmed = read_confirmation(&event);
+                self.resized = is_resize(&event);
             }
             GameState::Quitting => (),
         }
@@ -83,33 +136,50 @@ impl GameManager for TetrisGameManager {
     }
 
     fn update(&mut self) -> Result<()> {
+        // A resize only needs the screen drawn again.
+        if self.resized {
+            self.resized = false;
+            return Ok(());
+        }
         if self.kill_execution {
+            if matches!(
+                self.game_state,
+                GameState::Playing | GameState::Pause | GameState::AskingToQuit
+            ) {
+                self.suspend_game()?;
+            }
             self.game_state = GameState::Quitting;
         }
         match self.game_state {
-            GameState::Starting => self.game_state = GameState::Playing,
-            GameState::Helping => self.game_state = GameState::Menu,
+            GameState::Starting => {
+                // Let the player choose between the suspended game and a new one.
+                if self.records.borrow().has_saved_game(&Games::Tetris) {
+                    self.game_state = GameState::Menu;
+                } else {
+                    self.game_state = GameState::Playing;
+                }
+            }
+            GameState::Helping | GameState::Leaderboard => self.game_state = GameState::Menu,
             GameState::Menu | GameState::Lost => match self.menu_opt {
                 MenuOpt::Play => {
                     if let GameState::Lost = self.game_state {
-                        self.board.reset_board();
+                        self.board.reset_board(session_seed(self.settings.seed));
                     }
                     self.game_state = GameState::Playing;
                 }
+                MenuOpt::Continue => self.resume_game(),
                 MenuOpt::Help => {
                     self.game_state = GameState::Helping;
                 }
+                MenuOpt::Leaderboard => {
+                    self.game_state = GameState::Leaderboard;
+                }
                 MenuOpt::Quit => {
                     self.game_state = GameState::Quitting;
                 }
                 MenuOpt::None => (),
             },
             GameState::Playing => {
-                self.counter += 1;
-                if self.counter > 3 {
-                    self.board.drop();
-                    self.counter = 0;
-                }
                 match self.play_opt {
                     PlayOpt::Left => {
                         self.board.move_left();
@@ -137,16 +207,34 @@ impl GameManager for TetrisGameManager {
                     }
                     PlayOpt::None => (),
                 }
+                self.play_opt = PlayOpt::None;
                 if self.board.defeated() {
-                    self.game_state = GameState::Lost;
                     self.update_record();
+                    self.finish_game();
                 }
             }
             GameState::Pause => self.game_state = GameState::Playing,
+            GameState::EnteringName => match self.name_prompt.status() {
+                NamePromptStatus::Typing => (),
+                NamePromptStatus::Confirmed => {
+                    self.records.borrow_mut().add_leaderboard_entry(
+                        &Games::Tetris,
+                        DEFAULT_MODE,
+                        LeaderboardEntry::new(
+                            self.name_prompt.name(),
+                            self.board.consult_score(),
+                            Some(self.board.consult_lines_completed()),
+                        ),
+                    );
+                    self.game_state = GameState::Lost;
+                }
+                NamePromptStatus::Skipped => self.game_state = GameState::Lost,
+            },
             GameState::AskingToQuit => match self.confirmed {
                 true => {
+                    self.suspend_game()?;
                     self.game_state = GameState::Menu;
-                    self.board.reset_board();
+                    self.board.reset_board(session_seed(self.settings.seed));
                 }
                 false => self.game_state = GameState::Playing,
             },
@@ -155,7 +243,29 @@ impl GameManager for TetrisGameManager {
         Ok(())
     }
 
-    fn render(&mut self, terminal: &mut Terminal<CrosstermBackend<Stdout>>) -> Result<()> {
+    fn tick(&mut self) -> Result<()> {
+        self.counter += 1;
+        if self.counter >= TICKS_PER_DROP {
+            self.board.step_down();
+            self.counter = 0;
+        }
+        if self.board.defeated() {
+            self.update_record();
+            self.finish_game();
+        }
+        Ok(())
+    }
+
+    fn tick_rate(&self) -> Option<Duration> {
+        match self.game_state {
+            GameState::Playing => Some(TICK_RATE),
+            _ => None,
+        }
+    }
+
+    fn render(&mut self, terminal: &mut Terminal<B>) -> Result<()> {
+        let theme = self.settings.theme.clone();
+        let theme = theme.borrow();
         match self.game_state {
             GameState::Starting => (),
             GameState::Helping => self.display_game_rules(terminal)?,
@@ -163,7 +273,7 @@ impl GameManager for TetrisGameManager {
                 terminal,
                 self.score_record,
                 self.line_record,
-                Self::menu_guide(),
+                self.menu_guide(),
                 "Menu",
                 "Record",
                 "",
@@ -172,7 +282,7 @@ impl GameManager for TetrisGameManager {
                 terminal,
                 self.board.consult_score(),
                 self.board.consult_lines_completed(),
-                Self::play_guide(),
+                self.play_guide(),
                 "Game board",
                 "Score",
                 "",
@@ -181,7 +291,7 @@ impl GameManager for TetrisGameManager {
                 terminal,
                 self.score_record,
                 self.line_record,
-                Self::menu_guide(),
+                self.menu_guide(),
                 "Menu",
                 "Score",
                 "Game is paused. Press enter to continue.",
@@ -190,11 +300,26 @@ impl GameManager for TetrisGameManager {
                 terminal,
                 self.score_record,
                 self.line_record,
-                Self::menu_guide(),
+                self.menu_guide(),
                 "Menu",
                 "Record",
                 "You lost! Press enter to try again.",
             )?,
+            GameState::EnteringName => self.name_prompt.display(
+                terminal,
+                self.board.consult_score(),
+                &theme,
+                &self.settings.glyphs,
+            )?,
+            GameState::Leaderboard => display_leaderboard(
+                terminal,
+                &self.records.borrow(),
+                &Games::Tetris,
+                &modes(&Games::Tetris),
+                stat_title(&Games::Tetris),
+                &theme,
+                &self.settings.glyphs,
+            )?,
             GameState::AskingToQuit => self.display_screen(
                 terminal,
                 self.board.consult_score(),
@@ -212,7 +337,7 @@ impl GameManager for TetrisGameManager {
     fn reset(&mut self) {
         self.game_state = GameState::Starting;
         self.counter = 0;
-        self.board.reset_board();
+        self.board.reset_board(session_seed(self.settings.seed));
     }
 
     fn ended(&self) -> bool {
@@ -222,47 +347,119 @@ impl GameManager for TetrisGameManager {
     fn kill_execution(&self) -> bool {
         self.kill_execution
     }
+
+    fn force_quit(&mut self) {
+        self.kill_execution = true;
+    }
+
+    fn min_size(&self) -> (u16, u16) {
+        game_manager::min_size_for(
+            &self.board.display_board(
+                String::new(),
+                &self.settings.theme.borrow(),
+                &self.settings.glyphs,
+            ),
+            50,
+            100,
+        )
+    }
+
+    fn glyphs(&self) -> Glyphs {
+        self.settings.glyphs
+    }
 }
 
 impl TetrisGameManager {
-    pub fn new() -> Self {
+    pub fn new(records: SharedRecords, settings: GameSettings) -> Self {
+        let (score_record, line_record) = {
+            let records = records.borrow();
+            (
+                records.records().tetris_score,
+                records.records().tetris_lines,
+            )
+        };
         Self {
             game_state: GameState::Starting,
             menu_opt: MenuOpt::None,
             play_opt: PlayOpt::None,
             confirmed: false,
-            board: Board::new(),
+            board: Board::new(session_seed(settings.seed)),
             counter: 0,
-            score_record: 0,
-            line_record: 0,
+            score_record,
+            line_record,
+            records,
+            keymap: settings.keybindings.keymap(Games::Tetris.id()),
+            settings,
+            name_prompt: NamePrompt::new(),
             kill_execution: false,
+            resized: false,
+            help_area: Rect::default(),
         }
     }
 
-    fn menu_guide() -> String {
-        String::from(
-            "ENTER or p - Play\n?          - Display game rules\nESC or q   - Go to main menu\n",
-        )
+    fn menu_guide(&self) -> String {
+        self.keymap.guide(MENU_ACTIONS, &self.settings.glyphs)
+    }
+
+    fn play_guide(&self) -> String {
+        self.keymap.guide(PLAY_ACTIONS, &self.settings.glyphs)
     }
 
-    fn play_guide() -> String {
-        String::from(
-            "d or l or  - Move piece to the right\na or h or  - Move piece to the left\nw or k or  - Rotate piece\ns or j or  - Soft drop\nSPACE       - Hard drop\np           - Pause game\nESC or q    - Go back to menu\n ",
+    fn suspend_game(&mut self) -> Result<()> {
+        self.records.borrow_mut().store_saved_game(
+            &Games::Tetris,
+            SavedGame {
+                board: self.board.clone(),
+                counter: self.counter,
+            },
         )
     }
 
+    // The game is resumed paused, so the player has time to get ready.
+    fn resume_game(&mut self) {
+        let saved = self
+            .records
+            .borrow_mut()
+            .take_saved_game::<SavedGame>(&Games::Tetris);
+        if let Some(saved) = saved {
+            self.board = saved.board;
+            self.counter = saved.counter;
+            self.game_state = GameState::Pause;
+        }
+    }
+
     fn update_record(&mut self) {
-        if self.board.consult_score() > self.score_record {
-            self.score_record = self.board.consult_score();
+        if self.board.consult_score() <= self.score_record
+            && self.board.consult_lines_completed() <= self.line_record
+        {
+            return;
         }
-        if self.board.consult_lines_completed() > self.line_record {
-            self.line_record = self.board.consult_lines_completed();
+        self.score_record = self.score_record.max(self.board.consult_score());
+        self.line_record = self.line_record.max(self.board.consult_lines_completed());
+        let (score_record, line_record) = (self.score_record, self.line_record);
+        self.records.borrow_mut().update(|records| {
+            records.tetris_score = score_record;
+            records.tetris_lines = line_record;
+        });
+    }
+
+    fn finish_game(&mut self) {
+        if self.records.borrow().qualifies_for_leaderboard(
+            &Games::Tetris,
+            DEFAULT_MODE,
+            self.board.consult_score(),
+        ) {
+            self.name_prompt.clear();
+            self.game_state = GameState::EnteringName;
+        } else {
+            self.game_state = GameState::Lost;
         }
     }
 
-    fn display_screen(
+    #[allow(clippy::too_many_arguments)]
+    fn display_screen<B: GameBackend>(
         &mut self,
-        terminal: &mut Terminal<CrosstermBackend<Stdout>>,
+        terminal: &mut Terminal<B>,
         score: u32,
         line_score: u32,
         help_message: String,
@@ -270,7 +467,11 @@ impl TetrisGameManager {
         score_title: &str,
         message: &str,
     ) -> Result<()> {
-        terminal.draw(|frame| {
+        let theme = self.settings.theme.clone();
+        let theme = theme.borrow();
+        let glyphs = self.settings.glyphs;
+        let mut help_area = Rect::default();
+        draw(terminal, |frame| {
             let layout = Layout::default()
                 .direction(Direction::Horizontal)
                 .constraints([Constraint::Percentage(50), Constraint::Percentage(50)])
@@ -286,19 +487,26 @@ impl TetrisGameManager {
                 .split(layout[1]);
 
             frame.render_widget(
-                Paragraph::new(self.board.display_board(message.to_string())).block(
+                Paragraph::new(
+                    self.board
+                        .display_board(message.to_string(), &theme, &glyphs),
+                )
+                .block(
                     Block::new()
                         .borders(Borders::ALL)
+                        .border_set(glyphs.border)
                         .title(title)
+                        .title_bottom(format!("Seed {}", self.board.seed()))
                         .title_alignment(Alignment::Center),
                 ),
                 layout[0],
             );
 
             frame.render_widget(
-                Paragraph::new(self.board.display_next_brick()).block(
+                Paragraph::new(self.board.display_next_brick(&theme, &glyphs)).block(
                     Block::new()
                         .borders(Borders::ALL)
+                        .border_set(glyphs.border)
                         .title("Next brick")
                         .title_alignment(Alignment::Center),
                 ),
@@ -309,6 +517,7 @@ impl TetrisGameManager {
                 Paragraph::new(score.to_string()).block(
                     Block::new()
                         .borders(Borders::ALL)
+                        .border_set(glyphs.border)
                         .title("Game ".to_string() + score_title)
                         .title_alignment(Alignment::Center),
                 ),
@@ -319,6 +528,7 @@ impl TetrisGameManager {
                 Paragraph::new(line_score.to_string()).block(
                     Block::new()
                         .borders(Borders::ALL)
+                        .border_set(glyphs.border)
                         .title("Line ".to_string() + score_title)
                         .title_alignment(Alignment::Center),
                 ),
@@ -329,19 +539,19 @@ impl TetrisGameManager {
                 Paragraph::new(help_message).block(
                     Block::new()
                         .borders(Borders::ALL)
+                        .border_set(glyphs.border)
                         .title("Help")
                         .title_alignment(Alignment::Center),
                 ),
                 sub_layout[3],
             );
+            help_area = mouse::inner_area(sub_layout[3]);
         })?;
+        self.help_area = help_area;
         Ok(())
     }
 
-    fn display_game_rules(
-        &mut self,
-        terminal: &mut Terminal<CrosstermBackend<Stdout>>,
-    ) -> Result<()> {
+    fn display_game_rules<B: GameBackend>(&mut self, terminal: &mut Terminal<B>) -> Result<()> {
         let message = String::from(
             "Tetris is like a puzzle game where you fit different shapes together to clear lines.
 Imagine you have a little playground, and colorful blocks start falling from the sky,
@@ -360,63 +570,64 @@ over!
 So, in short: move, spin, and stack the falling blocks to make solid lines and keep the 
 playground clear. It's easy to learn, but oh-so-addictive once you get going!",
         );
-        terminal.draw(|frame| {
+        draw(terminal, |frame| {
             let area = frame.area();
-            frame.render_widget(Paragraph::new(message).white(), area)
+            frame.render_widget(
+                Paragraph::new(message).fg(self.settings.theme.borrow().text),
+                area,
+            )
         })?;
         Ok(())
     }
 
-    fn read_menu_input(&mut self) -> Result<()> {
+    fn read_menu_input(&mut self, events: &mut dyn EventSource) -> Result<()> {
         loop {
-            let event = read()?;
-            if should_quit(&event) {
+            let event =
+                mouse::click_to_key(events.read()?, self.help_area, MENU_ACTIONS, &self.keymap);
+            if is_resize(&event) {
+                self.resized = true;
+                break;
+            } else if self.keymap.matches(Action::Quit, &event) {
                 self.menu_opt = MenuOpt::Quit;
                 break;
             } else if should_force_quit(&event) {
                 self.kill_execution = true;
                 break;
-            } else if should_play(&event) {
+            } else if self.keymap.matches(Action::Play, &event) {
                 self.menu_opt = MenuOpt::Play;
                 break;
-            } else if should_help(&event) {
+            } else if self.keymap.matches(Action::Continue, &event) {
+                self.menu_opt = MenuOpt::Continue;
+                break;
+            } else if self.keymap.matches(Action::Help, &event) {
                 self.menu_opt = MenuOpt::Help;
                 break;
+            } else if self.keymap.matches(Action::Leaderboard, &event) {
+                self.menu_opt = MenuOpt::Leaderboard;
+                break;
             }
         }
         Ok(())
     }
 
-    fn read_play_input(&mut self) -> Result<()> {
-        if poll(Duration::from_millis(50))? {
-            let event = read()?;
-            if should_move_left(&event) {
-                self.play_opt = PlayOpt::Left;
-            } else if should_move_right(&event) {
-                self.play_opt = PlayOpt::Right;
-            } else if should_move_down(&event) {
-                self.play_opt = PlayOpt::SoftDrop;
-            } else if should_move_up(&event) {
-                self.play_opt = PlayOpt::Rotate;
-            } else if should_pause(&event) {
-                self.play_opt = PlayOpt::Pause;
-            } else if should_quit(&event) {
-                self.play_opt = PlayOpt::Quit;
-            } else if should_force_quit(&event) {
-                self.kill_execution = true;
-            } else if matches!(
-                event,
-                Event::Key(KeyEvent {
-                    code: KeyCode::Char(' '),
-                    modifiers: KeyModifiers::NONE,
-                    kind: KeyEventKind::Press,
-                    ..
-                })
-            ) {
-                self.play_opt = PlayOpt::HardDrop;
-            }
-        } else {
-            self.play_opt = PlayOpt::None;
+    fn read_play_input(&mut self, events: &mut dyn EventSource) -> Result<()> {
+        let event = events.read()?;
+        if self.keymap.matches(Action::MoveLeft, &event) {
+            self.play_opt = PlayOpt::Left;
+        } else if self.keymap.matches(Action::MoveRight, &event) {
+            self.play_opt = PlayOpt::Right;
+        } else if self.keymap.matches(Action::SoftDrop, &event) {
+            self.play_opt = PlayOpt::SoftDrop;
+        } else if self.keymap.matches(Action::Rotate, &event) {
+            self.play_opt = PlayOpt::Rotate;
+        } else if self.keymap.matches(Action::Pause, &event) {
+            self.play_opt = PlayOpt::Pause;
+        } else if self.keymap.matches(Action::Quit, &event) {
+            self.play_opt = PlayOpt::Quit;
+        } else if should_force_quit(&event) {
+            self.kill_execution = true;
+        } else if self.keymap.matches(Action::HardDrop, &event) {
+            self.play_opt = PlayOpt::HardDrop;
         }
         Ok(())
     }
@@ -1,13 +1,15 @@
-use ratatui::style::{Color, Style};
+use ratatui::style::Style;
 use ratatui::text::{Line, Span};
 
+use super::super::{glyphs::Glyphs, theme::Theme};
 use super::bricks::Brick;
-use super::game_manager::Coord;
+use super::game_manager::{new_rng, serde_rng, serde_seed, Coord, GameRng};
+use serde::{Deserialize, Serialize};
 
 const NUMBER_OF_LINES: usize = 20;
 const NUMBER_OF_COLUMNS: usize = 10;
 
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, Serialize, Deserialize)]
 pub enum BoardPossibilities {
     Orange,
     Blue,
@@ -19,6 +21,7 @@ pub enum BoardPossibilities {
     Empty,
 }
 
+#[derive(Clone, Serialize, Deserialize)]
 pub struct Board {
     board: Vec<Vec<BoardPossibilities>>,
     lost: bool,
@@ -27,20 +30,33 @@ pub struct Board {
     brick: Brick,
     next_brick: Brick,
     brick_coord: Coord,
+    #[serde(with = "serde_seed")]
+    seed: u64,
+    #[serde(with = "serde_rng")]
+    rng: GameRng,
 }
+impl Default for Board {
+    fn default() -> Self {
+        Self::new(rand::random())
+    }
+}
+
 impl Board {
-    pub fn new() -> Self {
+    pub fn new(seed: u64) -> Self {
+        let mut rng = new_rng(seed);
         let mut board = Self {
             board: Vec::with_capacity(NUMBER_OF_LINES),
             lost: false,
             score: 0,
             lines: 0,
-            brick: Brick::new(),
-            next_brick: Brick::new(),
+            brick: Brick::new(&mut rng),
+            next_brick: Brick::new(&mut rng),
             brick_coord: Coord {
                 x: NUMBER_OF_COLUMNS / 2,
                 y: 0,
             },
+            seed,
+            rng,
         };
         let mut line: Vec<BoardPossibilities> = Vec::with_capacity(NUMBER_OF_COLUMNS);
         for _counter in 0..NUMBER_OF_COLUMNS {
@@ -66,7 +82,7 @@ impl Board {
         }
     }
 
-    pub fn drop(&mut self) {
+    pub fn step_down(&mut self) {
         if self.fit(self.brick_coord.y as i8, self.brick_coord.x as i8 - 2) {
             self.brick_coord.y += 1;
         } else {
@@ -74,7 +90,7 @@ impl Board {
             self.remove_completed_lines();
             self.check_for_lost();
             self.brick = self.next_brick.clone();
-            self.next_brick = Brick::new();
+            self.next_brick = Brick::new(&mut self.rng);
             self.brick_coord.x = NUMBER_OF_COLUMNS / 2;
             self.brick_coord.y = 0;
         }
@@ -82,14 +98,14 @@ impl Board {
 
     pub fn soft_drop(&mut self) {
         for _ in 0..2 {
-            self.drop();
+            self.step_down();
             self.score += 1;
         }
     }
 
     pub fn hard_drop(&mut self) {
         while self.fit(self.brick_coord.y as i8, self.brick_coord.x as i8 - 2) {
-            self.drop();
+            self.step_down();
             self.score += 2;
         }
     }
@@ -105,7 +121,13 @@ impl Board {
         self.lost
     }
 
-    pub fn reset_board(&mut self) {
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    pub fn reset_board(&mut self, seed: u64) {
+        self.seed = seed;
+        self.rng = new_rng(seed);
         for line in self.board.iter_mut() {
             for column in line.iter_mut() {
                 *column = BoardPossibilities::Empty;
@@ -114,8 +136,8 @@ impl Board {
         self.score = 0;
         self.lines = 0;
         self.lost = false;
-        self.brick = self.next_brick.clone();
-        self.next_brick = Brick::new();
+        self.brick = Brick::new(&mut self.rng);
+        self.next_brick = Brick::new(&mut self.rng);
         self.brick_coord = Coord {
             x: NUMBER_OF_COLUMNS / 2,
             y: 0,
@@ -145,84 +167,54 @@ impl Board {
         self.score
     }
 
-    pub fn display_board(&self, message: String) -> Vec<Line<'_>> {
+    pub fn display_board(&self, message: String, theme: &Theme, glyphs: &Glyphs) -> Vec<Line<'_>> {
         let mut lines: Vec<Line> = Vec::new();
         lines.push(Line::from(message));
         for line in 0..NUMBER_OF_LINES {
             let mut spans: Vec<Span> = Vec::new();
-            spans.push(Span::styled("│", Style::default().fg(Color::DarkGray)));
+            spans.push(Span::styled(
+                glyphs.line.vertical,
+                Style::default().fg(theme.dim),
+            ));
             for column in 0..NUMBER_OF_COLUMNS {
-                match self.consult(line, column) {
-                    BoardPossibilities::Red => {
-                        spans.push(Span::styled("██", Style::default().fg(Color::Red)));
-                    }
-                    BoardPossibilities::Green => {
-                        spans.push(Span::styled("██", Style::default().fg(Color::Green)));
-                    }
-                    BoardPossibilities::Blue => {
-                        spans.push(Span::styled("██", Style::default().fg(Color::Blue)));
-                    }
-                    BoardPossibilities::Cyan => {
-                        spans.push(Span::styled("██", Style::default().fg(Color::Cyan)));
-                    }
-                    BoardPossibilities::Pink => {
-                        spans.push(Span::styled("██", Style::default().fg(Color::LightMagenta)));
-                    }
-                    BoardPossibilities::Orange => {
-                        spans.push(Span::styled("██", Style::default().fg(Color::Magenta)));
-                    }
-                    BoardPossibilities::Yellow => {
-                        spans.push(Span::styled("██", Style::default().fg(Color::Yellow)));
-                    }
-                    BoardPossibilities::Empty => {
-                        spans.push(Span::styled("  ", Style::default()));
-                    }
-                }
+                spans.push(Self::brick_span(*self.consult(line, column), theme, glyphs));
             }
-            spans.push(Span::styled("│", Style::default().fg(Color::DarkGray)));
+            spans.push(Span::styled(
+                glyphs.line.vertical,
+                Style::default().fg(theme.dim),
+            ));
             lines.push(Line::from(spans));
         }
         let mut spans: Vec<Span> = Vec::new();
-        spans.push(Span::styled("╰", Style::default().fg(Color::DarkGray)));
+        spans.push(Span::styled(
+            glyphs.line.bottom_left,
+            Style::default().fg(theme.dim),
+        ));
         for _counter in 1..NUMBER_OF_COLUMNS + 1 {
-            spans.push(Span::styled("──", Style::default().fg(Color::DarkGray)));
+            spans.push(Span::styled(
+                glyphs.line.horizontal.repeat(2),
+                Style::default().fg(theme.dim),
+            ));
         }
-        spans.push(Span::styled("╯", Style::default().fg(Color::DarkGray)));
+        spans.push(Span::styled(
+            glyphs.line.bottom_right,
+            Style::default().fg(theme.dim),
+        ));
         lines.push(Line::from(spans));
         lines
     }
 
-    pub fn display_next_brick(&self) -> Vec<Line<'_>> {
+    pub fn display_next_brick(&self, theme: &Theme, glyphs: &Glyphs) -> Vec<Line<'_>> {
         let mut lines: Vec<Line> = Vec::new();
         for i in 0..4 {
             let mut spans: Vec<Span> = Vec::new();
             for j in 0..4 {
                 if self.next_brick.consult(i, j) {
-                    match self.next_brick.consult_color() {
-                        BoardPossibilities::Red => {
-                            spans.push(Span::styled("██", Style::default().fg(Color::Red)));
-                        }
-                        BoardPossibilities::Green => {
-                            spans.push(Span::styled("██", Style::default().fg(Color::Green)));
-                        }
-                        BoardPossibilities::Blue => {
-                            spans.push(Span::styled("██", Style::default().fg(Color::Blue)));
-                        }
-                        BoardPossibilities::Cyan => {
-                            spans.push(Span::styled("██", Style::default().fg(Color::Cyan)));
-                        }
-                        BoardPossibilities::Pink => {
-                            spans
-                                .push(Span::styled("██", Style::default().fg(Color::LightMagenta)));
-                        }
-                        BoardPossibilities::Orange => {
-                            spans.push(Span::styled("██", Style::default().fg(Color::Magenta)));
-                        }
-                        BoardPossibilities::Yellow => {
-                            spans.push(Span::styled("██", Style::default().fg(Color::Yellow)));
-                        }
-                        BoardPossibilities::Empty => {}
-                    }
+                    spans.push(Self::brick_span(
+                        *self.next_brick.consult_color(),
+                        theme,
+                        glyphs,
+                    ));
                 } else {
                     spans.push(Span::styled("  ", Style::default()));
                 }
@@ -232,6 +224,25 @@ impl Board {
         lines
     }
 
+    fn brick_span(square: BoardPossibilities, theme: &Theme, glyphs: &Glyphs) -> Span<'static> {
+        let index = match square {
+            BoardPossibilities::Red => 0,
+            BoardPossibilities::Green => 1,
+            BoardPossibilities::Blue => 2,
+            BoardPossibilities::Cyan => 3,
+            BoardPossibilities::Pink => 4,
+            BoardPossibilities::Orange => 5,
+            BoardPossibilities::Yellow => 6,
+            BoardPossibilities::Empty => return Span::styled("  ", Style::default()),
+        };
+        let fill = if theme.patterns {
+            glyphs.bricks[index]
+        } else {
+            glyphs.square
+        };
+        Span::styled(fill, Style::default().fg(theme.bricks[index]))
+    }
+
     fn fit(&self, iy: i8, ix: i8) -> bool {
         for py in 0..4 {
             for px in 0..4 {
@@ -1,8 +1,10 @@
-use super::board::BoardPossibilities;
+use super::{board::BoardPossibilities, game_manager::GameRng};
+use rand::Rng;
+use serde::{Deserialize, Serialize};
 
 const NUMBER_OF_BRICKS: i32 = 7;
 
-#[derive(Clone)]
+#[derive(Clone, Serialize, Deserialize)]
 pub struct Brick {
     coord: Vec<bool>,
     color: BoardPossibilities,
@@ -10,8 +12,8 @@ pub struct Brick {
     number_of_rotations: i8,
 }
 impl Brick {
-    pub fn new() -> Self {
-        let random = rand::random_range(0..NUMBER_OF_BRICKS);
+    pub fn new(rng: &mut GameRng) -> Self {
+        let random = rng.random_range(0..NUMBER_OF_BRICKS);
         match random {
             0 => Self {
                 coord: Self::i_shape(),
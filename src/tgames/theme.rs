@@ -0,0 +1,567 @@
+use super::glyphs::GlyphSet;
+use ratatui::style::Color;
+use serde::{Deserialize, Serialize};
+use std::{
+    cell::RefCell,
+    fs,
+    io::{Error, ErrorKind, Result},
+    path::Path,
+    rc::Rc,
+};
+
+const THEMES_FILE_NAME: &str = "themes.toml";
+// Key of the themes config naming the theme used when none is chosen on the command line.
+const DEFAULT_THEME_KEY: &str = "theme";
+// Key of the themes config naming the glyph set, detected from the terminal when missing.
+const GLYPHS_KEY: &str = "glyphs";
+// Key of a user theme naming the built-in theme it starts from.
+const BASE_THEME_KEY: &str = "base";
+pub const DEFAULT_THEME: &str = "dark";
+
+const OKABE_ITO_ORANGE: Color = Color::Rgb(230, 159, 0);
+const OKABE_ITO_SKY_BLUE: Color = Color::Rgb(86, 180, 233);
+const OKABE_ITO_BLUISH_GREEN: Color = Color::Rgb(0, 158, 115);
+const OKABE_ITO_YELLOW: Color = Color::Rgb(240, 228, 66);
+const OKABE_ITO_BLUE: Color = Color::Rgb(0, 114, 178);
+const OKABE_ITO_VERMILLION: Color = Color::Rgb(213, 94, 0);
+const OKABE_ITO_REDDISH_PURPLE: Color = Color::Rgb(204, 121, 167);
+const TRITAN_RED: Color = Color::Rgb(230, 75, 53);
+const TRITAN_DARK_RED: Color = Color::Rgb(153, 34, 34);
+const TRITAN_PINK: Color = Color::Rgb(238, 136, 170);
+const TRITAN_TEAL: Color = Color::Rgb(0, 170, 170);
+const TRITAN_DARK_TEAL: Color = Color::Rgb(0, 102, 102);
+
+// Every color the interface and the boards use.
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Theme {
+    #[serde(skip)]
+    pub name: String,
+    pub text: Color,
+    // Borders, hints and anything else in the background.
+    pub dim: Color,
+    // Selected menu entry.
+    pub highlight: Color,
+    pub warning: Color,
+    pub success: Color,
+    pub failure: Color,
+    // First, second and third places of the leaderboards.
+    pub podium: [Color; 3],
+    pub snake_head: Color,
+    pub snake_body: Color,
+    pub snake_dead: Color,
+    pub food: Color,
+    // One per Tetris brick: red, green, blue, cyan, pink, orange and yellow.
+    pub bricks: [Color; 7],
+    // 2048 tiles from 2 upwards, starting over once they run out.
+    pub tiles: Vec<Color>,
+    // Minesweeper numbers from 1 to 8.
+    pub numbers: [Color; 8],
+    pub flag: Color,
+    pub wrong_flag: Color,
+    pub uncertain: Color,
+    pub mine: Color,
+    // Background of the mine that went off.
+    pub exploded: Color,
+    // Background of the square under the cursor.
+    pub cursor: Color,
+    pub bird: Color,
+    pub dead_bird: Color,
+    pub pipes: Color,
+    // Draws each kind of Tetris brick, the snake head and the Minesweeper cursor and exploded mine
+    // with a pattern of their own, so they can be told apart without colors.
+    pub patterns: bool,
+}
+
+pub type SharedTheme = Rc<RefCell<Theme>>;
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self::dark()
+    }
+}
+
+impl Theme {
+    pub fn dark() -> Self {
+        Self {
+            name: String::from("dark"),
+            text: Color::Reset,
+            dim: Color::DarkGray,
+            highlight: Color::Green,
+            warning: Color::Yellow,
+            success: Color::Green,
+            failure: Color::Red,
+            podium: [Color::Yellow, Color::Gray, Color::LightRed],
+            snake_head: Color::Cyan,
+            snake_body: Color::Blue,
+            snake_dead: Color::Red,
+            food: Color::LightRed,
+            bricks: [
+                Color::Red,
+                Color::Green,
+                Color::Blue,
+                Color::Cyan,
+                Color::LightMagenta,
+                Color::Magenta,
+                Color::Yellow,
+            ],
+            tiles: vec![
+                Color::Red,
+                Color::Yellow,
+                Color::Magenta,
+                Color::Blue,
+                Color::Cyan,
+                Color::Green,
+            ],
+            numbers: [
+                Color::LightBlue,
+                Color::LightGreen,
+                Color::LightRed,
+                Color::Blue,
+                Color::Red,
+                Color::Yellow,
+                Color::Magenta,
+                Color::Magenta,
+            ],
+            flag: Color::Red,
+            wrong_flag: Color::DarkGray,
+            uncertain: Color::Magenta,
+            mine: Color::LightRed,
+            exploded: Color::LightRed,
+            cursor: Color::Gray,
+            bird: Color::Blue,
+            dead_bird: Color::Red,
+            pipes: Color::Green,
+            patterns: false,
+        }
+    }
+
+    // For terminals with a light background, yellow and cyan are too faint there.
+    pub fn light() -> Self {
+        Self {
+            name: String::from("light"),
+            text: Color::Black,
+            dim: Color::DarkGray,
+            highlight: Color::Blue,
+            warning: Color::Rgb(175, 95, 0),
+            success: Color::Green,
+            failure: Color::Red,
+            podium: [
+                Color::Rgb(175, 135, 0),
+                Color::DarkGray,
+                Color::Rgb(175, 95, 0),
+            ],
+            snake_head: Color::Rgb(0, 95, 135),
+            snake_body: Color::Blue,
+            snake_dead: Color::Red,
+            food: Color::Red,
+            bricks: [
+                Color::Red,
+                Color::Green,
+                Color::Blue,
+                Color::Rgb(0, 135, 135),
+                Color::Magenta,
+                Color::Rgb(215, 95, 0),
+                Color::Rgb(175, 135, 0),
+            ],
+            tiles: vec![
+                Color::Red,
+                Color::Rgb(175, 135, 0),
+                Color::Magenta,
+                Color::Blue,
+                Color::Rgb(0, 135, 135),
+                Color::Green,
+            ],
+            numbers: [
+                Color::Blue,
+                Color::Green,
+                Color::Red,
+                Color::Rgb(0, 0, 135),
+                Color::Rgb(135, 0, 0),
+                Color::Rgb(0, 135, 135),
+                Color::Black,
+                Color::DarkGray,
+            ],
+            flag: Color::Red,
+            wrong_flag: Color::DarkGray,
+            uncertain: Color::Magenta,
+            mine: Color::Red,
+            exploded: Color::LightRed,
+            cursor: Color::Rgb(188, 188, 188),
+            bird: Color::Blue,
+            dead_bird: Color::Red,
+            pipes: Color::Green,
+            patterns: false,
+        }
+    }
+
+    // Only the bright colors, on dark backgrounds.
+    pub fn high_contrast() -> Self {
+        Self {
+            name: String::from("high-contrast"),
+            text: Color::White,
+            dim: Color::Gray,
+            highlight: Color::LightYellow,
+            warning: Color::LightYellow,
+            success: Color::LightGreen,
+            failure: Color::LightRed,
+            podium: [Color::LightYellow, Color::White, Color::LightRed],
+            snake_head: Color::White,
+            snake_body: Color::LightBlue,
+            snake_dead: Color::LightRed,
+            food: Color::LightYellow,
+            bricks: [
+                Color::LightRed,
+                Color::LightGreen,
+                Color::LightBlue,
+                Color::LightCyan,
+                Color::LightMagenta,
+                Color::White,
+                Color::LightYellow,
+            ],
+            tiles: vec![
+                Color::White,
+                Color::LightYellow,
+                Color::LightMagenta,
+                Color::LightBlue,
+                Color::LightCyan,
+                Color::LightGreen,
+                Color::LightRed,
+            ],
+            numbers: [
+                Color::LightCyan,
+                Color::LightGreen,
+                Color::LightRed,
+                Color::LightBlue,
+                Color::LightYellow,
+                Color::LightMagenta,
+                Color::White,
+                Color::White,
+            ],
+            flag: Color::LightRed,
+            wrong_flag: Color::Gray,
+            uncertain: Color::LightMagenta,
+            mine: Color::LightRed,
+            exploded: Color::LightRed,
+            cursor: Color::White,
+            bird: Color::LightYellow,
+            dead_bird: Color::LightRed,
+            pipes: Color::LightGreen,
+            patterns: false,
+        }
+    }
+
+    // No colors at all, only shades of gray.
+    pub fn monochrome() -> Self {
+        Self {
+            name: String::from("monochrome"),
+            text: Color::Reset,
+            dim: Color::DarkGray,
+            highlight: Color::White,
+            warning: Color::White,
+            success: Color::White,
+            failure: Color::White,
+            podium: [Color::White, Color::Gray, Color::Gray],
+            snake_head: Color::White,
+            snake_body: Color::Gray,
+            snake_dead: Color::White,
+            food: Color::White,
+            bricks: [Color::Gray; 7],
+            tiles: vec![Color::Gray, Color::White],
+            numbers: [Color::Reset; 8],
+            flag: Color::White,
+            wrong_flag: Color::DarkGray,
+            uncertain: Color::Gray,
+            mine: Color::White,
+            exploded: Color::Gray,
+            cursor: Color::Gray,
+            bird: Color::White,
+            dead_bird: Color::White,
+            pipes: Color::Gray,
+            patterns: true,
+        }
+    }
+
+    // The colorblind themes use the Okabe-Ito palette, without the pairs each kind of colorblindness
+    // confuses, and draw the pieces with patterns as well.
+    // Red and green look alike, so blue and orange take their place.
+    pub fn deuteranopia() -> Self {
+        Self {
+            name: String::from("deuteranopia"),
+            highlight: OKABE_ITO_SKY_BLUE,
+            warning: OKABE_ITO_YELLOW,
+            success: OKABE_ITO_SKY_BLUE,
+            failure: OKABE_ITO_VERMILLION,
+            podium: [OKABE_ITO_YELLOW, Color::Gray, OKABE_ITO_ORANGE],
+            snake_head: OKABE_ITO_YELLOW,
+            snake_body: OKABE_ITO_BLUE,
+            snake_dead: OKABE_ITO_VERMILLION,
+            food: OKABE_ITO_ORANGE,
+            bricks: [
+                OKABE_ITO_VERMILLION,
+                OKABE_ITO_BLUISH_GREEN,
+                OKABE_ITO_BLUE,
+                OKABE_ITO_SKY_BLUE,
+                OKABE_ITO_REDDISH_PURPLE,
+                OKABE_ITO_ORANGE,
+                OKABE_ITO_YELLOW,
+            ],
+            tiles: vec![
+                OKABE_ITO_SKY_BLUE,
+                OKABE_ITO_ORANGE,
+                OKABE_ITO_BLUE,
+                OKABE_ITO_YELLOW,
+                OKABE_ITO_REDDISH_PURPLE,
+                Color::White,
+            ],
+            numbers: [
+                OKABE_ITO_SKY_BLUE,
+                OKABE_ITO_YELLOW,
+                OKABE_ITO_ORANGE,
+                OKABE_ITO_BLUE,
+                OKABE_ITO_VERMILLION,
+                OKABE_ITO_REDDISH_PURPLE,
+                Color::White,
+                Color::Gray,
+            ],
+            flag: OKABE_ITO_ORANGE,
+            uncertain: OKABE_ITO_SKY_BLUE,
+            mine: OKABE_ITO_VERMILLION,
+            exploded: OKABE_ITO_ORANGE,
+            bird: OKABE_ITO_YELLOW,
+            dead_bird: OKABE_ITO_VERMILLION,
+            pipes: OKABE_ITO_BLUE,
+            patterns: true,
+            ..Self::dark()
+        }
+    }
+
+    // Like deuteranopia, but reds also look darker, so the bright orange is preferred to vermillion.
+    pub fn protanopia() -> Self {
+        Self {
+            name: String::from("protanopia"),
+            failure: OKABE_ITO_ORANGE,
+            snake_dead: OKABE_ITO_ORANGE,
+            food: OKABE_ITO_YELLOW,
+            snake_head: Color::White,
+            mine: OKABE_ITO_ORANGE,
+            exploded: OKABE_ITO_YELLOW,
+            dead_bird: OKABE_ITO_ORANGE,
+            ..Self::deuteranopia()
+        }
+    }
+
+    // Blue and green, and yellow and violet, look alike, so reds and teals carry the meaning.
+    pub fn tritanopia() -> Self {
+        Self {
+            name: String::from("tritanopia"),
+            highlight: TRITAN_TEAL,
+            warning: TRITAN_PINK,
+            success: TRITAN_TEAL,
+            failure: TRITAN_RED,
+            podium: [TRITAN_RED, Color::Gray, TRITAN_PINK],
+            snake_head: Color::White,
+            snake_body: TRITAN_TEAL,
+            snake_dead: TRITAN_RED,
+            food: TRITAN_PINK,
+            bricks: [
+                TRITAN_RED,
+                TRITAN_TEAL,
+                TRITAN_DARK_TEAL,
+                Color::White,
+                TRITAN_PINK,
+                TRITAN_DARK_RED,
+                Color::Gray,
+            ],
+            tiles: vec![
+                TRITAN_TEAL,
+                TRITAN_RED,
+                Color::White,
+                TRITAN_PINK,
+                Color::Gray,
+            ],
+            numbers: [
+                TRITAN_TEAL,
+                TRITAN_RED,
+                TRITAN_PINK,
+                Color::White,
+                TRITAN_DARK_RED,
+                TRITAN_DARK_TEAL,
+                Color::Gray,
+                Color::Gray,
+            ],
+            flag: TRITAN_RED,
+            uncertain: TRITAN_PINK,
+            mine: TRITAN_RED,
+            exploded: TRITAN_PINK,
+            bird: Color::White,
+            dead_bird: TRITAN_RED,
+            pipes: TRITAN_TEAL,
+            patterns: true,
+            ..Self::dark()
+        }
+    }
+
+    // Color of a 2048 tile, nothing for the empty ones.
+    pub fn tile(&self, value: u32) -> Color {
+        if value < 2 || self.tiles.is_empty() {
+            return Color::Reset;
+        }
+        self.tiles[(value.ilog2() as usize - 1) % self.tiles.len()]
+    }
+}
+
+// The built-in themes followed by the user ones, from `themes.toml` in the config directory.
+// Each table of that file is a theme starting from a built-in one, `base = "light"` or the dark
+// theme by default, and overriding some of its colors:
+//
+//     theme = "solarized"
+//     glyphs = "unicode"
+//
+//     [solarized]
+//     base = "dark"
+//     highlight = "#b58900"
+//     bricks = ["#dc322f", "#859900", "#268bd2", "#2aa198", "#d33682", "#cb4b16", "#b58900"]
+#[derive(Clone)]
+pub struct Themes {
+    themes: Vec<Theme>,
+    default: String,
+    glyphs: Option<GlyphSet>,
+}
+
+impl Default for Themes {
+    fn default() -> Self {
+        Self {
+            themes: vec![
+                Theme::dark(),
+                Theme::light(),
+                Theme::high_contrast(),
+                Theme::monochrome(),
+                Theme::deuteranopia(),
+                Theme::protanopia(),
+                Theme::tritanopia(),
+            ],
+            default: String::from(DEFAULT_THEME),
+            glyphs: None,
+        }
+    }
+}
+
+impl Themes {
+    // A missing file means only the built-in themes.
+    pub fn load() -> Result<Self> {
+        match dirs::config_dir() {
+            Some(directory) => Self::from_file(&directory.join("tgames").join(THEMES_FILE_NAME)),
+            None => Ok(Self::default()),
+        }
+    }
+
+    pub fn from_file(path: &Path) -> Result<Self> {
+        let content = match fs::read_to_string(path) {
+            Ok(content) => content,
+            Err(error) if error.kind() == ErrorKind::NotFound => return Ok(Self::default()),
+            Err(error) => return Err(error),
+        };
+        Self::from_toml(&content).map_err(|error| {
+            Error::new(
+                ErrorKind::InvalidData,
+                format!("{}: {error}", path.display()),
+            )
+        })
+    }
+
+    pub fn from_toml(content: &str) -> Result<Self> {
+        let invalid = |message: String| Error::new(ErrorKind::InvalidData, message);
+        let mut themes = Self::default();
+        let builtin = themes.themes.clone();
+        let mut table: toml::Table =
+            toml::from_str(content).map_err(|error| invalid(error.to_string()))?;
+        let default = match table.remove(DEFAULT_THEME_KEY) {
+            Some(toml::Value::String(name)) => Some(name),
+            Some(_) => return Err(invalid(format!("\"{DEFAULT_THEME_KEY}\" must be a name"))),
+            None => None,
+        };
+        themes.glyphs = match table.remove(GLYPHS_KEY) {
+            Some(toml::Value::String(name)) => {
+                Some(GlyphSet::from_name(&name).map_err(|error| invalid(error.to_string()))?)
+            }
+            Some(_) => return Err(invalid(format!("\"{GLYPHS_KEY}\" must be a name"))),
+            None => None,
+        };
+        for (name, value) in table {
+            let toml::Value::Table(mut colors) = value else {
+                return Err(invalid(format!("theme \"{name}\" must be a table")));
+            };
+            let base_name = match colors.remove(BASE_THEME_KEY) {
+                Some(toml::Value::String(base)) => base,
+                Some(_) => return Err(invalid(format!("the base of \"{name}\" must be a name"))),
+                None => String::from(DEFAULT_THEME),
+            };
+            let base = builtin
+                .iter()
+                .find(|theme| theme.name == base_name)
+                .ok_or_else(|| {
+                    invalid(format!(
+                        "unknown base theme \"{base_name}\" for \"{name}\", expected one of: {}",
+                        Self::default().names().join(", ")
+                    ))
+                })?;
+            let mut merged =
+                toml::Table::try_from(base).map_err(|error| invalid(error.to_string()))?;
+            merged.extend(colors);
+            let mut theme: Theme = merged
+                .try_into()
+                .map_err(|error| invalid(format!("in theme \"{name}\": {error}")))?;
+            theme.name = name.clone();
+            themes.themes.retain(|theme| theme.name != name);
+            themes.themes.push(theme);
+        }
+        if let Some(default) = default {
+            themes.get(&default)?;
+            themes.default = default;
+        }
+        Ok(themes)
+    }
+
+    pub fn names(&self) -> Vec<&str> {
+        self.themes
+            .iter()
+            .map(|theme| theme.name.as_str())
+            .collect()
+    }
+
+    pub fn get(&self, name: &str) -> Result<&Theme> {
+        self.themes
+            .iter()
+            .find(|theme| theme.name == name)
+            .ok_or_else(|| {
+                Error::new(
+                    ErrorKind::InvalidInput,
+                    format!(
+                        "unknown theme \"{name}\", expected one of: {}",
+                        self.names().join(", ")
+                    ),
+                )
+            })
+    }
+
+    // Theme chosen in the config, or the dark one.
+    pub fn default_theme(&self) -> &Theme {
+        self.get(&self.default).unwrap_or(&self.themes[0])
+    }
+
+    // Glyph set chosen in the config, if any.
+    pub fn glyph_set(&self) -> Option<GlyphSet> {
+        self.glyphs
+    }
+
+    // Theme after `name`, going back to the first one after the last.
+    pub fn next(&self, name: &str) -> &Theme {
+        let index = self
+            .themes
+            .iter()
+            .position(|theme| theme.name == name)
+            .map_or(0, |index| (index + 1) % self.themes.len());
+        &self.themes[index]
+    }
+}
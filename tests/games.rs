@@ -0,0 +1,285 @@
+use ratatui::{backend::TestBackend, buffer::Buffer, Terminal};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use tgames::{
+    flappy_bird_gm::FlappyBirdGameManager,
+    g2048_gm::G2048GameManager,
+    game_manager::{Difficult, FrameStats, GameManager, GameOptions, GameSettings},
+    input::{
+        event_source::{parse_event, ScriptedEventSource},
+        replay::{RecordingEventSource, ReplayEventSource},
+    },
+    minesweeper_gm::MinesweeperGameManager,
+    records::RecordsStore,
+    snake_gm::SnakeGameManager,
+    tetris_gm::TetrisGameManager,
+    Games,
+};
+
+fn settings(seed: u64) -> GameSettings {
+    GameSettings {
+        seed: Some(seed),
+        ..GameSettings::default()
+    }
+}
+
+// Runs the script until it runs out and the game is quit. Frames are skipped when events come
+// in faster than the frame rate, so scripts end with an idle or a resize to draw the last screen.
+fn play(game: &mut dyn GameManager<TestBackend>, script: &[&str]) -> Buffer {
+    let mut terminal = Terminal::new(TestBackend::new(160, 50)).unwrap();
+    let mut events = ScriptedEventSource::new(script.iter().map(|line| match *line {
+        "idle" => None,
+        line => Some(parse_event(line).unwrap()),
+    }));
+    game.run(&mut terminal, &mut events, &mut FrameStats::default())
+        .unwrap();
+    terminal.backend().buffer().clone()
+}
+
+fn screen(buffer: &Buffer) -> String {
+    let area = buffer.area;
+    (0..area.height)
+        .map(|row| {
+            (0..area.width)
+                .map(|column| buffer[(column, row)].symbol())
+                .collect::<String>()
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+#[test]
+fn snake_shows_board_and_controls() {
+    let mut game = SnakeGameManager::new(RecordsStore::in_memory().shared(), settings(7));
+    let screen = screen(&play(&mut game, &["d", "idle"]));
+    assert!(screen.contains("Game board"));
+    assert!(screen.contains("Move up"));
+    assert!(screen.contains("Seed 7"));
+}
+
+#[test]
+fn tetris_pauses() {
+    let mut game = TetrisGameManager::new(RecordsStore::in_memory().shared(), settings(7));
+    let screen = screen(&play(&mut game, &["idle", "p", "resize 160 50"]));
+    assert!(screen.contains("Game is paused. Press enter to continue."));
+}
+
+#[test]
+fn g2048_shows_board_and_controls() {
+    let mut game = G2048GameManager::new(RecordsStore::in_memory().shared(), settings(7));
+    let screen = screen(&play(&mut game, &["left"]));
+    assert!(screen.contains("Game board"));
+    assert!(screen.contains("Move left"));
+}
+
+#[test]
+fn minesweeper_reveals_numbers() {
+    let mut game = MinesweeperGameManager::new(RecordsStore::in_memory().shared(), settings(7));
+    let screen = screen(&play(&mut game, &["r"]));
+    assert!(screen.contains("Reveal square"));
+    assert!(screen.chars().any(|symbol| ('1'..='8').contains(&symbol)));
+}
+
+#[test]
+fn minesweeper_wins_with_hints_are_marked() {
+    // The first reveal opens every square but the mine, which the hint then points at.
+    let settings = GameSettings {
+        options: GameOptions {
+            difficult: Some(Difficult::Custom {
+                height: 5,
+                width: 5,
+                mines: 1,
+            }),
+            ..GameOptions::default()
+        },
+        ..settings(7)
+    };
+    let records = RecordsStore::in_memory().shared();
+    let mut game = MinesweeperGameManager::new(records.clone(), settings);
+    play(&mut game, &["r", "?", "m", "a", "enter", "idle"]);
+    let records = records.borrow();
+    let leaderboard = records.leaderboard(&Games::Minesweeper, "Custom-5x5-1");
+    assert_eq!(leaderboard.len(), 1);
+    assert!(leaderboard[0].hinted);
+}
+
+#[test]
+fn flappy_bird_shows_score_and_controls() {
+    let mut game = FlappyBirdGameManager::new(RecordsStore::in_memory().shared(), settings(7));
+    let screen = screen(&play(&mut game, &["space", "idle"]));
+    assert!(screen.contains("Score"));
+    assert!(screen.contains("Jump"));
+}
+
+// Two sessions with the same seed and input draw the same screens.
+fn assert_repeatable<G: GameManager<TestBackend>>(new: impl Fn() -> G, script: &[&str]) {
+    let first = play(&mut new(), script);
+    let second = play(&mut new(), script);
+    assert_eq!(screen(&first), screen(&second));
+    assert_eq!(first, second);
+}
+
+#[test]
+fn snake_is_repeatable_with_a_seed() {
+    assert_repeatable(
+        || SnakeGameManager::new(RecordsStore::in_memory().shared(), settings(3)),
+        &["d", "idle", "idle", "s", "idle", "idle", "a", "idle"],
+    );
+}
+
+#[test]
+fn tetris_is_repeatable_with_a_seed() {
+    assert_repeatable(
+        || TetrisGameManager::new(RecordsStore::in_memory().shared(), settings(3)),
+        &[
+            "idle", "space", "idle", "a", "space", "idle", "w", "space", "idle",
+        ],
+    );
+}
+
+#[test]
+fn g2048_is_repeatable_with_a_seed() {
+    assert_repeatable(
+        || G2048GameManager::new(RecordsStore::in_memory().shared(), settings(3)),
+        &["left", "up", "right", "down", "left", "up"],
+    );
+}
+
+#[test]
+fn minesweeper_is_repeatable_with_a_seed() {
+    // The clock goes by the real time, so the time and the 3BV/s are left out.
+    let without_clock = |buffer: &Buffer| {
+        screen(buffer)
+            .lines()
+            .map(|row| match row.find("Time").or_else(|| row.find("3BV/s")) {
+                Some(clock) => &row[..clock],
+                None => row,
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    };
+    let new = || MinesweeperGameManager::new(RecordsStore::in_memory().shared(), settings(3));
+    let script = ["r", "right", "right", "r", "down", "m"];
+    let first = play(&mut new(), &script);
+    let second = play(&mut new(), &script);
+    assert_eq!(without_clock(&first), without_clock(&second));
+}
+
+#[test]
+fn flappy_bird_is_repeatable_with_a_seed() {
+    assert_repeatable(
+        || FlappyBirdGameManager::new(RecordsStore::in_memory().shared(), settings(3)),
+        &["space", "idle", "idle", "space", "idle", "idle", "idle"],
+    );
+}
+
+#[test]
+fn minesweeper_boards_change_with_the_seed() {
+    let first = play(
+        &mut MinesweeperGameManager::new(RecordsStore::in_memory().shared(), settings(3)),
+        &["r"],
+    );
+    let second = play(
+        &mut MinesweeperGameManager::new(RecordsStore::in_memory().shared(), settings(4)),
+        &["r"],
+    );
+    // Only the boards should differ, not the seed shown under them.
+    assert_ne!(screen(&first), screen(&second).replace("Seed 4", "Seed 3"));
+}
+
+// Replays check the size against the one recorded, whatever the size of the terminal. Each one
+// gets a file of its own, tests run at the same time.
+fn replay(columns: u16, rows: u16, recording: &str) -> String {
+    static REPLAYS: AtomicUsize = AtomicUsize::new(0);
+    let number = REPLAYS.fetch_add(1, Ordering::Relaxed);
+    let name = format!("tgames-replay-{}-{number}.txt", std::process::id());
+    let path = std::env::temp_dir().join(name);
+    std::fs::write(&path, recording).unwrap();
+    let mut events = ReplayEventSource::load(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    let mut terminal = Terminal::new(TestBackend::new(columns, rows)).unwrap();
+    let settings = GameSettings {
+        keybindings: events.keybindings().clone(),
+        ..settings(3)
+    };
+    let mut game = G2048GameManager::new(RecordsStore::in_memory().shared(), settings);
+    game.run(&mut terminal, &mut events, &mut FrameStats::default())
+        .unwrap();
+    screen(terminal.backend().buffer())
+}
+
+#[test]
+fn replays_fit_the_recorded_terminal() {
+    let recording = "# tgames recording\nseed 3\nsize 160 50\n0 left\n0 up\n";
+    assert!(!replay(50, 12, recording).contains("Terminal too small"));
+    let recording = "# tgames recording\nseed 3\nsize 50 12\n0 left\n0 up\n";
+    assert!(replay(160, 50, recording).contains("have 50x12"));
+}
+
+#[test]
+fn recordings_keep_the_game_options() {
+    let board = "# Minesweeper board, 2x3 with 2 mines\n*..\n.*.\n";
+    let settings = GameSettings {
+        options: GameOptions {
+            difficult: Some(Difficult::Custom {
+                height: 2,
+                width: 3,
+                mines: 2,
+            }),
+            board_size: Some((10, 30)),
+            auto_open: true,
+            no_guess: true,
+            board: Some(String::from(board)),
+            fps: Some(30),
+        },
+        ..settings(3)
+    };
+    let path = std::env::temp_dir().join(format!("tgames-options-{}.txt", std::process::id()));
+    let events = Box::new(ScriptedEventSource::new([]));
+    drop(
+        RecordingEventSource::create(&path, events, 3, Some("minesweeper"), None, &settings)
+            .unwrap(),
+    );
+    let replay = ReplayEventSource::load(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    let options = replay.options().unwrap();
+    assert!(matches!(
+        options.difficult,
+        Some(Difficult::Custom {
+            height: 2,
+            width: 3,
+            mines: 2
+        })
+    ));
+    assert_eq!(options.board_size, Some((10, 30)));
+    assert!(options.auto_open && options.no_guess);
+    assert_eq!(options.board.as_deref(), Some(board));
+    assert_eq!(options.fps, Some(30));
+}
+
+#[test]
+fn replays_keep_the_recorded_keybindings() {
+    let recorded = "# tgames recording\nseed 3\nkeys [2048]\nkeys move_left = [\"z\"]\n0 z\n0 up\n";
+    let default = "# tgames recording\nseed 3\n0 left\n0 up\n";
+    // Only the help tells them apart, it shows the key the recording moved left with.
+    let board = |screen: String| -> Vec<String> {
+        screen
+            .lines()
+            .map(|line| line.chars().take(80).collect())
+            .collect()
+    };
+    let recorded = replay(160, 50, recorded);
+    assert!(recorded.contains("z           - Move left"));
+    assert_eq!(board(recorded), board(replay(160, 50, default)));
+}
+
+#[test]
+fn frames_are_measured() {
+    let mut terminal = Terminal::new(TestBackend::new(160, 50)).unwrap();
+    let mut events = ScriptedEventSource::new([None, None, None]);
+    let mut frames = FrameStats::default();
+    SnakeGameManager::new(RecordsStore::in_memory().shared(), settings(7))
+        .run(&mut terminal, &mut events, &mut frames)
+        .unwrap();
+    assert!(frames.frames() >= 3);
+    assert!(frames.report().contains("frames in"));
+}